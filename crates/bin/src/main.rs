@@ -47,16 +47,52 @@ struct Args {
 
     #[arg(long)]
     context: Option<std::path::PathBuf>,
+
+    /// Directory to read partials from, for `include` and `render` [default: the input's directory]
+    #[arg(long)]
+    partials: Option<std::path::PathBuf>,
+
+    /// Print the template's lint findings as JSON instead of rendering it
+    #[arg(long)]
+    lint: bool,
+
+    /// Write the template's source, formatted, instead of rendering it
+    #[arg(long, conflicts_with = "lint")]
+    format: bool,
 }
 
 fn run() -> Result<i32, Box<dyn std::error::Error>> {
     let args = Args::parse();
 
+    let partials = match &args.partials {
+        Some(partials) => partials.clone(),
+        None => match args.input.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_owned(),
+            _ => path::PathBuf::from("."),
+        },
+    };
+    let partials = liquid::partials::FileSystemSource::new(partials);
     let parser = liquid::ParserBuilder::with_stdlib()
-        .build()
-        .expect("should succeed without partials");
+        .partials(liquid::partials::LazyCompiler::new(partials))
+        .build()?;
+    if args.format {
+        let source = fs::read_to_string(&args.input)?;
+        let formatted = parser.format(&source)?;
+        match args.output {
+            Some(path) => fs::write(path, formatted)?,
+            None => print!("{}", formatted),
+        }
+        return Ok(0);
+    }
+
     let template = parser.parse_file(&args.input)?;
 
+    if args.lint {
+        let findings = template.lint();
+        println!("{}", serde_json::to_string_pretty(&findings)?);
+        return Ok(if findings.is_empty() { 0 } else { 1 });
+    }
+
     let data = args
         .context
         .as_ref()
@@ -3,6 +3,7 @@ use std::fmt;
 use std::result;
 
 use super::ErrorClone;
+use super::Location;
 use super::Trace;
 
 /// Convenience type alias for Liquid compiler errors
@@ -10,6 +11,21 @@ pub type Result<T, E = Error> = result::Result<T, E>;
 
 type BoxedError = Box<dyn ErrorClone>;
 
+/// The category of an `Error`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A problem with the template or the data it was rendered with.
+    #[default]
+    Other,
+    /// Rendering was aborted for exceeding one of the configured `Limits`.
+    ResourceLimit,
+    /// Rendering was stopped through its `Cancellation`.
+    Cancelled,
+    /// A filter was given input of a type it can't handle.
+    InvalidInput,
+}
+
 /// Compiler error
 #[derive(Debug, Clone)]
 pub struct Error {
@@ -22,8 +38,10 @@ pub struct Error {
 // shouldn't hurt.
 #[derive(Debug, Clone)]
 struct InnerError {
+    kind: ErrorKind,
     msg: crate::model::KString,
     user_backtrace: Vec<Trace>,
+    location: Option<Location>,
     cause: Option<BoxedError>,
 }
 
@@ -35,8 +53,10 @@ impl Error {
 
     fn with_msg_cow(msg: crate::model::KString) -> Self {
         let error = InnerError {
+            kind: ErrorKind::Other,
             msg,
             user_backtrace: vec![Trace::empty()],
+            location: None,
             cause: None,
         };
         Self {
@@ -82,6 +102,28 @@ impl Error {
         self
     }
 
+    /// Categorize the error.
+    pub fn with_kind(mut self, kind: ErrorKind) -> Self {
+        self.inner.kind = kind;
+        self
+    }
+
+    /// The category of the error.
+    pub fn kind(&self) -> ErrorKind {
+        self.inner.kind
+    }
+
+    /// Record where in the template source the error occurred.
+    pub fn with_location(mut self, location: Location) -> Self {
+        self.inner.location = Some(location);
+        self
+    }
+
+    /// Where in the template source the error occurred, if known.
+    pub fn location(&self) -> Option<&Location> {
+        self.inner.location.as_ref()
+    }
+
     /// Add an external cause to the error for debugging purposes.
     pub fn cause<E: ErrorClone>(self, cause: E) -> Self {
         let cause = Box::new(cause);
@@ -109,7 +151,7 @@ const ERROR_DESCRIPTION: &str = "liquid";
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "{}: {}", ERROR_DESCRIPTION, self.inner.msg)?;
-        for trace in &self.inner.user_backtrace {
+        for (i, trace) in self.inner.user_backtrace.iter().enumerate() {
             if let Some(trace) = trace.get_trace() {
                 writeln!(f, "from: {}", trace)?;
             }
@@ -119,6 +161,11 @@ impl fmt::Display for Error {
             for (key, value) in trace.get_context() {
                 writeln!(f, "    {}={}", key, value)?;
             }
+            if i == 0 {
+                if let Some(location) = &self.inner.location {
+                    writeln!(f, "  --> {}", location)?;
+                }
+            }
         }
         Ok(())
     }
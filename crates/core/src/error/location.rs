@@ -0,0 +1,42 @@
+use std::fmt;
+
+/// Where in the template source an error occurred.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Location {
+    name: Option<crate::model::KString>,
+    line: usize,
+    column: usize,
+}
+
+impl Location {
+    /// Create a location within the template called `name`.
+    ///
+    /// `name` is `None` for the top-level template.
+    pub fn new(name: Option<crate::model::KString>, line: usize, column: usize) -> Self {
+        Self { name, line, column }
+    }
+
+    /// The name of the partial, if not the top-level template.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The line, starting from 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The column, in characters, starting from 1.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = &self.name {
+            write!(f, "{}:", name)?;
+        }
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
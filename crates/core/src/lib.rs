@@ -33,3 +33,4 @@ pub use runtime::Expression;
 pub use runtime::Renderable;
 pub use runtime::Runtime;
 pub use runtime::Template;
+pub use runtime::Visitor;
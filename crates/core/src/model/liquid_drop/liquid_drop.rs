@@ -4,7 +4,7 @@ use std::sync::Arc;
 use serde::Deserialize;
 use serde::Serialize;
 
-use crate::model::{State, DisplayCow, KStringCow, object::{Object, ObjectView}};
+use crate::model::{State, DisplayCow, KStringCow, object::{BoxFuture, Object, ObjectView}};
 use crate::ValueCow;
 use crate::ValueView;
 
@@ -150,6 +150,12 @@ impl ObjectView for DropWrapper {
             DropWrapper::DropObject(d) => d.inner.get(index),
         }
     }
+
+    fn get_async<'s>(&'s self, index: &'s str) -> Option<BoxFuture<'s, Option<crate::Value>>> {
+        match self {
+            DropWrapper::DropObject(d) => d.inner.get_async(index),
+        }
+    }
 }
 
 
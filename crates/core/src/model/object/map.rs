@@ -1,18 +1,25 @@
 //! Type representing a Liquid object, payload of the `Value::Object` variant
 
 use std::borrow::Borrow;
+#[cfg(not(feature = "preserve_order"))]
 use std::collections::hash_map;
 use std::fmt::{self, Debug};
 use std::hash::Hash;
 use std::iter::FromIterator;
 use std::ops;
 
+#[cfg(feature = "preserve_order")]
+use indexmap::map as hash_map;
+#[cfg(not(feature = "preserve_order"))]
 use itertools::Itertools;
 use serde::{de, ser};
 
 use super::Value;
 
 /// Type representing a Liquid object, payload of the `Value::Object` variant
+///
+/// Entries are rendered and serialized sorted by key, or in insertion order with the
+/// `preserve_order` feature.
 #[derive(Default, Clone, PartialEq, Eq)]
 pub struct Object {
     map: MapImpl<Key, Value>,
@@ -20,7 +27,10 @@ pub struct Object {
 
 type Key = crate::model::KString;
 
+#[cfg(not(feature = "preserve_order"))]
 type MapImpl<K, V> = hash_map::HashMap<K, V>;
+#[cfg(feature = "preserve_order")]
+type MapImpl<K, V> = indexmap::IndexMap<K, V>;
 type VacantEntryImpl<'a> = hash_map::VacantEntry<'a, Key, Value>;
 type OccupiedEntryImpl<'a> = hash_map::OccupiedEntry<'a, Key, Value>;
 type IterImpl<'a> = hash_map::Iter<'a, Key, Value>;
@@ -106,7 +116,10 @@ impl Object {
         Key: Borrow<Q>,
         Q: Ord + Eq + Hash + ?Sized,
     {
-        self.map.remove(key)
+        #[cfg(not(feature = "preserve_order"))]
+        return self.map.remove(key);
+        #[cfg(feature = "preserve_order")]
+        return self.map.shift_remove(key);
     }
 
     /// Gets the given key's corresponding entry in the map for in-place
@@ -115,7 +128,7 @@ impl Object {
     where
         S: Into<Key>,
     {
-        use std::collections::hash_map::Entry as EntryImpl;
+        use self::hash_map::Entry as EntryImpl;
         match self.map.entry(key.into()) {
             EntryImpl::Vacant(vacant) => Entry::Vacant(VacantEntry { vacant }),
             EntryImpl::Occupied(occupied) => Entry::Occupied(OccupiedEntry { occupied }),
@@ -142,6 +155,17 @@ impl Object {
         }
     }
 
+    /// Gets an iterator over the entries of the map, in the order they are rendered and
+    /// serialized.
+    ///
+    /// This is insertion order with the `preserve_order` feature, and otherwise sorted by key.
+    pub(crate) fn iter_ordered(&self) -> Box<dyn Iterator<Item = (&Key, &Value)> + '_> {
+        #[cfg(not(feature = "preserve_order"))]
+        return Box::new(self.iter().sorted_by_key(|x| x.0.as_str()));
+        #[cfg(feature = "preserve_order")]
+        return Box::new(self.iter());
+    }
+
     /// Gets a mutable iterator over the entries of the map.
     #[inline]
     pub fn iter_mut(&mut self) -> IterMut<'_> {
@@ -238,7 +262,7 @@ impl ser::Serialize for Object {
     {
         use serde::ser::SerializeMap;
         let mut map = serializer.serialize_map(Some(self.len()))?;
-        for (k, v) in self.iter().sorted_by_key(|x| x.0.as_str()) {
+        for (k, v) in self.iter_ordered() {
             map.serialize_key(k)?;
             map.serialize_value(v)?;
         }
@@ -598,7 +622,10 @@ impl<'a> OccupiedEntry<'a> {
     /// ```
     #[inline]
     pub fn remove(self) -> Value {
-        self.occupied.remove()
+        #[cfg(not(feature = "preserve_order"))]
+        return self.occupied.remove();
+        #[cfg(feature = "preserve_order")]
+        return self.occupied.shift_remove();
     }
 }
 
@@ -6,6 +6,8 @@ mod ser;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
 
 use crate::model::KStringCow;
 
@@ -19,6 +21,9 @@ use itertools::Position;
 pub use map::Object;
 pub use ser::to_object;
 
+/// A boxed future, as returned by `ObjectView::get_async`.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
 /// Accessor for objects.
 
 pub trait ObjectView: ValueView {
@@ -40,6 +45,14 @@ pub trait ObjectView: ValueView {
     /// Access a contained `Value`.
     ///
     fn get<'s>(&'s self, index: &str) -> Option<ValueCow<'s>>;
+
+    /// Fetch a contained `Value` that can't be read with `get`, like from a database.
+    ///
+    /// Only used when rendering asynchronously.  `None`, the default, means `index` is read with
+    /// `get` as usual.
+    fn get_async<'s>(&'s self, _index: &'s str) -> Option<BoxFuture<'s, Option<Value>>> {
+        None
+    }
 }
 
 impl ValueView for Object {
@@ -96,7 +109,7 @@ impl ObjectView for Object {
     }
 
     fn iter<'k>(&'k self) -> Box<dyn Iterator<Item = (KStringCow<'k>, &'k dyn ValueView)> + 'k> {
-        let i = Object::iter(self).sorted_by_key(|x| x.0.as_str()).map(|(k, v)| (k.as_str().into(), v.as_view()));
+        let i = Object::iter_ordered(self).map(|(k, v)| (k.as_str().into(), v.as_view()));
         Box::new(i)
     }
 
@@ -137,6 +150,10 @@ impl<'o, O: ObjectView + ?Sized> ObjectView for &'o O {
     fn get<'s>(&'s self, index: &str) -> Option<ValueCow<'s>> {
         <O as ObjectView>::get(self, index)
     }
+
+    fn get_async<'s>(&'s self, index: &'s str) -> Option<BoxFuture<'s, Option<Value>>> {
+        <O as ObjectView>::get_async(self, index)
+    }
 }
 
 /// Owned object index
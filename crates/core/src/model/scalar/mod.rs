@@ -30,6 +30,9 @@ pub struct ScalarCow<'s>(ScalarCowEnum<'s>);
 pub type Scalar = ScalarCow<'static>;
 
 /// An enum to represent different value types
+///
+/// Safe strings are written as plain strings and never read back, so the safe marker can't come
+/// from data.
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 #[serde(untagged)]
 enum ScalarCowEnum<'s> {
@@ -39,6 +42,8 @@ enum ScalarCowEnum<'s> {
     DateTime(DateTime),
     Date(Date),
     Str(KStringCow<'s>),
+    #[serde(skip_deserializing)]
+    SafeStr(KStringCow<'s>),
 }
 
 impl<'s> ScalarCow<'s> {
@@ -47,6 +52,16 @@ impl<'s> ScalarCow<'s> {
         value.into()
     }
 
+    /// Create a string that is already safe to output as HTML, so it won't be escaped again.
+    pub fn new_safe<S: Into<KStringCow<'s>>>(value: S) -> Self {
+        ScalarCow(ScalarCowEnum::SafeStr(value.into()))
+    }
+
+    /// Whether the value is a string marked as safe to output as HTML.
+    pub fn is_safe(&self) -> bool {
+        matches!(self.0, ScalarCowEnum::SafeStr(_))
+    }
+
     /// Create an owned version of the value.
     pub fn into_owned(self) -> Scalar {
         match self.0 {
@@ -56,6 +71,7 @@ impl<'s> ScalarCow<'s> {
             ScalarCowEnum::DateTime(x) => Scalar::new(x),
             ScalarCowEnum::Date(x) => Scalar::new(x),
             ScalarCowEnum::Str(x) => Scalar::new(x.into_owned()),
+            ScalarCowEnum::SafeStr(x) => Scalar::new_safe(x.into_owned()),
         }
     }
 
@@ -68,6 +84,7 @@ impl<'s> ScalarCow<'s> {
             ScalarCowEnum::DateTime(x) => ScalarCow::new(x),
             ScalarCowEnum::Date(x) => ScalarCow::new(x),
             ScalarCowEnum::Str(ref x) => ScalarCow::new(x.as_ref()),
+            ScalarCowEnum::SafeStr(ref x) => ScalarCow::new_safe(x.as_ref()),
         }
     }
 
@@ -79,7 +96,7 @@ impl<'s> ScalarCow<'s> {
             ScalarCowEnum::Bool(ref x) => x,
             ScalarCowEnum::DateTime(ref x) => x,
             ScalarCowEnum::Date(ref x) => x,
-            ScalarCowEnum::Str(ref x) => x,
+            ScalarCowEnum::Str(ref x) | ScalarCowEnum::SafeStr(ref x) => x,
         }
     }
 
@@ -91,7 +108,7 @@ impl<'s> ScalarCow<'s> {
             ScalarCowEnum::Bool(x) => x.to_string().into(),
             ScalarCowEnum::DateTime(x) => x.to_string().into(),
             ScalarCowEnum::Date(x) => x.to_string().into(),
-            ScalarCowEnum::Str(x) => x.into_owned(),
+            ScalarCowEnum::Str(x) | ScalarCowEnum::SafeStr(x) => x.into_owned(),
         }
     }
 
@@ -99,7 +116,9 @@ impl<'s> ScalarCow<'s> {
     pub fn to_integer(&self) -> Option<i64> {
         match self.0 {
             ScalarCowEnum::Integer(ref x) => Some(*x),
-            ScalarCowEnum::Str(ref x) => x.trim().parse::<i64>().ok(),
+            ScalarCowEnum::Str(ref x) | ScalarCowEnum::SafeStr(ref x) => {
+                x.trim().parse::<i64>().ok()
+            }
             _ => None,
         }
     }
@@ -109,7 +128,9 @@ impl<'s> ScalarCow<'s> {
         match self.0 {
             ScalarCowEnum::Integer(ref x) => Some(*x as f64),
             ScalarCowEnum::Float(ref x) => Some(*x),
-            ScalarCowEnum::Str(ref x) => x.trim().parse::<f64>().ok(),
+            ScalarCowEnum::Str(ref x) | ScalarCowEnum::SafeStr(ref x) => {
+                x.trim().parse::<f64>().ok()
+            }
             _ => None,
         }
     }
@@ -128,7 +149,9 @@ impl<'s> ScalarCow<'s> {
             ScalarCowEnum::DateTime(ref x) => Some(*x),
             ScalarCowEnum::Date(ref x) => DateTime::from_date(x),
             ScalarCowEnum::Integer(ref x) => DateTime::from_unix_timestamp(*x),
-            ScalarCowEnum::Str(ref x) => DateTime::from_str(x.as_str()),
+            ScalarCowEnum::Str(ref x) | ScalarCowEnum::SafeStr(ref x) => {
+                DateTime::from_str(x.as_str())
+            }
             _ => None,
         };
         res
@@ -139,7 +162,7 @@ impl<'s> ScalarCow<'s> {
         let res = match self.0 {
             ScalarCowEnum::DateTime(ref x) => Some(x.date()),
             ScalarCowEnum::Date(ref x) => Some(*x),
-            ScalarCowEnum::Str(ref x) => Date::from_str(x.as_str()),
+            ScalarCowEnum::Str(ref x) | ScalarCowEnum::SafeStr(ref x) => Date::from_str(x.as_str()),
             _ => None,
         };
         res
@@ -148,7 +171,7 @@ impl<'s> ScalarCow<'s> {
     /// Interpret as a Cow str, borrowing if possible
     pub fn into_cow_str(self) -> Cow<'s, str> {
         match self {
-            Self(ScalarCowEnum::Str(x)) => x.into_cow_str(),
+            Self(ScalarCowEnum::Str(x) | ScalarCowEnum::SafeStr(x)) => x.into_cow_str(),
             other => other.into_string().into_cow_str(),
         }
     }
@@ -196,7 +219,7 @@ impl<'s> ScalarCow<'s> {
     /// returns underlying string
     pub fn as_str(&self) -> Option<&str> {
         match &self.0 {
-            ScalarCowEnum::Str(x) => Some(x),
+            ScalarCowEnum::Str(x) | ScalarCowEnum::SafeStr(x) => Some(x),
             _ => None
         }
     }
@@ -231,7 +254,7 @@ impl<'s> ValueView for ScalarCow<'s> {
         self.as_view().to_kstr()
     }
     fn to_value(&self) -> Value {
-        self.as_view().to_value()
+        Value::Scalar(self.as_ref().into_owned())
     }
 
     fn as_scalar(&self) -> Option<ScalarCow<'_>> {
@@ -912,7 +935,10 @@ fn scalar_eq<'s>(lhs: &ScalarCow<'s>, rhs: &ScalarCow<'s>) -> bool {
         (&ScalarCowEnum::Date(x), &ScalarCowEnum::Date(y)) => x == y,
         (&ScalarCowEnum::DateTime(x), &ScalarCowEnum::Date(y)) => x == x.with_date(y),
         (&ScalarCowEnum::Date(x), &ScalarCowEnum::DateTime(y)) => y.with_date(x) == y,
-        (ScalarCowEnum::Str(x), ScalarCowEnum::Str(y)) => x == y,
+        (
+            ScalarCowEnum::Str(x) | ScalarCowEnum::SafeStr(x),
+            ScalarCowEnum::Str(y) | ScalarCowEnum::SafeStr(y),
+        ) => x == y,
         // encode Ruby truthiness: all values except false and nil are true
         //(_, &ScalarCowEnum::Bool(b)) | (&ScalarCowEnum::Bool(b), _) => b,
         _ => false,
@@ -932,7 +958,10 @@ fn scalar_cmp<'s>(lhs: &ScalarCow<'s>, rhs: &ScalarCow<'s>) -> Option<Ordering>
         (&ScalarCowEnum::Date(x), &ScalarCowEnum::Date(y)) => x.partial_cmp(&y),
         (&ScalarCowEnum::DateTime(x), &ScalarCowEnum::Date(y)) => x.partial_cmp(&x.with_date(y)),
         (&ScalarCowEnum::Date(x), &ScalarCowEnum::DateTime(y)) => y.with_date(x).partial_cmp(&y),
-        (ScalarCowEnum::Str(x), ScalarCowEnum::Str(y)) => x.partial_cmp(y),
+        (
+            ScalarCowEnum::Str(x) | ScalarCowEnum::SafeStr(x),
+            ScalarCowEnum::Str(y) | ScalarCowEnum::SafeStr(y),
+        ) => x.partial_cmp(y),
         _ => None,
     };
     res
@@ -971,6 +1000,33 @@ mod test {
         assert_eq!(val.to_kstr(), "foobar");
     }
 
+    #[test]
+    fn test_safe_str() {
+        let val = ScalarCow::new_safe("<b>foobar</b>");
+        assert!(val.is_safe());
+        assert!(val.to_value().as_scalar().unwrap().is_safe());
+        assert_eq!(val, ScalarCow::new("<b>foobar</b>"));
+        assert!(!ScalarCow::new("<b>foobar</b>").is_safe());
+    }
+
+    #[test]
+    fn test_safe_str_serde() {
+        let safe: Scalar = ScalarCow::new_safe("<b>foobar</b>");
+        let json = serde_json::to_string(&safe).unwrap();
+        assert_eq!(json, r#""<b>foobar</b>""#);
+        let round_trip: Scalar = serde_json::from_str(&json).unwrap();
+        assert!(!round_trip.is_safe());
+
+        let tagged = serde_json::from_str::<Scalar>(r#"{"$liquid_safe":"<b>foobar</b>"}"#);
+        assert!(tagged.is_err());
+        let number: Scalar = serde_json::from_str("42").unwrap();
+        assert_eq!(number.as_integer(), Some(42));
+
+        let value = crate::model::to_value(&safe).unwrap();
+        let value = value.as_scalar().expect("a plain string");
+        assert_eq!(value.to_kstr(), "<b>foobar</b>");
+    }
+
     #[test]
     fn test_to_integer_bool() {
         assert_eq!(TRUE.to_integer(), None);
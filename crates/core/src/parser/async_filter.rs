@@ -0,0 +1,36 @@
+use std::fmt::{Debug, Display};
+
+use crate::error::{Error, Result};
+use crate::model::{BoxFuture, Value, ValueView};
+use crate::runtime::Runtime;
+
+/// A filter whose result is computed asynchronously, like by querying a database.
+///
+/// An async filter is still called through `Filter`, by forwarding `Filter::evaluate` to
+/// `evaluate_async_filter`, so it can be registered like any other filter.
+pub trait AsyncFilter: Send + Sync + Debug + Display {
+    /// Start computing the result for `input`.
+    ///
+    /// Arguments are evaluated up-front, as `runtime` isn't available to the future.
+    fn evaluate_async(
+        &self,
+        input: &dyn ValueView,
+        runtime: &dyn Runtime,
+    ) -> Result<BoxFuture<'static, Result<Value>>>;
+}
+
+/// Evaluate `filter` within a render pass, see `AsyncFilters`.
+///
+/// Fails unless the template is being rendered asynchronously.
+pub fn evaluate_async_filter(
+    filter: &dyn AsyncFilter,
+    input: &dyn ValueView,
+    runtime: &dyn Runtime,
+) -> Result<Value> {
+    match runtime.async_filters() {
+        Some(filters) => filters.evaluate(filter, input, runtime),
+        None => Error::with_msg("Async filters need an asynchronous render")
+            .context("filter", filter.to_string())
+            .into_err(),
+    }
+}
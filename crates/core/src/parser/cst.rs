@@ -0,0 +1,763 @@
+use std::ops::Range;
+
+use pest::Parser as _;
+
+use super::parser::inner::{LiquidParser, Rule};
+use super::parser::lax_file_rule;
+use super::Language;
+
+type Pair<'a> = ::pest::iterators::Pair<'a, Rule>;
+
+/// Parses `text` into a lossless syntax tree, without needing any tags, blocks or filters.
+///
+/// Every byte of `text` is covered by exactly one token, so concatenating the tokens gives back
+/// `text`.  Markup that can't be lexed, like `{{ }}`, becomes an `Invalid` node rather than an
+/// error.  Blocks aren't matched up with their end tags, but the bodies of `raw` and `comment`
+/// blocks are kept as single `Text` tokens, as they aren't parsed as liquid.  String literals are lexed as `options`
+/// would parse them, with or without backslash escapes.
+pub fn parse_cst<'s>(text: &'s str, options: &Language) -> SyntaxTree<'s> {
+    let mut children = Vec::new();
+    let mut offset = 0;
+    'relex: while offset < text.len() {
+        let elements = LiquidParser::parse(lax_file_rule(options), &text[offset..])
+            .expect("Parsing with Rule::LaxLiquidFile should not raise errors, but InvalidLiquid tokens instead.")
+            .next()
+            .expect("Unwrapping LiquidFile to access the elements.")
+            .into_inner();
+        for element in elements {
+            match element.as_rule() {
+                Rule::EOI => break,
+                Rule::InvalidLiquid => {
+                    // Skip past the markup that failed to lex, then carry on from there.
+                    let start = offset + element.as_span().start();
+                    let end = invalid_end(text, start);
+                    children.push(SyntaxNode::token(SyntaxKind::Invalid, start..end));
+                    offset = end;
+                    continue 'relex;
+                }
+                Rule::Tag => {
+                    let name = tag_name(&element);
+                    let end = offset + element.as_span().end();
+                    markup(text, element, offset, &mut children);
+                    if let Some(body_end) = verbatim_end(text, end, name) {
+                        // The body isn't liquid, so it's kept whole until the end tag.
+                        if end < body_end {
+                            children.push(SyntaxNode::token(SyntaxKind::Text, end..body_end));
+                        }
+                        offset = body_end;
+                        continue 'relex;
+                    }
+                }
+                _ => markup(text, element, offset, &mut children),
+            }
+        }
+        break;
+    }
+
+    SyntaxTree {
+        text,
+        root: SyntaxNode {
+            kind: SyntaxKind::Document,
+            range: 0..text.len(),
+            children,
+        },
+    }
+}
+
+/// A `SyntaxNode` along with the text it was parsed from, see `parse_cst`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyntaxTree<'s> {
+    text: &'s str,
+    root: SyntaxNode,
+}
+
+impl<'s> SyntaxTree<'s> {
+    /// The parsed text.
+    pub fn text(&self) -> &'s str {
+        self.text
+    }
+
+    /// The `Document` node covering all of the text.
+    pub fn root(&self) -> &SyntaxNode {
+        &self.root
+    }
+
+    /// The text covered by `node`.
+    pub fn node_text(&self, node: &SyntaxNode) -> &'s str {
+        &self.text[node.range()]
+    }
+
+    /// Every token, in order.
+    pub fn tokens(&self) -> Vec<&SyntaxNode> {
+        let mut tokens = Vec::new();
+        self.root.collect_tokens(&mut tokens);
+        tokens
+    }
+
+    /// The innermost nodes covering the byte `offset`, from the root down.
+    pub fn path_at(&self, offset: usize) -> Vec<&SyntaxNode> {
+        let mut path = vec![&self.root];
+        let mut node = &self.root;
+        while let Some(child) = node
+            .children
+            .iter()
+            .find(|child| child.range.contains(&offset))
+        {
+            path.push(child);
+            node = child;
+        }
+        path
+    }
+}
+
+/// The kind of a `SyntaxNode`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SyntaxKind {
+    /// The root of the tree.
+    Document,
+    /// Text outside of any markup.
+    Text,
+    /// A `{% %}` tag.
+    Tag,
+    /// A `{{ }}` output.
+    Output,
+    /// Markup that couldn't be lexed.
+    Invalid,
+    /// A value followed by filters, like `name | upcase`.
+    FilterChain,
+    /// A filter and its arguments, like `append: "x"`.
+    Filter,
+    /// A named filter argument, like `limit: 2`.
+    KeywordArgument,
+    /// A variable and its indexes, like `items[0].title`.
+    Variable,
+    /// A range, like `(1..3)`.
+    Range,
+    /// An array literal, like `[1, x]`.
+    Array,
+    /// A hash literal, like `{"a": x}`.
+    Hash,
+    /// A condition passed to a filter, like `(a < b)`.
+    Condition,
+
+    /// Whitespace, including whitespace trimmed by whitespace control.
+    Whitespace,
+    /// `{%`.
+    TagOpen,
+    /// `%}`.
+    TagClose,
+    /// `{{`.
+    OutputOpen,
+    /// `}}`.
+    OutputClose,
+    /// The `-` of whitespace control.
+    Trim,
+    /// The name of a tag.
+    TagName,
+    /// The name of a filter.
+    FilterName,
+    /// Any other name, like a variable or a bare word in a tag.
+    Identifier,
+    /// A quoted string.
+    String,
+    /// An integer.
+    Integer,
+    /// A floating point number.
+    Float,
+    /// `true` or `false`.
+    Boolean,
+    /// `nil` or `null`.
+    Nil,
+    /// `empty`.
+    Empty,
+    /// `blank`.
+    Blank,
+    /// A comparison, like `==` or `<>`.
+    Operator,
+    /// `=`.
+    Assign,
+    /// `|`.
+    Pipe,
+    /// `:`.
+    Colon,
+    /// `,`.
+    Comma,
+    /// `.`.
+    Dot,
+    /// `..`.
+    DotDot,
+    /// `[`.
+    LeftBracket,
+    /// `]`.
+    RightBracket,
+    /// `(`.
+    LeftParen,
+    /// `)`.
+    RightParen,
+    /// `{`.
+    LeftBrace,
+    /// `}`.
+    RightBrace,
+}
+
+/// A node of a `SyntaxTree`, or a token if it has no children.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyntaxNode {
+    kind: SyntaxKind,
+    range: Range<usize>,
+    children: Vec<SyntaxNode>,
+}
+
+impl SyntaxNode {
+    fn token(kind: SyntaxKind, range: Range<usize>) -> Self {
+        Self {
+            kind,
+            range,
+            children: Vec::new(),
+        }
+    }
+
+    /// What the node is.
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    /// The byte range of the text the node covers.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// The nodes and tokens the node is made of, in order.
+    pub fn children(&self) -> &[SyntaxNode] {
+        &self.children
+    }
+
+    /// Whether the node is a token, without children.
+    pub fn is_token(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    fn collect_tokens<'n>(&'n self, tokens: &mut Vec<&'n SyntaxNode>) {
+        if self.is_token() {
+            tokens.push(self);
+        }
+        for child in &self.children {
+            child.collect_tokens(tokens);
+        }
+    }
+}
+
+/// Adds the nodes for a top-level `element` to `nodes`.
+fn markup(text: &str, element: Pair<'_>, offset: usize, nodes: &mut Vec<SyntaxNode>) {
+    let span = element.as_span();
+    let range = offset + span.start()..offset + span.end();
+    let kind = match element.as_rule() {
+        Rule::Tag => SyntaxKind::Tag,
+        Rule::Expression => SyntaxKind::Output,
+        _ => {
+            nodes.push(SyntaxNode::token(SyntaxKind::Text, range));
+            return;
+        }
+    };
+
+    // Whitespace trimmed by `{%-` and `-%}` is part of the element in the grammar.
+    let source = &text[range.clone()];
+    let start = range.start + (source.len() - source.trim_start().len());
+    let end = range.start + source.trim_end().len();
+    if range.start < start {
+        nodes.push(SyntaxNode::token(
+            SyntaxKind::Whitespace,
+            range.start..start,
+        ));
+    }
+    let mut node = node(text, kind, start..end, element.into_inner(), offset);
+    if kind == SyntaxKind::Tag {
+        rename_first(&mut node, SyntaxKind::TagName);
+    }
+    nodes.push(node);
+    if end < range.end {
+        nodes.push(SyntaxNode::token(SyntaxKind::Whitespace, end..range.end));
+    }
+}
+
+/// Builds a node from the `inner` pairs, filling the gaps between them with tokens.
+fn node(
+    text: &str,
+    kind: SyntaxKind,
+    range: Range<usize>,
+    inner: pest::iterators::Pairs<'_, Rule>,
+    offset: usize,
+) -> SyntaxNode {
+    let mut children = Vec::new();
+    let mut position = range.start;
+    for pair in inner {
+        let span = pair.as_span();
+        let start = offset + span.start();
+        lex_gap(text, position..start, &mut children);
+        position = offset + span.end();
+        pair_nodes(text, pair, offset, &mut children);
+    }
+    lex_gap(text, position..range.end, &mut children);
+    SyntaxNode {
+        kind,
+        range,
+        children,
+    }
+}
+
+/// Adds the nodes for `pair` to `nodes`, flattening the rules that only group others.
+fn pair_nodes(text: &str, pair: Pair<'_>, offset: usize, nodes: &mut Vec<SyntaxNode>) {
+    let span = pair.as_span();
+    let range = offset + span.start()..offset + span.end();
+    let kind = match pair.as_rule() {
+        Rule::TagInner
+        | Rule::ExpressionInner
+        | Rule::Value
+        | Rule::Literal
+        | Rule::HashEntry
+        | Rule::PositionalFilterArgument => {
+            let flattened = node(text, SyntaxKind::Document, range, pair.into_inner(), offset);
+            nodes.extend(flattened.children);
+            return;
+        }
+        Rule::Filter => {
+            let mut filter = node(text, SyntaxKind::Filter, range, pair.into_inner(), offset);
+            rename_first(&mut filter, SyntaxKind::FilterName);
+            nodes.push(filter);
+            return;
+        }
+        rule @ (Rule::FilterChain
+        | Rule::KeywordFilterArgument
+        | Rule::Variable
+        | Rule::Range
+        | Rule::ArrayLiteral
+        | Rule::HashLiteral
+        | Rule::ConditionArgument) => syntax_kind(rule),
+        rule => {
+            nodes.push(SyntaxNode::token(syntax_kind(rule), range));
+            return;
+        }
+    };
+    nodes.push(node(text, kind, range, pair.into_inner(), offset));
+}
+
+/// The kind of the node or token for `rule`, for every rule of the grammar.
+fn syntax_kind(rule: Rule) -> SyntaxKind {
+    match rule {
+        Rule::Identifier => SyntaxKind::Identifier,
+        Rule::StringLiteral | Rule::EscapedStringLiteral => SyntaxKind::String,
+        Rule::IntegerLiteral => SyntaxKind::Integer,
+        Rule::FloatLiteral => SyntaxKind::Float,
+        Rule::BooleanLiteral => SyntaxKind::Boolean,
+        Rule::NilLiteral => SyntaxKind::Nil,
+        Rule::EmptyLiteral => SyntaxKind::Empty,
+        Rule::BlankLiteral => SyntaxKind::Blank,
+        Rule::Assign => SyntaxKind::Assign,
+        Rule::Comma => SyntaxKind::Comma,
+        Rule::Colon => SyntaxKind::Colon,
+        Rule::Equals
+        | Rule::NotEquals
+        | Rule::LesserThanGreaterThan
+        | Rule::GreaterThan
+        | Rule::LesserThan
+        | Rule::GreaterThanEquals
+        | Rule::LesserThanEquals => SyntaxKind::Operator,
+        Rule::Raw => SyntaxKind::Text,
+        Rule::Tag => SyntaxKind::Tag,
+        Rule::Expression => SyntaxKind::Output,
+        Rule::FilterChain => SyntaxKind::FilterChain,
+        Rule::Filter => SyntaxKind::Filter,
+        Rule::KeywordFilterArgument => SyntaxKind::KeywordArgument,
+        Rule::Variable => SyntaxKind::Variable,
+        Rule::Range => SyntaxKind::Range,
+        Rule::ArrayLiteral => SyntaxKind::Array,
+        Rule::HashLiteral => SyntaxKind::Hash,
+        Rule::ConditionArgument => SyntaxKind::Condition,
+        Rule::InvalidLiquid => SyntaxKind::Invalid,
+        // Flattened into their children by `pair_nodes`.
+        Rule::TagInner
+        | Rule::ExpressionInner
+        | Rule::Value
+        | Rule::Literal
+        | Rule::HashEntry
+        | Rule::PositionalFilterArgument
+        // Silent or atomic-only rules, the files and the end of input never lex into tokens.
+        | Rule::LaxLiquidFile
+        | Rule::LiquidFile
+        | Rule::EscapedLaxLiquidFile
+        | Rule::EscapedLiquidFile
+        | Rule::EOI
+        | Rule::WHITESPACE
+        | Rule::NON_WHITESPACE_CONTROL_HYPHEN
+        | Rule::Element
+        | Rule::TagStart
+        | Rule::TagEnd
+        | Rule::ExpressionStart
+        | Rule::ExpressionEnd
+        | Rule::FilterArgument
+        | Rule::StringEscape
+        | Rule::NonNumeric
+        | Rule::TagToken
+        | Rule::SingleCharSymbol
+        | Rule::DoubleCharSymbol => SyntaxKind::Invalid,
+    }
+}
+
+/// Gives the first `Identifier` among the children of `node` the more specific `kind`.
+fn rename_first(node: &mut SyntaxNode, kind: SyntaxKind) {
+    if let Some(name) = node
+        .children
+        .iter_mut()
+        .find(|child| child.kind == SyntaxKind::Identifier)
+    {
+        name.kind = kind;
+    }
+}
+
+/// Splits the text the grammar matched without a rule of its own into tokens.
+fn lex_gap(text: &str, range: Range<usize>, tokens: &mut Vec<SyntaxNode>) {
+    const PUNCTUATION: &[(&str, SyntaxKind)] = &[
+        ("{%", SyntaxKind::TagOpen),
+        ("%}", SyntaxKind::TagClose),
+        ("{{", SyntaxKind::OutputOpen),
+        ("}}", SyntaxKind::OutputClose),
+        ("-", SyntaxKind::Trim),
+        ("|", SyntaxKind::Pipe),
+        (":", SyntaxKind::Colon),
+        (",", SyntaxKind::Comma),
+        ("..", SyntaxKind::DotDot),
+        (".", SyntaxKind::Dot),
+        ("[", SyntaxKind::LeftBracket),
+        ("]", SyntaxKind::RightBracket),
+        ("(", SyntaxKind::LeftParen),
+        (")", SyntaxKind::RightParen),
+        ("{", SyntaxKind::LeftBrace),
+        ("}", SyntaxKind::RightBrace),
+    ];
+
+    let mut position = range.start;
+    while position < range.end {
+        let rest = &text[position..range.end];
+        let whitespace = whitespace_len(rest);
+        let (kind, len) = if whitespace > 0 {
+            (SyntaxKind::Whitespace, whitespace)
+        } else if let Some((symbol, kind)) = PUNCTUATION.iter().find(|(s, _)| rest.starts_with(s)) {
+            (*kind, symbol.len())
+        } else {
+            let len = rest.chars().next().map(char::len_utf8).unwrap_or(1);
+            (SyntaxKind::Invalid, len)
+        };
+        match tokens.last_mut() {
+            Some(last) if last.kind == kind && kind == SyntaxKind::Whitespace => {
+                last.range.end += len;
+            }
+            _ => tokens.push(SyntaxNode::token(kind, position..position + len)),
+        }
+        position += len;
+    }
+}
+
+/// The length of the whitespace, as the grammar sees it, starting `text`.
+fn whitespace_len(text: &str) -> usize {
+    let mut len = 0;
+    loop {
+        let rest = &text[len..];
+        if rest.starts_with("&nbsp;") {
+            len += "&nbsp;".len();
+        } else if let Some(c) = rest
+            .chars()
+            .next()
+            .filter(|c| matches!(c, ' ' | '\t' | '\n' | '\r' | '\u{a0}'))
+        {
+            len += c.len_utf8();
+        } else {
+            return len;
+        }
+    }
+}
+
+fn tag_name<'p>(tag: &Pair<'p>) -> &'p str {
+    tag.clone()
+        .into_inner()
+        .next()
+        .and_then(|inner| inner.into_inner().next())
+        .map(|name| name.as_str())
+        .unwrap_or_default()
+}
+
+/// Where the body of a `raw` or `comment` block, named `name` and starting at `start`, ends: before
+/// its end tag, or the end of `text` if it has none.  `comment` blocks nest, `raw` blocks don't.
+fn verbatim_end(text: &str, start: usize, name: &str) -> Option<usize> {
+    let (nested_by, end_tag) = match name {
+        "raw" => (None, "endraw"),
+        "comment" => (Some("comment"), "endcomment"),
+        _ => return None,
+    };
+    let mut nesting = 0;
+    let mut position = start;
+    while let Some(found) = text[position..].find("{%") {
+        let tag = position + found;
+        position = tag + 2;
+        let markup = text[position..]
+            .strip_prefix('-')
+            .unwrap_or(&text[position..]);
+        let markup = &markup[whitespace_len(markup)..];
+        let len = markup
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(markup.len());
+        let tag_name = &markup[..len];
+        if Some(tag_name) == nested_by {
+            nesting += 1;
+        } else if tag_name == end_tag {
+            if nesting == 0 {
+                // Whitespace trimmed by `{%-` belongs to the end tag.
+                return Some(if text[tag..].starts_with("{%-") {
+                    tag - trailing_whitespace_len(&text[start..tag])
+                } else {
+                    tag
+                });
+            }
+            nesting -= 1;
+        }
+    }
+    Some(text.len())
+}
+
+/// The length of the whitespace, as the grammar sees it, ending `text`.
+fn trailing_whitespace_len(text: &str) -> usize {
+    let mut len = 0;
+    loop {
+        let rest = &text[..text.len() - len];
+        if rest.ends_with("&nbsp;") {
+            len += "&nbsp;".len();
+        } else if let Some(c) = rest
+            .chars()
+            .next_back()
+            .filter(|c| matches!(c, ' ' | '\t' | '\n' | '\r' | '\u{a0}'))
+        {
+            len += c.len_utf8();
+        } else {
+            return len;
+        }
+    }
+}
+
+/// Where the markup starting at `start` that failed to lex ends.
+fn invalid_end(text: &str, start: usize) -> usize {
+    let markup = start + whitespace_len(&text[start..]);
+    let close = if text[markup..].starts_with("{{") {
+        "}}"
+    } else if text[markup..].starts_with("{%") {
+        "%}"
+    } else {
+        let len = text[start..]
+            .chars()
+            .next()
+            .map(char::len_utf8)
+            .unwrap_or(1);
+        return start + len;
+    };
+    text[markup + 2..]
+        .find(close)
+        .map(|i| markup + 2 + i + close.len())
+        .unwrap_or(text.len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(text: &str) -> SyntaxTree<'_> {
+        parse_cst(text, &Language::default())
+    }
+
+    fn tokens(text: &str) -> Vec<(SyntaxKind, &str)> {
+        let tree = parse(text);
+        tree.tokens()
+            .into_iter()
+            .map(|token| (token.kind(), tree.node_text(token)))
+            .collect()
+    }
+
+    fn assert_lossless(text: &str) {
+        let tree = parse(text);
+        let mut position = 0;
+        for token in tree.tokens() {
+            assert_eq!(token.range().start, position, "{:?}", tree.root());
+            position = token.range().end;
+        }
+        assert_eq!(position, text.len());
+    }
+
+    #[test]
+    fn test_lossless() {
+        for text in [
+            "",
+            "Hello",
+            "a  {%- if x.y[ 'z' ] == empty -%}  b{%endif%}",
+            "{{ items | slice: 1, 2 | join: sep , reverse: true }}",
+            "{% for i in (1..n) limit:2 %}{{ -1.5 }}{% endfor %}",
+            "{{&nbsp;a\u{a0}}}",
+            "{{ }} {% %} after {{ ok }}",
+            "{{ unclosed",
+            "{% liquid\n  assign x = 'é'\n%}",
+            "{{ {\"a\": [1, x ], \"b\":{}}}}",
+            "{{ a | upcase if b >= 2 else c }}",
+            "{{ a | f: ( b < 2 and c ), k: (d) }}",
+            "{% raw %} {{ }} {%- endraw -%} {% comment %}{% endcomment %}",
+        ] {
+            assert_lossless(text);
+        }
+    }
+
+    #[test]
+    fn test_tag_tokens() {
+        use SyntaxKind::*;
+
+        assert_eq!(
+            tokens(" {%- assign x = a[0] | plus: 1 %}"),
+            [
+                (Whitespace, " "),
+                (TagOpen, "{%"),
+                (Trim, "-"),
+                (Whitespace, " "),
+                (TagName, "assign"),
+                (Whitespace, " "),
+                (Identifier, "x"),
+                (Whitespace, " "),
+                (Assign, "="),
+                (Whitespace, " "),
+                (Identifier, "a"),
+                (LeftBracket, "["),
+                (Integer, "0"),
+                (RightBracket, "]"),
+                (Whitespace, " "),
+                (Pipe, "|"),
+                (Whitespace, " "),
+                (FilterName, "plus"),
+                (Colon, ":"),
+                (Whitespace, " "),
+                (Integer, "1"),
+                (Whitespace, " "),
+                (TagClose, "%}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        use SyntaxKind::*;
+
+        let text = r#"{{ "a\"b" }}"#;
+        let options = Language {
+            string_escapes: true,
+            ..Default::default()
+        };
+        let tree = parse_cst(text, &options);
+        let tokens: Vec<_> = tree
+            .tokens()
+            .into_iter()
+            .map(|token| (token.kind(), tree.node_text(token)))
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                (OutputOpen, "{{"),
+                (Whitespace, " "),
+                (String, r#""a\"b""#),
+                (Whitespace, " "),
+                (OutputClose, "}}"),
+            ]
+        );
+
+        // Without escapes, the backslash doesn't keep the string open.
+        assert_eq!(parse(text).root().children()[0].kind(), Invalid);
+    }
+
+    #[test]
+    fn test_verbatim_bodies() {
+        use SyntaxKind::*;
+
+        let kinds = |text| -> Vec<_> {
+            let tree = parse(text);
+            tree.root()
+                .children()
+                .iter()
+                .map(|node| (node.kind(), tree.node_text(node)))
+                .collect()
+        };
+        assert_eq!(
+            kinds("{% raw %}{{ }}{% comment %}{% endraw %}x"),
+            [
+                (Tag, "{% raw %}"),
+                (Text, "{{ }}{% comment %}"),
+                (Tag, "{% endraw %}"),
+                (Text, "x"),
+            ]
+        );
+        assert_eq!(
+            kinds("{% comment %}a{% comment %}{{ }}{% endcomment %} {%- endcomment %}"),
+            [
+                (Tag, "{% comment %}"),
+                (Text, "a{% comment %}{{ }}{% endcomment %}"),
+                (Whitespace, " "),
+                (Tag, "{%- endcomment %}"),
+            ]
+        );
+        assert_eq!(
+            kinds("{% raw %}{{ unclosed"),
+            [(Tag, "{% raw %}"), (Text, "{{ unclosed")]
+        );
+    }
+
+    #[test]
+    fn test_invalid_regions() {
+        let tree = parse("a{{ }}b{{ c }}");
+        let kinds: Vec<_> = tree.root().children().iter().map(|n| n.kind()).collect();
+        assert_eq!(
+            kinds,
+            [
+                SyntaxKind::Text,
+                SyntaxKind::Invalid,
+                SyntaxKind::Text,
+                SyntaxKind::Output
+            ]
+        );
+        assert_eq!(tree.node_text(&tree.root().children()[1]), "{{ }}");
+    }
+
+    #[test]
+    fn test_array_and_hash_literals() {
+        let tree = parse("{{ {'a': [x]} }}");
+        let kinds: Vec<_> = tree.path_at(10).iter().map(|n| n.kind()).collect();
+        assert_eq!(
+            kinds,
+            [
+                SyntaxKind::Document,
+                SyntaxKind::Output,
+                SyntaxKind::FilterChain,
+                SyntaxKind::Hash,
+                SyntaxKind::Array,
+                SyntaxKind::Variable,
+                SyntaxKind::Identifier
+            ]
+        );
+    }
+
+    #[test]
+    fn test_path_at() {
+        let tree = parse("x {{ a.b | upcase }}");
+        let kinds: Vec<_> = tree.path_at(7).iter().map(|n| n.kind()).collect();
+        assert_eq!(
+            kinds,
+            [
+                SyntaxKind::Document,
+                SyntaxKind::Output,
+                SyntaxKind::FilterChain,
+                SyntaxKind::Variable,
+                SyntaxKind::Identifier
+            ]
+        );
+    }
+}
@@ -1,8 +1,10 @@
 use std::fmt::{Debug, Display};
 
-use crate::error::Result;
-use crate::model::{Value, ValueView};
-use crate::runtime::{Expression, Runtime};
+use crate::error::{Error, Result};
+use crate::model::{KString, Value, ValueView};
+use crate::runtime::{Expression, Runtime, Strictness, WarningKind};
+
+use super::ParseMode;
 
 /// A structure that holds the information of a single parameter in a filter.
 /// This includes its name, description and whether it is optional or required.
@@ -270,3 +272,53 @@ impl Display for NoopFilter {
         ::std::write!(f, "{}", "noop")
     }
 }
+
+/// A filter that isn't registered.
+///
+/// Used for `ParseMode::Lax`, or with `Language::defer_unknown_filters`.  What rendering it does is
+/// up to `RenderOptions::undefined_filters`, defaulting to failing for `ParseMode::Strict` and
+/// passing the input through for `ParseMode::Lax`.
+#[derive(Debug)]
+pub struct UndefinedFilter {
+    name: KString,
+    mode: ParseMode,
+    available: KString,
+}
+
+impl UndefinedFilter {
+    /// Stand in for the filter called `name`, parsed with `mode`, where the `available` filters
+    /// are listed in the error.
+    pub fn new(name: KString, mode: ParseMode, available: KString) -> Self {
+        Self {
+            name,
+            mode,
+            available,
+        }
+    }
+}
+
+impl Filter for UndefinedFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let strictness = runtime
+            .render_options()
+            .undefined_filters()
+            .unwrap_or(match self.mode {
+                ParseMode::Strict => Strictness::Error,
+                ParseMode::Lax => Strictness::Empty,
+            });
+        if strictness != Strictness::Empty {
+            let error = Error::with_msg("Unknown filter")
+                .context("requested filter", self.name.clone())
+                .context("available filters", self.available.clone());
+            let kind = WarningKind::UndefinedFilter;
+            crate::runtime::handle_strictness(strictness, kind, error, runtime)?;
+        }
+        Ok(input.to_value())
+    }
+}
+
+impl Display for UndefinedFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        ::std::write!(f, "{}", self.name)
+    }
+}
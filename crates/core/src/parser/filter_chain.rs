@@ -2,25 +2,109 @@ use std::fmt;
 use std::io::Write;
 
 use super::Filter;
-use crate::error::{Result, ResultLiquidExt, ResultLiquidReplaceExt};
-use crate::model::{ValueCow, ValueView};
+use crate::error::{ErrorKind, Result, ResultLiquidExt, ResultLiquidReplaceExt};
+use crate::model::{KString, Value, ValueCow, ValueView};
 use crate::runtime::Expression;
 use crate::runtime::Renderable;
 use crate::runtime::Runtime;
+use crate::runtime::SerializeRenderable;
+use crate::runtime::Visitor;
+use crate::runtime::WarningKind;
+use crate::runtime::{current_language, escape_html, handle_strictness, is_safe};
 
-/// A `Value` expression.
+/// A filter, as called within a `FilterChain`.
 #[derive(Debug)]
+pub struct FilterCall {
+    name: KString,
+    positional: Vec<Expression>,
+    keyword: Vec<(KString, Expression)>,
+    filter: Box<dyn Filter>,
+}
+
+impl FilterCall {
+    /// Create a filter call from the arguments it was parsed with.
+    pub fn new(
+        name: KString,
+        positional: Vec<Expression>,
+        keyword: Vec<(KString, Expression)>,
+        filter: Box<dyn Filter>,
+    ) -> Self {
+        Self {
+            name,
+            positional,
+            keyword,
+            filter,
+        }
+    }
+
+    /// The name the filter was called by.
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// The positional arguments, as written in the template.
+    pub fn positional(&self) -> &[Expression] {
+        &self.positional
+    }
+
+    /// The keyword arguments, as written in the template.
+    pub fn keyword(&self) -> &[(KString, Expression)] {
+        &self.keyword
+    }
+
+    /// The filter, ready to evaluate.
+    pub fn filter(&self) -> &dyn Filter {
+        self.filter.as_ref()
+    }
+
+    /// Walk the arguments passed to the filter.
+    pub fn walk<V: Visitor + ?Sized>(&self, visitor: &mut V) {
+        for arg in &self.positional {
+            visitor.visit_expression(arg);
+        }
+        for (_, arg) in &self.keyword {
+            visitor.visit_expression(arg);
+        }
+    }
+}
+
+impl fmt::Display for FilterCall {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.filter)
+    }
+}
+
+/// A `Value` expression.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct FilterChain {
     entry: Expression,
-    filters: Vec<Box<dyn Filter>>,
+    filters: Vec<FilterCall>,
 }
 
 impl FilterChain {
     /// Create a new expression.
-    pub fn new(entry: Expression, filters: Vec<Box<dyn Filter>>) -> Self {
+    pub fn new(entry: Expression, filters: Vec<FilterCall>) -> Self {
         Self { entry, filters }
     }
 
+    /// The value the filters are applied to.
+    pub fn entry(&self) -> &Expression {
+        &self.entry
+    }
+
+    /// The filters applied, in order.
+    pub fn filters(&self) -> &[FilterCall] {
+        &self.filters
+    }
+
+    /// Walk the expressions and filters of the chain.
+    pub fn walk<V: Visitor + ?Sized>(&self, visitor: &mut V) {
+        visitor.visit_expression(&self.entry);
+        for filter in &self.filters {
+            visitor.visit_filter(filter);
+        }
+    }
+
     /// Process `Value` expression within `runtime`'s stack.
     pub fn evaluate<'s>(&'s self, runtime: &'s dyn Runtime) -> Result<ValueCow<'s>> {
         // take either the provided value or the value from the provided variable
@@ -28,15 +112,24 @@ impl FilterChain {
 
         // apply all specified filters
         for filter in &self.filters {
-            entry = ValueCow::Owned(
-                filter
-                    .evaluate(entry.as_view(), runtime)
-                    .trace("Filter error")
-                    .context_key("filter")
-                    .value_with(|| format!("{}", filter).into())
-                    .context_key("input")
-                    .value_with(|| format!("{}", entry.source()).into())?,
-            );
+            runtime.cancellation().check()?;
+            let result = filter
+                .filter
+                .evaluate(entry.as_view(), runtime)
+                .trace("Filter error")
+                .context_key("filter")
+                .value_with(|| format!("{}", filter).into())
+                .context_key("input")
+                .value_with(|| format!("{}", entry.source()).into());
+            entry = match result {
+                Ok(value) => ValueCow::Owned(value),
+                Err(err) if err.kind() == ErrorKind::InvalidInput => {
+                    let strictness = runtime.render_options().filter_type_mismatches();
+                    handle_strictness(strictness, WarningKind::FilterTypeMismatch, err, runtime)?;
+                    ValueCow::Owned(Value::Nil)
+                }
+                Err(err) => return Err(err),
+            };
         }
 
         Ok(entry)
@@ -57,11 +150,73 @@ impl fmt::Display for FilterChain {
 impl Renderable for FilterChain {
     fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
         let entry = self.evaluate(runtime)?;
-        write!(writer, "{}", entry.render()).replace("Failed to render")?;
+        if runtime.auto_escape() && !is_safe(entry.as_view()) {
+            let escaped = escape_html(&entry.render().to_string());
+            write!(writer, "{}", escaped).replace("Failed to render")?;
+        } else {
+            write!(writer, "{}", entry.render()).replace("Failed to render")?;
+        }
         Ok(())
     }
 
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        self.walk(visitor);
+    }
+
     fn is_blank(&self) -> bool {
         false
     }
+
+    fn as_serialize(&self) -> Option<&dyn SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[typetag::serde(name = "output")]
+impl SerializeRenderable for FilterChain {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
+}
+
+/// How a `FilterCall` is serialized; the filter is re-created from its arguments when loaded.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerializedFilterCall {
+    name: KString,
+    positional: Vec<Expression>,
+    keyword: Vec<(KString, Expression)>,
+}
+
+impl serde::Serialize for FilterCall {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedFilterCall {
+            name: self.name.clone(),
+            positional: self.positional.clone(),
+            keyword: self.keyword.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for FilterCall {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error as _;
+
+        let call = SerializedFilterCall::deserialize(deserializer)?;
+        let language = current_language()
+            .ok_or_else(|| D::Error::custom("Filters can only be loaded within `with_language`"))?;
+        let keyword = call
+            .keyword
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.clone()))
+            .collect();
+        super::parser::build_filter_call(
+            call.name.as_str(),
+            call.positional.clone(),
+            keyword,
+            call.name.as_str(),
+            &language,
+        )
+        .map_err(D::Error::custom)
+    }
 }
@@ -0,0 +1,427 @@
+use pest::Parser as _;
+
+use crate::error::Result;
+
+use super::parser::inner::{LiquidParser, Rule};
+use super::parser::lax_file_rule;
+use super::Language;
+
+type Pair<'a> = ::pest::iterators::Pair<'a, Rule>;
+
+const INDENT: &str = "  ";
+
+/// Tags parsed by their enclosing block rather than registered, which sit at the block's depth.
+const BRANCHES: &[&str] = &["else", "elsif", "when"];
+
+/// Re-emits the template source `text` in a consistent style.
+///
+/// Whitespace within `{{ }}` and `{% %}` is normalized.  A tag or output starting a line is
+/// indented by how deeply it is nested in blocks, but only where that indentation is trimmed
+/// anyway: by the element itself starting with `{%-` or `{{-`, or by the one before it ending with
+/// `-%}` or `-}}`.  Other text, whitespace control and the bodies of `raw` and `comment` blocks are
+/// kept as written, as is the `liquid` tag, so the output renders the same as `text`.
+///
+/// `text` must parse with `options`.
+pub fn format(text: &str, options: &Language) -> Result<String> {
+    super::parse(text, options)?;
+
+    let elements = LiquidParser::parse(lax_file_rule(options), text)
+        .expect("Parsing with Rule::LaxLiquidFile should not raise errors, but InvalidLiquid tokens instead.")
+        .next()
+        .expect("Unwrapping LiquidFile to access the elements.")
+        .into_inner();
+
+    let mut formatter = Formatter {
+        options,
+        output: String::with_capacity(text.len()),
+        blocks: Vec::new(),
+        verbatim: None,
+        trimmed_from: None,
+    };
+    for element in elements {
+        formatter.element(element);
+    }
+    Ok(formatter.output)
+}
+
+/// The body of a block kept as written, until its end tag.
+struct Verbatim {
+    /// The block's start tag, for blocks that nest, like `comment`.
+    nested_by: Option<&'static str>,
+    nesting: usize,
+}
+
+struct Formatter<'o> {
+    options: &'o Language,
+    output: String,
+    /// The end tags of the blocks the next element is nested in.
+    blocks: Vec<String>,
+    verbatim: Option<Verbatim>,
+    /// Where the whitespace trimmed by the last element, ending with `-%}` or `-}}`, starts in
+    /// `output`, if nothing was written after it.
+    trimmed_from: Option<usize>,
+}
+
+impl<'o> Formatter<'o> {
+    fn element(&mut self, element: Pair<'_>) {
+        match element.as_rule() {
+            Rule::Tag => self.tag(element),
+            Rule::Expression if self.verbatim.is_none() => self.expression(element),
+            Rule::EOI => {}
+            _ => {
+                self.trimmed_from = None;
+                self.output.push_str(element.as_str());
+            }
+        }
+    }
+
+    fn tag(&mut self, element: Pair<'_>) {
+        let text = element.as_str();
+        let inner = element.into_inner().next().expect("Unwrapping TagInner.");
+        let mut tokens = inner.into_inner();
+        let name = tokens.next().expect("A tag starts with an identifier.");
+        let name = name.as_str();
+
+        if let Some(verbatim) = self.verbatim.as_mut() {
+            if verbatim.nested_by == Some(name) {
+                verbatim.nesting += 1;
+            } else if self.blocks.last().map(String::as_str) == Some(name) {
+                if verbatim.nesting == 0 {
+                    // Indenting the end tag would change the body.
+                    self.verbatim = None;
+                    self.blocks.pop();
+                    let markup = normalize_tag(name, tokens);
+                    self.emit(text, "%", &markup, None);
+                    return;
+                }
+                verbatim.nesting -= 1;
+            }
+            self.trimmed_from = None;
+            self.output.push_str(text);
+            return;
+        }
+
+        let depth = self.blocks.len();
+        if name == "liquid" {
+            // Lines are significant within `liquid`.
+            let body = text.trim_start();
+            self.output.push_str(&text[..text.len() - body.len()]);
+            self.indent(depth, body.starts_with("{%-"));
+            self.trimmed_from = None;
+            self.output.push_str(body);
+            return;
+        }
+        let markup = normalize_tag(name, tokens);
+        if self.blocks.last().map(String::as_str) == Some(name) {
+            self.blocks.pop();
+            self.emit(text, "%", &markup, Some(depth - 1));
+        } else if let Some(block) = self.options.blocks.get(name) {
+            self.emit(text, "%", &markup, Some(depth));
+            self.blocks.push(block.reflection().end_tag().to_owned());
+            let nested_by = match name {
+                "raw" => Some(None),
+                "comment" => Some(Some("comment")),
+                _ => None,
+            };
+            if let Some(nested_by) = nested_by {
+                self.verbatim = Some(Verbatim {
+                    nested_by,
+                    nesting: 0,
+                });
+            }
+        } else if BRANCHES.contains(&name) {
+            self.emit(text, "%", &markup, Some(depth.saturating_sub(1)));
+        } else {
+            self.emit(text, "%", &markup, Some(depth));
+        }
+    }
+
+    fn expression(&mut self, element: Pair<'_>) {
+        let text = element.as_str();
+        let mut tokens = element
+            .into_inner()
+            .next()
+            .expect("Unwrapping ExpressionInner")
+            .into_inner();
+        let chain = tokens
+            .next()
+            .expect("An expression starts with a filterchain.");
+        let mut markup = filter_chain(chain);
+        push_tokens(&mut markup, tokens);
+        let depth = self.blocks.len();
+        self.emit(text, "{", &markup, Some(depth));
+    }
+
+    /// Writes a tag or output as `markup` between `delimiter`s, keeping the whitespace control
+    /// of the source `text`.
+    fn emit(&mut self, text: &str, delimiter: &str, markup: &str, depth: Option<usize>) {
+        let trimmed = text.trim_start();
+        let (leading, trimmed) = text.split_at(text.len() - trimmed.len());
+        let trimmed = trimmed.trim_end();
+        let trailing = &text[leading.len() + trimmed.len()..];
+        let close = if delimiter == "{" { "}" } else { delimiter };
+
+        let trims_leading = trimmed[2..].starts_with('-');
+        self.output.push_str(leading);
+        if let Some(depth) = depth {
+            self.indent(depth, trims_leading);
+        }
+        self.output.push('{');
+        self.output.push_str(delimiter);
+        if trims_leading {
+            self.output.push('-');
+        }
+        self.output.push(' ');
+        self.output.push_str(markup);
+        self.output.push(' ');
+        let trims_trailing = trimmed[..trimmed.len() - 2].ends_with('-');
+        if trims_trailing {
+            self.output.push('-');
+        }
+        self.output.push_str(close);
+        self.output.push('}');
+        self.trimmed_from = trims_trailing.then_some(self.output.len());
+        self.output.push_str(trailing);
+    }
+
+    /// Replaces the indentation of the current line, if nothing else is on it yet and it is
+    /// trimmed when rendering, either because `trims_leading` or by the element before.
+    fn indent(&mut self, depth: usize, trims_leading: bool) {
+        let line_start = self.output.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let trimmed = trims_leading
+            || self
+                .trimmed_from
+                .take()
+                .is_some_and(|from| from <= line_start);
+        if trimmed
+            && self.output[line_start..]
+                .chars()
+                .all(|c| c == ' ' || c == '\t')
+        {
+            self.output.truncate(line_start);
+            for _ in 0..depth {
+                self.output.push_str(INDENT);
+            }
+        }
+    }
+}
+
+fn normalize_tag(name: &str, tokens: pest::iterators::Pairs<'_, Rule>) -> String {
+    let mut markup = name.to_owned();
+    push_tokens(&mut markup, tokens);
+    markup
+}
+
+/// Appends the normalized `tokens` to `markup`, each after a space unless it is a separator.
+fn push_tokens(markup: &mut String, tokens: pest::iterators::Pairs<'_, Rule>) {
+    for token in tokens {
+        match token.as_rule() {
+            Rule::Comma | Rule::Colon => {}
+            _ => markup.push(' '),
+        }
+        match token.as_rule() {
+            Rule::FilterChain => markup.push_str(&filter_chain(token)),
+            Rule::Range => {
+                let mut bounds = token.into_inner();
+                let start = value(bounds.next().expect("A range has a start."));
+                let end = value(bounds.next().expect("A range has an end."));
+                markup.push_str(&format!("({}..{})", start, end));
+            }
+            _ => markup.push_str(token.as_str()),
+        }
+    }
+}
+
+fn filter_chain(chain: Pair<'_>) -> String {
+    let mut parts = chain.into_inner();
+    let mut markup = value(parts.next().expect("A filterchain starts with a value."));
+    for filter in parts {
+        let mut filter = filter.into_inner();
+        markup.push_str(" | ");
+        markup.push_str(filter.next().expect("A filter has a name.").as_str());
+        for (i, argument) in filter.enumerate() {
+            markup.push_str(if i == 0 { ": " } else { ", " });
+            match argument.as_rule() {
+                Rule::KeywordFilterArgument => {
+                    let mut argument = argument.into_inner();
+                    let key = argument.next().expect("A keyword argument has a key.");
+                    let arg = argument.next().expect("A keyword argument has a value.");
+                    markup.push_str(key.as_str());
+                    markup.push_str(": ");
+                    markup.push_str(&filter_argument(arg));
+                }
+                _ => markup.push_str(&filter_argument(
+                    argument
+                        .into_inner()
+                        .next()
+                        .expect("A positional argument is a value."),
+                )),
+            }
+        }
+    }
+    markup
+}
+
+fn filter_argument(argument: Pair<'_>) -> String {
+    if argument.as_rule() != Rule::ConditionArgument {
+        return value(argument);
+    }
+
+    let mut tokens = argument.into_inner();
+    let chain = tokens
+        .next()
+        .expect("A condition starts with a filterchain.");
+    let mut markup = filter_chain(chain);
+    push_tokens(&mut markup, tokens);
+    format!("({})", markup)
+}
+
+fn value(value: Pair<'_>) -> String {
+    let value = value
+        .into_inner()
+        .next()
+        .expect("A value is a literal or variable.");
+    match value.as_rule() {
+        Rule::Variable => {}
+        Rule::ArrayLiteral => {
+            let entries = value.into_inner().map(self::value);
+            return format!("[{}]", itertools::join(entries, ", "));
+        }
+        Rule::HashLiteral => {
+            let entries = value.into_inner().map(|entry| {
+                let mut entry = entry.into_inner();
+                let key = entry.next().expect("A hash entry has a key.");
+                let value = entry.next().expect("A hash entry has a value.");
+                format!("{}: {}", key.as_str(), self::value(value))
+            });
+            return format!("{{{}}}", itertools::join(entries, ", "));
+        }
+        _ => return value.as_str().to_owned(),
+    }
+
+    let mut parts = value.into_inner();
+    let mut markup = parts
+        .next()
+        .expect("A variable starts with an identifier.")
+        .as_str()
+        .to_owned();
+    for part in parts {
+        match part.as_rule() {
+            Rule::Identifier => {
+                markup.push('.');
+                markup.push_str(part.as_str());
+            }
+            _ => {
+                markup.push('[');
+                markup.push_str(&self::value(part));
+                markup.push(']');
+            }
+        }
+    }
+    markup
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::parser::{BlockReflection, ParseBlock, ParseMode, TagBlock, TagTokenIter};
+    use crate::runtime::Renderable;
+
+    #[derive(Copy, Clone, Debug)]
+    struct RawBlock(&'static str, &'static str);
+
+    impl BlockReflection for RawBlock {
+        fn start_tag(&self) -> &str {
+            self.0
+        }
+
+        fn end_tag(&self) -> &str {
+            self.1
+        }
+
+        fn description(&self) -> &str {
+            ""
+        }
+    }
+
+    impl ParseBlock for RawBlock {
+        fn parse(
+            &self,
+            _arguments: TagTokenIter<'_>,
+            mut block: TagBlock<'_, '_>,
+            _options: &Language,
+        ) -> Result<Box<dyn Renderable>> {
+            let raw = block.escape_liquid(false)?;
+            block.assert_empty();
+            Ok(Box::new(super::super::Text::new(raw)))
+        }
+
+        fn reflection(&self) -> &dyn BlockReflection {
+            self
+        }
+    }
+
+    fn options() -> Language {
+        let mut options = Language {
+            mode: ParseMode::Lax,
+            ..Default::default()
+        };
+        options
+            .blocks
+            .register("raw".to_owned(), Box::new(RawBlock("raw", "endraw")));
+        options
+    }
+
+    #[test]
+    fn test_normalizes_outputs() {
+        let options = options();
+        assert_eq!(
+            format("{{x|f:1,k:'a'}} {{-  a[ 0 ].b  -}}", &options).unwrap(),
+            "{{ x | f: 1, k: 'a' }} {{- a[0].b -}}"
+        );
+    }
+
+    #[test]
+    fn test_normalizes_array_and_hash_literals() {
+        let options = options();
+        assert_eq!(
+            format("{{h|f:{ 'a' :[1,x ],\"b\":{}}}}", &options).unwrap(),
+            "{{ h | f: {'a': [1, x], \"b\": {}} }}"
+        );
+    }
+
+    #[test]
+    fn test_normalizes_conditions_in_outputs() {
+        let options = options();
+        assert_eq!(
+            format("{{a|f   if b>=2  else  c}}", &options).unwrap(),
+            "{{ a | f if b >= 2 else c }}"
+        );
+        assert_eq!(
+            format("{{a|f:(b>=2 and c ),k:( d)}}", &options).unwrap(),
+            "{{ a | f: (b >= 2 and c), k: (d) }}"
+        );
+    }
+
+    #[test]
+    fn test_indents_unknown_tags_at_their_depth() {
+        let mut options = options();
+        options
+            .blocks
+            .register("tabs".to_owned(), Box::new(RawBlock("tabs", "endtabs")));
+        assert_eq!(
+            format("{%tabs%}\n{%-tab a%}\n{%-else%}\n{%-endtabs%}", &options).unwrap(),
+            "{% tabs %}\n  {%- tab a %}\n{%- else %}\n{%- endtabs %}"
+        );
+    }
+
+    #[test]
+    fn test_keeps_raw_bodies() {
+        let options = options();
+        assert_eq!(
+            format("{%raw%}\n    {{x|f}}{%   endraw   %}", &options).unwrap(),
+            "{% raw %}\n    {{x|f}}{% endraw %}"
+        );
+    }
+}
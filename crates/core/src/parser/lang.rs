@@ -3,7 +3,7 @@ use super::ParseFilter;
 use super::ParseTag;
 use super::PluginRegistry;
 
-#[derive(Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum ParseMode {
     Strict,
     Lax,
@@ -22,6 +22,13 @@ pub struct Language {
     pub tags: PluginRegistry<Box<dyn ParseTag>>,
     pub filters: PluginRegistry<Box<dyn ParseFilter>>,
     pub mode: ParseMode,
+    /// Whether string literals decode backslash escapes, like `\n` or `\u{e9}`.
+    ///
+    /// Off by default, keeping a backslash as written.
+    pub string_escapes: bool,
+    /// Whether `ParseMode::Strict` leaves unknown filters to `RenderOptions::undefined_filters`,
+    /// rather than failing the parse.
+    pub defer_unknown_filters: bool,
 }
 
 impl Language {
@@ -1,18 +1,28 @@
+mod async_filter;
 mod block;
+mod cst;
 mod filter;
 mod filter_chain;
+mod format;
 mod lang;
+mod node;
 mod parser;
 mod registry;
+mod source_span;
 mod tag;
 mod text;
 
+pub use async_filter::*;
 pub use block::*;
+pub use cst::*;
 pub use filter::*;
 pub use filter_chain::*;
+pub use format::*;
 pub use lang::*;
 pub use parser::*;
 pub use registry::*;
+pub use source_span::*;
 pub use tag::*;
 
+use node::Node;
 use text::Text;
@@ -0,0 +1,111 @@
+use std::io::Write;
+use std::time::Instant;
+
+use crate::error::{Location, Result};
+use crate::model::KString;
+use crate::runtime::Renderable;
+use crate::runtime::Runtime;
+use crate::runtime::SerializeRenderable;
+use crate::runtime::Visitor;
+use crate::runtime::{deserialize_renderable, SerializeElement};
+
+use super::SourceSpan;
+
+/// A tag, block or expression, along with where it came from in the template source.
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct Node {
+    code: Box<str>,
+    span: SourceSpan,
+    #[serde(deserialize_with = "deserialize_renderable")]
+    element: Box<dyn Renderable>,
+}
+
+impl Node {
+    pub(crate) fn new(code: &str, span: SourceSpan, element: Box<dyn Renderable>) -> Self {
+        Self {
+            code: code.trim().into(),
+            span,
+            element,
+        }
+    }
+
+    fn render_element(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+        let location = || {
+            let name = runtime.name().map(|name| KString::from_ref(name.as_str()));
+            Location::new(name, self.span.line(), self.span.column())
+        };
+        let warnings = runtime
+            .warnings()
+            .map(|warnings| (warnings, warnings.len()));
+
+        let result = self.element.render_to(writer, runtime).map_err(|err| {
+            // Nested nodes render first, so the innermost location wins.
+            if err.location().is_some() {
+                return err;
+            }
+            err.with_location(location())
+        });
+        if let Some((warnings, start)) = warnings {
+            warnings.locate(start, location);
+        }
+        result
+    }
+}
+
+impl Renderable for Node {
+    fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+        let Some(profiler) = runtime.profiler() else {
+            return self.render_element(writer, runtime);
+        };
+
+        let partial = runtime.name();
+        profiler.enter(&self.span, &self.code, partial.as_deref());
+        let start = Instant::now();
+        let result = self.render_element(writer, runtime);
+        profiler.leave(start.elapsed());
+        result
+    }
+
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.enter_span(&self.span);
+        self.element.visit(visitor);
+        visitor.leave_span();
+    }
+
+    fn is_blank(&self) -> bool {
+        self.element.is_blank()
+    }
+
+    fn is_text(&self) -> bool {
+        self.element.is_text()
+    }
+
+    fn as_serialize(&self) -> Option<&dyn SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[typetag::serde(name = "node")]
+impl SerializeRenderable for Node {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
+}
+
+impl serde::Serialize for Node {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::{Error as _, SerializeStruct as _};
+
+        if self.element.as_serialize().is_none() {
+            return Err(S::Error::custom(format!(
+                "`{}` doesn't support serialization",
+                self.code
+            )));
+        }
+        let mut node = serializer.serialize_struct("Node", 3)?;
+        node.serialize_field("code", &self.code)?;
+        node.serialize_field("span", &self.span)?;
+        node.serialize_field("element", &SerializeElement(self.element.as_ref()))?;
+        node.end()
+    }
+}
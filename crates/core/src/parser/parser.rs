@@ -4,18 +4,22 @@
 //! but should be ignored for simple usage.
 
 use crate::error::{Error, Result, ResultLiquidExt};
-use crate::model::Value;
+use crate::model::{KString, Value};
 use crate::runtime::Expression;
 use crate::runtime::Renderable;
+use crate::runtime::TagExpression;
 use crate::runtime::Variable;
+use crate::runtime::{BinaryCondition, ComparisonOperator, Condition, ExistenceCondition};
 
+use super::Node;
+use super::SourceSpan;
 use super::Text;
-use super::{Filter, FilterArguments, FilterChain};
+use super::{FilterArguments, FilterCall, FilterChain};
 use super::{Language, ParseMode};
 
 use pest::{Parser, Span};
 
-mod inner {
+pub(super) mod inner {
     #[derive(Parser)]
     #[grammar = "parser/grammar.pest"]
     pub struct LiquidParser;
@@ -24,7 +28,6 @@ mod inner {
 use self::inner::*;
 
 type Pair<'a> = ::pest::iterators::Pair<'a, Rule>;
-type Pairs<'a> = ::pest::iterators::Pairs<'a, Rule>;
 
 /// Converts a `pest::Error` into a `liquid::Error`.
 fn convert_pest_error(err: ::pest::error::Error<Rule>) -> Error {
@@ -55,40 +58,176 @@ fn error_from_span(span: Span, msg: String) -> Error {
     convert_pest_error(pest_error)
 }
 
-/// Generates a `liquid::Error` with the given message pointing to
-/// the pest
-fn error_from_pair(pair: Pair, msg: String) -> Error {
-    let pest_error = ::pest::error::Error::new_from_span(
-        ::pest::error::ErrorVariant::CustomError { message: msg },
-        pair.as_span(),
-    );
-    convert_pest_error(pest_error)
+/// The rule to lex a file with, keeping what can't be parsed as `InvalidLiquid` tokens.
+pub(super) fn lax_file_rule(options: &Language) -> Rule {
+    if options.string_escapes {
+        Rule::EscapedLaxLiquidFile
+    } else {
+        Rule::LaxLiquidFile
+    }
+}
+
+/// The rule to parse a file with, raising errors for what can't be parsed.
+fn file_rule(options: &Language) -> Rule {
+    if options.string_escapes {
+        Rule::EscapedLiquidFile
+    } else {
+        Rule::LiquidFile
+    }
 }
 
 /// Parses the provided &str into a number of Renderable items.
 pub fn parse(text: &str, options: &Language) -> Result<Vec<Box<dyn Renderable>>> {
-    let mut liquid = LiquidParser::parse(Rule::LaxLiquidFile, text)
+    let liquid = LiquidParser::parse(lax_file_rule(options), text)
         .expect("Parsing with Rule::LaxLiquidFile should not raise errors, but InvalidLiquid tokens instead.")
         .next()
-        .expect("Unwrapping LiquidFile to access the elements.")
-        .into_inner();
+        .expect("Unwrapping LiquidFile to access the elements.");
+    if options.string_escapes {
+        check_escapes(liquid.clone())?;
+    }
+    let mut liquid = liquid.into_inner().map(Element::Pair);
+
+    parse_elements(&mut liquid, options)
+}
 
+/// Parses every element up to their end.
+fn parse_elements(
+    elements: &mut dyn Iterator<Item = Element>,
+    options: &Language,
+) -> Result<Vec<Box<dyn Renderable>>> {
     let mut renderables = Vec::new();
 
-    while let Some(element) = liquid.next() {
-        if element.as_rule() == Rule::EOI {
+    while let Some(element) = elements.next() {
+        if element.is_end() {
             break;
         }
 
         renderables.push(BlockElement::parse_pair(
-            element.into(),
-            &mut liquid,
+            element.into_block_element(),
+            elements,
             options,
         )?);
     }
     Ok(renderables)
 }
 
+/// An element to parse: a `Pair` of the template, or a line of a `{% liquid %}` tag.
+enum Element<'a> {
+    Pair(Pair<'a>),
+    Line(Tag<'a>),
+    /// The end of the lines, like `Rule::EOI` for a template.
+    End(::pest::Position<'a>),
+}
+
+impl<'a> Element<'a> {
+    fn is_end(&self) -> bool {
+        match self {
+            Element::Pair(pair) => pair.as_rule() == Rule::EOI,
+            Element::Line(_) => false,
+            Element::End(_) => true,
+        }
+    }
+
+    fn span(&self) -> Span<'a> {
+        match self {
+            Element::Pair(pair) => pair.as_span(),
+            Element::Line(tag) => tag.name.as_span().start_pos().span(&tag.tokens.end),
+            Element::End(position) => position.span(position),
+        }
+    }
+
+    fn into_block_element(self) -> BlockElement<'a> {
+        match self {
+            Element::Pair(pair) => pair.into(),
+            Element::Line(tag) => BlockElement::Tag(tag),
+            Element::End(_) => panic!("The end of the lines can't be converted to BlockElement."),
+        }
+    }
+}
+
+/// Parses the text of a `Pair` with a string literal, without its quotes.
+/// This `Pair` must be `Rule::StringLiteral` or `Rule::EscapedStringLiteral`.
+fn parse_string(literal: Pair) -> String {
+    let is_escaped = literal.as_rule() == Rule::EscapedStringLiteral;
+    let literal = literal.as_str();
+    let trim_quotes = &literal[1..literal.len() - 1];
+
+    if is_escaped {
+        unescape(trim_quotes)
+    } else {
+        trim_quotes.to_owned()
+    }
+}
+
+/// Checks that every `\u{...}` escape within `file` names a character, rather than a surrogate or
+/// a value past `\u{10FFFF}`; the grammar has checked the rest.
+fn check_escapes(file: Pair) -> Result<()> {
+    let literals = file
+        .into_inner()
+        .flatten()
+        .filter(|pair| pair.as_rule() == Rule::EscapedStringLiteral);
+    for literal in literals {
+        let span = literal.as_span();
+        let mut chars = literal.as_str().char_indices();
+        while let Some((start, c)) = chars.next() {
+            if c != '\\' {
+                continue;
+            }
+            if !matches!(chars.next(), Some((_, 'u'))) {
+                continue;
+            }
+            let hex: String = chars
+                .by_ref()
+                .skip(1)
+                .take_while(|(_, c)| *c != '}')
+                .map(|(_, c)| c)
+                .collect();
+            let is_char = u32::from_str_radix(&hex, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .is_some();
+            if !is_char {
+                let start = span.start() + start;
+                let end = start + "\\u{}".len() + hex.len();
+                let escape = Span::new(span.get_input(), start, end)
+                    .expect("The escape is within the literal.");
+                let msg = format!("`{}` is not a valid unicode character", escape.as_str());
+                return Err(error_from_span(escape, msg));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Decodes the backslash escapes of an `EscapedStringLiteral`, which `check_escapes` has checked.
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        let escaped = match chars
+            .next()
+            .expect("Grammar ensures an escape follows `\\`.")
+        {
+            'n' => '\n',
+            't' => '\t',
+            'u' => {
+                let hex: String = chars.by_ref().skip(1).take_while(|c| *c != '}').collect();
+                u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .expect("`check_escapes` ensures escapes name a character.")
+            }
+            quote_or_backslash => quote_or_backslash,
+        };
+        unescaped.push(escaped);
+    }
+    unescaped
+}
+
 /// Given a `Variable` as a string, parses it into a `Variable`.
 pub fn parse_variable(text: &str) -> Result<Variable> {
     let variable = LiquidParser::parse(Rule::Variable, text)
@@ -115,12 +254,7 @@ fn parse_literal(literal: Pair) -> Value {
         Rule::NilLiteral => Value::Nil,
         Rule::EmptyLiteral => Value::State(crate::model::State::Empty),
         Rule::BlankLiteral => Value::State(crate::model::State::Blank),
-        Rule::StringLiteral => {
-            let literal = literal.as_str();
-            let trim_quotes = &literal[1..literal.len() - 1];
-
-            Value::scalar(trim_quotes.to_owned())
-        }
+        Rule::StringLiteral | Rule::EscapedStringLiteral => Value::scalar(parse_string(literal)),
         Rule::IntegerLiteral => Value::scalar(
             literal
                 .as_str()
@@ -184,6 +318,37 @@ fn parse_value(value: Pair) -> Expression {
 
     match value.as_rule() {
         Rule::Literal => Expression::Literal(parse_literal(value)),
+        Rule::ArrayLiteral => {
+            let entries: Vec<_> = value.into_inner().map(parse_value).collect();
+            if entries.iter().all(|e| matches!(e, Expression::Literal(_))) {
+                let array = entries.into_iter().filter_map(Expression::into_literal);
+                Expression::Literal(Value::Array(array.collect()))
+            } else {
+                Expression::Array(entries)
+            }
+        }
+        Rule::HashLiteral => {
+            let entries: Vec<_> = value
+                .into_inner()
+                .map(|entry| {
+                    let mut entry = entry.into_inner();
+                    let key = parse_string(entry.next().expect("A hash entry has a key."));
+                    let value = parse_value(entry.next().expect("A hash entry has a value."));
+                    (KString::from_string(key), value)
+                })
+                .collect();
+            if entries
+                .iter()
+                .all(|(_, e)| matches!(e, Expression::Literal(_)))
+            {
+                let object = entries
+                    .into_iter()
+                    .filter_map(|(k, e)| e.into_literal().map(|v| (k, v)));
+                Expression::Literal(Value::Object(object.collect()))
+            } else {
+                Expression::Object(entries)
+            }
+        }
         Rule::Variable => Expression::Variable(parse_variable_pair(value)),
         _ => unreachable!(),
     }
@@ -191,7 +356,7 @@ fn parse_value(value: Pair) -> Expression {
 
 /// Parses a `FilterCall` from a `Pair` with a filter.
 /// This `Pair` must be `Rule::Filter`.
-fn parse_filter(filter: Pair, options: &Language) -> Result<Box<dyn Filter>> {
+fn parse_filter(filter: Pair, options: &Language) -> Result<FilterCall> {
     if filter.as_rule() != Rule::Filter {
         panic!("Expected a filter.");
     }
@@ -207,57 +372,85 @@ fn parse_filter(filter: Pair, options: &Language) -> Result<Box<dyn Filter>> {
         match arg.as_rule() {
             Rule::PositionalFilterArgument => {
                 let value = arg.into_inner().next().expect("Rule ensures value.");
-                let value = parse_value(value);
+                let value = parse_filter_argument(value)?;
                 positional_args.push(value);
             }
             Rule::KeywordFilterArgument => {
                 let mut arg = arg.into_inner();
                 let key = arg.next().expect("Rule ensures identifier.").as_str();
                 let value = arg.next().expect("Rule ensures value.");
-                let value = parse_value(value);
+                let value = parse_filter_argument(value)?;
                 keyword_args.push((key, value));
             }
             _ => unreachable!(),
         }
     }
 
+    build_filter_call(name, positional_args, keyword_args, filter_str, options)
+}
+
+/// Parses a filter argument: a value, or a condition like `(a < b)`.
+fn parse_filter_argument(argument: Pair) -> Result<Expression> {
+    if argument.as_rule() != Rule::ConditionArgument {
+        return Ok(parse_value(argument));
+    }
+
+    let position = argument.as_span().start_pos();
+    let mut tokens = TagTokenIter::with_position(position, argument.into_inner());
+    let condition = tokens.expect_condition()?;
+    tokens.expect_nothing()?;
+    Ok(Expression::Condition(Box::new(condition)))
+}
+
+/// Create a `FilterCall` to the filter `name` in `options`.
+///
+/// `filter_str` is the filter as written, for error messages.
+pub(crate) fn build_filter_call(
+    name: &str,
+    positional_args: Vec<Expression>,
+    keyword_args: Vec<(&str, Expression)>,
+    filter_str: &str,
+    options: &Language,
+) -> Result<FilterCall> {
     let args = FilterArguments {
-        positional: Box::new(positional_args.into_iter()),
-        keyword: Box::new(keyword_args.into_iter()),
+        positional: Box::new(positional_args.clone().into_iter()),
+        keyword: Box::new(keyword_args.clone().into_iter()),
     };
 
-    match options.mode {
-        ParseMode::Strict => {
-            let f = options.filters.get(name).ok_or_else(|| {
-                let mut available: Vec<_> = options.filters.plugin_names().collect();
-                available.sort_unstable();
-                let available = itertools::join(available, ", ");
-                Error::with_msg("Unknown filter")
+    let f = match options.filters.get(name) {
+        Some(f) => f
+            .parse(args)
+            .trace("Filter parsing error")
+            .context_key("filter")
+            .value_with(|| filter_str.to_string().into())?,
+        None => {
+            let mut available: Vec<_> = options.filters.plugin_names().collect();
+            available.sort_unstable();
+            let available = itertools::join(available, ", ");
+            if options.mode == ParseMode::Strict && !options.defer_unknown_filters {
+                return Err(Error::with_msg("Unknown filter")
                     .context("requested filter", name.to_owned())
-                    .context("available filters", available)
-            })?;
-
-            let f = f
-                .parse(args)
-                .trace("Filter parsing error")
-                .context_key("filter")
-                .value_with(|| filter_str.to_string().into())?;
-
-            Ok(f)
-        }
-        ParseMode::Lax => match options.filters.get(name) {
-            Some(f) => {
-                let f = f
-                    .parse(args)
-                    .trace("Filter parsing error")
-                    .context_key("filter")
-                    .value_with(|| filter_str.to_string().into())?;
-
-                Ok(f)
+                    .context("available filters", available));
             }
-            None => Ok(Box::new(super::NoopFilter {})),
-        },
-    }
+            // Whether this fails is decided when rendering, see `RenderOptions::undefined_filters`.
+            Box::new(super::UndefinedFilter::new(
+                KString::from_ref(name),
+                options.mode,
+                available.into(),
+            ))
+        }
+    };
+
+    let keyword_args = keyword_args
+        .into_iter()
+        .map(|(key, value)| (KString::from_ref(key), value))
+        .collect();
+    Ok(FilterCall::new(
+        KString::from_ref(name),
+        positional_args,
+        keyword_args,
+        f,
+    ))
 }
 
 /// Parses a `FilterChain` from a `Pair` with a filter chain.
@@ -284,7 +477,7 @@ fn parse_filter_chain(chain: Pair, options: &Language) -> Result<FilterChain> {
 pub struct TagBlock<'a: 'b, 'b> {
     start_tag: &'b str,
     end_tag: &'b str,
-    iter: &'b mut dyn Iterator<Item = Pair<'a>>,
+    iter: &'b mut dyn Iterator<Item = Element<'a>>,
     closed: bool,
 }
 
@@ -292,7 +485,7 @@ impl<'a, 'b> TagBlock<'a, 'b> {
     fn new(
         start_tag: &'b str,
         end_tag: &'b str,
-        next_elements: &'b mut dyn Iterator<Item = Pair<'a>>,
+        next_elements: &'b mut dyn Iterator<Item = Element<'a>>,
     ) -> Self {
         TagBlock {
             start_tag,
@@ -316,49 +509,35 @@ impl<'a, 'b> TagBlock<'a, 'b> {
             Error::with_msg("File shouldn't end before EOI.")
         })?;
 
-        if element.as_rule() == Rule::EOI {
-            return error_from_pair(
-                element,
+        if element.is_end() {
+            return error_from_span(
+                element.span(),
                 format!("Unclosed block. {{% {} %}} tag expected.", self.end_tag),
             )
             .into_err();
         }
 
         // Tags are treated separately so as to check for a possible `{% endtag %}`
-        if element.as_rule() == Rule::Tag {
-            let as_str = element.as_str();
-            let mut tag = element
-                .into_inner()
-                .next()
-                .expect("Unwrapping TagInner")
-                .into_inner();
-            let name = tag.next().ok_or_else(||{
-                Error::with_msg("Tags start by their identifier.")
-            })?;
-            let name_str = name.as_str();
-
-            // Check if this tag is the same as the block's reflected end-tag.
-            if name_str == self.end_tag {
-                // Then this is a block ending tag and will close the block.
-
-                // no more arguments should be supplied, trying to supply them is an error
-                if let Some(token) = tag.next() {
-                    return TagToken::from(token).raise_error().into_err();
-                }
+        let mut tag = match element.into_block_element() {
+            BlockElement::Tag(tag) => tag,
+            element => return Ok(Some(element)),
+        };
 
-                self.closed = true;
-                return Ok(None);
-            } else {
-                // Then this is a regular tag
-                let tokens = TagTokenIter::new(&name, tag);
-                return Ok(Some(BlockElement::Tag(Tag {
-                    name,
-                    tokens,
-                    as_str,
-                })));
+        // Check if this tag is the same as the block's reflected end-tag.
+        if tag.name() == self.end_tag {
+            // Then this is a block ending tag and will close the block.
+
+            // no more arguments should be supplied, trying to supply them is an error
+            if let Some(token) = tag.tokens.next() {
+                return token.raise_error().into_err();
             }
+
+            self.closed = true;
+            return Ok(None);
         }
-        Ok(Some(element.into()))
+
+        // Then this is a regular tag
+        Ok(Some(BlockElement::Tag(tag)))
     }
 
     /// Retrieves all the content of this block as a String, regardless of
@@ -388,37 +567,28 @@ impl<'a, 'b> TagBlock<'a, 'b> {
 
         #[allow(clippy::while_let_on_iterator)]
         while let Some(element) = self.iter.next() {
-            let element_as_span = element.as_span();
+            let element_as_span = element.span();
             if start_pos.is_none() {
                 start_pos = Some(element_as_span.start_pos());
             }
 
-            if element.as_rule() == Rule::EOI {
-                return error_from_pair(
-                    element,
+            if element.is_end() {
+                return error_from_span(
+                    element_as_span,
                     format!("Unclosed block. {{% {} %}} tag expected.", self.end_tag),
                 )
                 .into_err();
             }
 
             // Tags are potentially `{% endtag %}`
-            if element.as_rule() == Rule::Tag {
-                let mut tag = element
-                    .into_inner()
-                    .next()
-                    .expect("Unwrapping TagInner")
-                    .into_inner();
-                let name = tag.next()
-                .ok_or_else(||{
-                    Error::with_msg("Tags start by their identifier.")
-                })?;
-                let name_str = name.as_str();
+            if let BlockElement::Tag(mut tag) = element.into_block_element() {
+                let name_str = tag.name();
 
                 // Check if this tag is the same as the block's reflected end-tag.
                 if name_str == self.end_tag {
                     // No more arguments should be supplied. If they are, it is
                     // assumed not to be a tag closer.
-                    if tag.next().is_none() {
+                    if tag.tokens.next().is_none() {
                         nesting_level -= 1;
                         if nesting_level == 0 {
                             self.closed = true;
@@ -452,6 +622,16 @@ impl<'a, 'b> TagBlock<'a, 'b> {
         Ok(renderables)
     }
 
+    /// Parses every element left in the template, ignoring the block's end tag.
+    ///
+    /// This is for blocks that are closed by the end of the template, like `extends`, and so
+    /// must be used at the top level.
+    pub fn parse_to_end(&mut self, options: &Language) -> Result<Vec<Box<dyn Renderable>>> {
+        let renderables = parse_elements(&mut *self.iter, options)?;
+        self.closed = true;
+        Ok(renderables)
+    }
+
     /// Parses the next element in the block just as if it weren't inside any block.
     ///
     /// Returns none if no element is left and raises the same errors as `next()`.
@@ -512,6 +692,8 @@ pub struct Tag<'a> {
     name: Pair<'a>,
     tokens: TagTokenIter<'a>,
     as_str: &'a str,
+    markup: &'a str,
+    span: SourceSpan,
 }
 
 impl<'a> From<Pair<'a>> for Tag<'a> {
@@ -520,18 +702,21 @@ impl<'a> From<Pair<'a>> for Tag<'a> {
             panic!("Only rule Tag can be converted to Tag.");
         }
         let as_str = element.as_str();
-        let mut tag = element
-            .into_inner()
-            .next()
-            .expect("Unwrapping TagInner.")
-            .into_inner();
+        let start = element.as_span().start();
+        let span = SourceSpan::from_pest(&element.as_span());
+        let inner = element.into_inner().next().expect("Unwrapping TagInner.");
+        let markup = inner.as_str();
+        let mut tag = inner.into_inner();
         let name = tag.next().expect("A tag starts with an identifier.");
-        let tokens = TagTokenIter::new(&name, tag);
+        let mut tokens = TagTokenIter::new(&name, tag);
+        tokens.start = start;
 
         Tag {
             name,
             tokens,
             as_str,
+            markup,
+            span,
         }
     }
 }
@@ -549,6 +734,26 @@ impl<'a> Tag<'a> {
         Ok(tag.into())
     }
 
+    /// A tag written on a line of its own, without delimiters, as in the `liquid` tag.
+    fn from_line(tokens: Vec<Pair<'a>>) -> Result<Self> {
+        let mut tokens = tokens.into_iter();
+        let mut first = TagToken::from(tokens.next().expect("A line has at least one token."));
+        let name = match first.unwrap_identifier() {
+            Ok(name) => name,
+            Err(()) => return Err(first.raise_custom_error("Tag name expected.")),
+        };
+        let tokens = TagTokenIter::new(&name, tokens);
+        let span = name.as_span().start_pos().span(&tokens.end);
+
+        Ok(Tag {
+            name,
+            tokens,
+            as_str: span.as_str().trim_end(),
+            markup: span.as_str().trim_end(),
+            span: SourceSpan::from_pest(&span),
+        })
+    }
+
     /// Returns the name of this tag.
     pub fn name(&self) -> &str {
         self.name.as_str()
@@ -581,7 +786,7 @@ impl<'a> Tag<'a> {
     /// The same as `parse`, but directly takes an iterator over `Pair`s instead of a TagBlock.
     fn parse_pair(
         self,
-        next_elements: &mut dyn Iterator<Item = Pair>,
+        next_elements: &mut dyn Iterator<Item = Element>,
         options: &Language,
     ) -> Result<Box<dyn Renderable>> {
         let (name, tokens) = (self.name, self.tokens);
@@ -589,12 +794,13 @@ impl<'a> Tag<'a> {
         let name = name.as_str();
 
         if let Some(plugin) = options.tags.get(name) {
-            plugin.parse(tokens, options)
+            let renderable = plugin.parse(tokens, options)?;
+            Ok(Box::new(Node::new(self.markup, self.span, renderable)))
         } else if let Some(plugin) = options.blocks.get(name) {
             let reflection = plugin.reflection();
             let block = TagBlock::new(reflection.start_tag(), reflection.end_tag(), next_elements);
             let renderables = plugin.parse(tokens, block, options)?;
-            Ok(renderables)
+            Ok(Box::new(Node::new(self.markup, self.span, renderables)))
         } else {
             let pest_error = ::pest::error::Error::new_from_span(
                 ::pest::error::ErrorVariant::CustomError {
@@ -634,17 +840,28 @@ impl<'a> From<Pair<'a>> for Exp<'a> {
 impl<'a> Exp<'a> {
     /// Parses the expression just as if it weren't inside any block.
     pub fn parse(self, options: &Language) -> Result<Box<dyn Renderable>> {
-        let filter_chain = self
+        let span = SourceSpan::from_pest(&self.element.as_span());
+        let inner = self
             .element
             .into_inner()
             .next()
-            .expect("Unwrapping ExpressionInner")
-            .into_inner()
+            .expect("Unwrapping ExpressionInner");
+        let markup = inner.as_str();
+        let mut tokens = inner.clone().into_inner();
+        let filter_chain = tokens
             .next()
-            .expect("An expression consists of one filterchain.");
+            .expect("An expression starts with a filterchain.");
 
-        let filter_chain = parse_filter_chain(filter_chain, options)?;
-        Ok(Box::new(filter_chain))
+        let renderable: Box<dyn Renderable> = if tokens.next().is_none() {
+            Box::new(parse_filter_chain(filter_chain, options)?)
+        } else {
+            let position = inner.as_span().start_pos();
+            let mut tokens = TagTokenIter::with_position(position, inner.into_inner());
+            let expression = tokens.expect_tag_expression(options)?;
+            tokens.expect_nothing()?;
+            Box::new(expression)
+        };
+        Ok(Box::new(Node::new(markup, span, renderable)))
     }
 
     /// Returns the expression as a str.
@@ -667,15 +884,20 @@ impl<'a> InvalidLiquidToken<'a> {
 
     /// Tries to parse this as valid liquid, which will inevitably raise an error.
     /// This is needed in order to raise the right error message.
-    pub fn parse(self, tag_block: &mut TagBlock) -> Result<Box<dyn Renderable>> {
-        self.parse_pair(&mut tag_block.iter)
+    pub fn parse(
+        self,
+        tag_block: &mut TagBlock,
+        options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        self.parse_pair(&mut tag_block.iter, file_rule(options))
     }
 
     /// Tries to parse this as valid liquid, which will inevitably raise an error.
     /// This is needed in order to raise the correct error message.
     fn parse_pair(
         self,
-        next_elements: &mut dyn Iterator<Item = Pair>,
+        next_elements: &mut dyn Iterator<Item = Element>,
+        file_rule: Rule,
     ) -> Result<Box<dyn Renderable>> {
         use pest::error::LineColLocation;
         let invalid_token_span = self.element.as_span();
@@ -687,7 +909,7 @@ impl<'a> InvalidLiquidToken<'a> {
             .find(|i| invalid_token_position.line_of().is_char_boundary(*i))
             .unwrap_or(0);
         let end_position = match next_elements.last() {
-            Some(element) => element.as_span().end_pos(),
+            Some(element) => element.span().end_pos(),
             None => invalid_token_span.end_pos(),
         };
 
@@ -700,7 +922,7 @@ impl<'a> InvalidLiquidToken<'a> {
 
         // Reparses from the line where invalid liquid started, in order
         // to raise the error.
-        let mut error = match LiquidParser::parse(Rule::LiquidFile, &text) {
+        let mut error = match LiquidParser::parse(file_rule, &text) {
             Ok(_) => {
                 let span = invalid_token_position.span(&invalid_token_position);
                 return error_from_span(
@@ -769,21 +991,23 @@ impl<'a> BlockElement<'a> {
             BlockElement::Raw(raw) => Ok(raw.into_renderable()),
             BlockElement::Tag(tag) => tag.parse(block, options),
             BlockElement::Expression(exp) => exp.parse(options),
-            BlockElement::Invalid(invalid) => invalid.parse(block),
+            BlockElement::Invalid(invalid) => {
+                invalid.parse_pair(&mut block.iter, file_rule(options))
+            }
         }
     }
 
     /// The same as `parse`, but directly takes an iterator over `Pair`s instead of a TagBlock.
     fn parse_pair(
         self,
-        next_elements: &mut dyn Iterator<Item = Pair>,
+        next_elements: &mut dyn Iterator<Item = Element>,
         options: &Language,
     ) -> Result<Box<dyn Renderable>> {
         match self {
             BlockElement::Raw(raw) => Ok(raw.into_renderable()),
             BlockElement::Tag(tag) => tag.parse_pair(next_elements, options),
             BlockElement::Expression(exp) => exp.parse(options),
-            BlockElement::Invalid(invalid) => invalid.parse_pair(next_elements),
+            BlockElement::Invalid(invalid) => invalid.parse_pair(next_elements, file_rule(options)),
         }
     }
 
@@ -802,8 +1026,11 @@ impl<'a> BlockElement<'a> {
 ///
 /// The awareness of the position allows more precise error messages.
 pub struct TagTokenIter<'a> {
-    iter: Box<dyn Iterator<Item = TagToken<'a>> + 'a>,
+    iter: std::iter::Peekable<Box<dyn Iterator<Item = TagToken<'a>> + 'a>>,
+    /// Where the tag starts in its source, including any whitespace trimmed by `{%-`.
+    start: usize,
     position: ::pest::Position<'a>,
+    end: ::pest::Position<'a>,
 }
 impl<'a> Iterator for TagTokenIter<'a> {
     type Item = TagToken<'a>;
@@ -815,13 +1042,82 @@ impl<'a> Iterator for TagTokenIter<'a> {
     }
 }
 impl<'a> TagTokenIter<'a> {
-    fn new(name: &Pair<'a>, tokens: Pairs<'a>) -> Self {
+    fn new<I>(name: &Pair<'a>, tokens: I) -> Self
+    where
+        I: Iterator<Item = Pair<'a>> + Clone + 'a,
+    {
+        Self::with_position(name.as_span().end_pos(), tokens)
+    }
+
+    fn with_position<I>(position: ::pest::Position<'a>, tokens: I) -> Self
+    where
+        I: Iterator<Item = Pair<'a>> + Clone + 'a,
+    {
+        let end = tokens
+            .clone()
+            .last()
+            .map(|token| token.as_span().end_pos())
+            .unwrap_or(position);
+        let iter: Box<dyn Iterator<Item = TagToken<'a>> + 'a> =
+            Box::new(tokens.map(TagToken::from));
         TagTokenIter {
-            iter: Box::new(tokens.map(TagToken::from)),
-            position: name.as_span().end_pos(),
+            iter: iter.peekable(),
+            start: position.pos(),
+            position,
+            end,
         }
     }
 
+    /// Whether the tag starts its source, with nothing before it but the whitespace trimmed by
+    /// `{%-`.
+    ///
+    /// This is for tags that must come first, like `extends`.
+    pub fn starts_source(&self) -> bool {
+        self.start == 0
+    }
+
+    /// The source of the tokens not consumed yet, including the whitespace between them.
+    ///
+    /// This is for tags that parse their content on their own.
+    pub fn as_str(&self) -> &'a str {
+        self.position.span(&self.end).as_str().trim()
+    }
+
+    /// Parses the tokens not consumed yet as tags, one per line, like the markup of the `liquid`
+    /// tag.
+    ///
+    /// A block is closed by its end tag on a later line.
+    pub fn parse_lines(self, options: &Language) -> Result<Vec<Box<dyn Renderable>>> {
+        let end = self.end;
+        let lines = self.into_lines()?;
+        let mut elements = lines
+            .into_iter()
+            .map(Element::Line)
+            .chain(std::iter::once(Element::End(end)));
+        parse_elements(&mut elements, options)
+    }
+
+    /// Splits the tokens not consumed yet into tags, one per line.
+    fn into_lines(self) -> Result<Vec<Tag<'a>>> {
+        let mut lines: Vec<Vec<Pair<'a>>> = Vec::new();
+        for TagToken { token, .. } in self.iter {
+            // A token's span may include the whitespace after it, up to the next token.
+            let same_line = lines
+                .last()
+                .and_then(|line| line.last())
+                .is_some_and(|previous| {
+                    let start = previous.as_span().start_pos();
+                    let between = start.span(&token.as_span().start_pos()).as_str();
+                    !between[previous.as_str().trim_end().len()..].contains('\n')
+                });
+            match lines.last_mut() {
+                Some(line) if same_line => line.push(token),
+                _ => lines.push(vec![token]),
+            }
+        }
+        lines.into_iter().map(Tag::from_line).collect()
+    }
+
     /// Creates an error with the given message pointing at the current
     /// position of the iterator.
     pub fn raise_error(&mut self, error_msg: &str) -> Error {
@@ -847,6 +1143,99 @@ impl<'a> TagTokenIter<'a> {
             Ok(())
         }
     }
+
+    /// Returns the next tag token without consuming it.
+    pub fn peek(&mut self) -> Option<&TagToken<'a>> {
+        self.iter.peek()
+    }
+
+    /// Consumes a condition, like `a == b and c`.
+    ///
+    /// `and` and `or` have the same precedence and group to the right.  Tokens after the
+    /// condition, like `else`, are left in the iterator.
+    pub fn expect_condition(&mut self) -> Result<Condition> {
+        let lh = self
+            .expect_next("Value expected.")?
+            .expect_value()
+            .into_result()?;
+        self.condition_from(lh)
+    }
+
+    fn condition_from(&mut self, lh: Expression) -> Result<Condition> {
+        let operator = self
+            .peek()
+            .and_then(|token| ComparisonOperator::from_token(token.as_str()));
+        let mut condition = match operator {
+            Some(operator) => {
+                self.next();
+                let rh = self
+                    .expect_next("Value expected.")?
+                    .expect_value()
+                    .into_result()?;
+                Condition::Binary(BinaryCondition::new(lh, operator, rh))
+            }
+            None => Condition::Existence(ExistenceCondition::new(lh)),
+        };
+
+        let junction = self.peek().map(|token| token.as_str().to_ascii_lowercase());
+        match junction.as_deref() {
+            Some("and") => {
+                self.next();
+                let rh = self.expect_condition()?;
+                condition = Condition::Conjunction(Box::new(rh), Box::new(condition));
+            }
+            Some("or") => {
+                self.next();
+                let rh = self.expect_condition()?;
+                condition = Condition::Disjunction(Box::new(rh), Box::new(condition));
+            }
+            _ => {}
+        }
+
+        Ok(condition)
+    }
+
+    /// Consumes the value of a tag argument: a `FilterChain`, a condition like `a < b`, or an
+    /// inline conditional like `a if b else c`.
+    pub fn expect_tag_expression(&mut self, options: &Language) -> Result<TagExpression> {
+        let token = self.expect_next("FilterChain expected.")?;
+        let next = self.peek().map(|token| token.as_str().to_owned());
+        let is_keyword = |keyword: &str| {
+            next.as_deref()
+                .is_some_and(|s| s.eq_ignore_ascii_case(keyword))
+        };
+        match next.as_deref() {
+            _ if is_keyword("if") => {
+                let if_true = token.expect_filter_chain(options).into_result()?;
+                self.next();
+                let condition = self.expect_condition()?;
+                let if_false = match self.peek().map(TagToken::as_str) {
+                    Some(s) if s.eq_ignore_ascii_case("else") => {
+                        self.next();
+                        Some(Box::new(self.expect_tag_expression(options)?))
+                    }
+                    _ => None,
+                };
+                Ok(TagExpression::Ternary {
+                    if_true,
+                    condition,
+                    if_false,
+                })
+            }
+            Some(next)
+                if is_keyword("and")
+                    || is_keyword("or")
+                    || ComparisonOperator::from_token(next).is_some() =>
+            {
+                let lh = token.expect_value().into_result()?;
+                self.condition_from(lh).map(TagExpression::Condition)
+            }
+            _ => {
+                let chain = token.expect_filter_chain(options).into_result()?;
+                Ok(TagExpression::FilterChain(chain))
+            }
+        }
+    }
 }
 
 /// The result of calling `TagToken`'s `try`.
@@ -1253,18 +1642,65 @@ mod test {
         assert_eq!(output, "5");
     }
 
+    #[test]
+    fn test_string_escapes() {
+        let runtime = RuntimeBuilder::new().build();
+        let text = r#"{{ "a\"b\n" }}{{ 'c\'\\\t\u{e9}' }}"#;
+
+        let options = Language {
+            string_escapes: true,
+            ..Default::default()
+        };
+        let template = parse(text, &options).map(Template::new).unwrap();
+        let output = template.render(&runtime).unwrap();
+        assert_eq!(output, "a\"b\nc'\\\té");
+
+        // Unknown escapes and a trailing backslash are errors.
+        assert!(parse(r#"{{ "\q" }}"#, &options).is_err());
+        assert!(parse(r#"{{ 'C:\' }}"#, &options).is_err());
+
+        // So are escapes of surrogates and of values past the last character.
+        let error = parse("{{ 'a' }}\n{{ 'b\\u{d800}' }}", &options).unwrap_err();
+        let error = error.to_string();
+        assert!(
+            error.contains("`\\u{d800}` is not a valid unicode character"),
+            "{}",
+            error
+        );
+        assert!(error.contains("--> 2:6"), "{}", error);
+        assert!(parse(r#"{{ "\u{110000}" }}"#, &options).is_err());
+        let template = parse(r#"{{ "\\u{110000}" }}"#, &options)
+            .map(Template::new)
+            .unwrap();
+        assert_eq!(template.render(&runtime).unwrap(), r#"\u{110000}"#);
+
+        // Without the option, backslashes are kept.
+        let options = Language::default();
+        let template = parse(r#"{{ 'C:\n\' }}"#, &options)
+            .map(Template::new)
+            .unwrap();
+        let output = template.render(&runtime).unwrap();
+        assert_eq!(output, r#"C:\n\"#);
+    }
+
     #[test]
     fn test_parse_mode_filters() {
         let mut options = Language::default();
-        let text = "{{ exp | undefined }}";
+        let text = "{{ 'exp' | undefined }}";
+        let runtime = RuntimeBuilder::new().build();
 
         options.mode = ParseMode::Strict;
-        let result = parse(text, &options);
-        assert_eq!(result.is_err(), true);
+        let err = parse(text, &options).unwrap_err();
+        assert!(err.to_string().contains("undefined"), "{}", err);
+
+        options.defer_unknown_filters = true;
+        let template = parse(text, &options).map(Template::new).unwrap();
+        let err = template.render(&runtime).unwrap_err();
+        assert!(err.to_string().contains("undefined"), "{}", err);
 
         options.mode = ParseMode::Lax;
-        let result = parse(text, &options);
-        assert_eq!(result.is_err(), false);
+        let template = parse(text, &options).map(Template::new).unwrap();
+        assert_eq!(template.render(&runtime).unwrap(), "exp");
     }
 
     /// Macro implementation of custom block test.
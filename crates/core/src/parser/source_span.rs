@@ -0,0 +1,50 @@
+/// The region of the template source a tag, block or expression was parsed from.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct SourceSpan {
+    start: usize,
+    end: usize,
+    line: usize,
+    column: usize,
+}
+
+impl SourceSpan {
+    #[cfg(test)]
+    pub(crate) fn new(start: usize, end: usize, line: usize, column: usize) -> Self {
+        Self {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    pub(crate) fn from_pest(span: &pest::Span<'_>) -> Self {
+        let (line, column) = span.start_pos().line_col();
+        Self {
+            start: span.start(),
+            end: span.end(),
+            line,
+            column,
+        }
+    }
+
+    /// Byte offset of the start of the element.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Byte offset just past the end of the element.
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// The line the element starts on, starting from 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The column the element starts on, in characters, starting from 1.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
@@ -3,9 +3,10 @@ use std::io::Write;
 use crate::error::{Result, ResultLiquidReplaceExt};
 use crate::runtime::Renderable;
 use crate::runtime::Runtime;
+use crate::runtime::SerializeRenderable;
 
 /// A raw template expression.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub(crate) struct Text {
     text: String,
 }
@@ -30,4 +31,15 @@ impl Renderable for Text {
     fn is_text(&self) -> bool {
         true
     }
+
+    fn as_serialize(&self) -> Option<&dyn SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[typetag::serde(name = "text")]
+impl SerializeRenderable for Text {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
 }
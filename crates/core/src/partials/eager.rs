@@ -98,7 +98,10 @@ where
                 (name.to_owned(), source)
             })
             .collect();
-        let store = EagerStore { store };
+        let store = EagerStore {
+            source: self.source,
+            store,
+        };
         Ok(Box::new(store))
     }
 
@@ -107,13 +110,18 @@ where
     }
 }
 
-struct EagerStore {
+struct EagerStore<S: PartialSource> {
+    source: S,
     store: HashMap<String, Result<sync::Arc<dyn runtime::Renderable>>>,
 }
 
-impl PartialStore for EagerStore {
+impl<S> PartialStore for EagerStore<S>
+where
+    S: PartialSource,
+{
     fn contains(&self, name: &str) -> bool {
-        self.store.contains_key(name)
+        let name = self.source.canonical_name(name);
+        self.store.contains_key(name.as_ref())
     }
 
     fn names(&self) -> Vec<&str> {
@@ -121,11 +129,13 @@ impl PartialStore for EagerStore {
     }
 
     fn try_get(&self, name: &str) -> Option<sync::Arc<dyn Renderable>> {
-        self.store.get(name).and_then(|r| r.clone().ok())
+        let name = self.source.canonical_name(name);
+        self.store.get(name.as_ref()).and_then(|r| r.clone().ok())
     }
 
     fn get(&self, name: &str) -> Result<sync::Arc<dyn Renderable>> {
-        let result = self.store.get(name).ok_or_else(|| {
+        let canonical = self.source.canonical_name(name);
+        let result = self.store.get(canonical.as_ref()).ok_or_else(|| {
             let mut available: Vec<_> = self.names();
             available.sort_unstable();
             let available = itertools::join(available, ", ");
@@ -137,7 +147,10 @@ impl PartialStore for EagerStore {
     }
 }
 
-impl fmt::Debug for EagerStore {
+impl<S> fmt::Debug for EagerStore<S>
+where
+    S: PartialSource,
+{
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.names().fmt(f)
     }
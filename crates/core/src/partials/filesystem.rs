@@ -0,0 +1,351 @@
+use std::borrow;
+use std::collections::HashMap;
+use std::fs;
+use std::path;
+use std::sync;
+use std::sync::OnceLock;
+
+use super::Modified;
+use super::PartialSource;
+
+/// Partial-template source code read from directories on disk.
+///
+/// A name like `product/card` is looked up relative to each root, in order, trying it as-is and
+/// with each of the extensions, so `card` and `card.liquid` both find `card.liquid`.  With
+/// `set_underscore_prefix`, `card` also finds `_card.liquid`.  Names that would leave a root,
+/// through `..` or a symlink, are refused.
+///
+/// For Jekyll, use the site's `_includes` with extensions like `html`, so
+/// `{% include footer.html %}` works.  For Shopify, use the theme's `snippets`, so
+/// `{% render 'card' %}` finds `snippets/card.liquid`.
+///
+/// Partial-templates are read each time they are requested, leaving caching to the
+/// `PartialCompiler`, like `ReloadingCompiler` which checks their modification times.  The
+/// listing of `names` is made once, on first use, and the file a name refers to is looked up
+/// once, when it is first found.
+#[derive(Debug, Clone)]
+pub struct FileSystemSource {
+    roots: Vec<path::PathBuf>,
+    extensions: Vec<String>,
+    underscore_prefix: bool,
+    names: OnceLock<Vec<String>>,
+    found: sync::Arc<sync::RwLock<HashMap<String, (usize, path::PathBuf)>>>,
+}
+
+impl FileSystemSource {
+    /// Read partial-templates from `root`, with the `liquid` extension.
+    pub fn new<P: Into<path::PathBuf>>(root: P) -> Self {
+        Self {
+            roots: vec![root.into()],
+            extensions: vec!["liquid".to_owned()],
+            underscore_prefix: false,
+            names: OnceLock::new(),
+            found: Default::default(),
+        }
+    }
+
+    /// Also read partial-templates from `root`, after the roots already added.
+    pub fn add_root<P: Into<path::PathBuf>>(mut self, root: P) -> Self {
+        self.roots.push(root.into());
+        self.names = OnceLock::new();
+        self.found = Default::default();
+        self
+    }
+
+    /// Only read files with one of these extensions, which may be left off of names.
+    ///
+    /// With no extensions, any file can be read, by its full name.
+    pub fn set_extensions<I, E>(mut self, extensions: I) -> Self
+    where
+        I: IntoIterator<Item = E>,
+        E: Into<String>,
+    {
+        self.extensions = extensions.into_iter().map(|e| e.into()).collect();
+        self.names = OnceLock::new();
+        self.found = Default::default();
+        self
+    }
+
+    /// Whether partial-templates are named like `_card.liquid`, with the `_` left off of names.
+    pub fn set_underscore_prefix(mut self, underscore_prefix: bool) -> Self {
+        self.underscore_prefix = underscore_prefix;
+        self.names = OnceLock::new();
+        self.found = Default::default();
+        self
+    }
+
+    /// Find the file `name` refers to, with the root it was found in.
+    fn resolve(&self, name: &str) -> Option<(&path::Path, path::PathBuf)> {
+        let found = self.found.read().expect("no panics while locked");
+        if let Some((root, relative)) = found.get(name) {
+            return Some((self.roots[*root].as_path(), relative.clone()));
+        }
+        drop(found);
+
+        // Names not found are looked up again, in case the file was added since.
+        let (root, relative) = self.find(name)?;
+        self.found
+            .write()
+            .expect("no panics while locked")
+            .insert(name.to_owned(), (root, relative.clone()));
+        Some((self.roots[root].as_path(), relative))
+    }
+
+    /// Search the roots for the file `name` refers to, with the index of its root.
+    fn find(&self, name: &str) -> Option<(usize, path::PathBuf)> {
+        let relative = path::Path::new(name);
+        let is_relative = relative
+            .components()
+            .all(|c| matches!(c, path::Component::Normal(_)));
+        if name.is_empty() || !is_relative {
+            return None;
+        }
+        let file_name = relative.file_name()?.to_str()?;
+
+        let mut candidates = vec![file_name.to_owned()];
+        candidates.extend(
+            self.extensions
+                .iter()
+                .map(|ext| format!("{}.{}", file_name, ext)),
+        );
+        if self.underscore_prefix && !file_name.starts_with('_') {
+            let prefixed: Vec<_> = candidates.iter().map(|c| format!("_{}", c)).collect();
+            candidates.extend(prefixed);
+        }
+
+        self.roots.iter().enumerate().find_map(|(index, root)| {
+            candidates.iter().find_map(|candidate| {
+                let relative = relative.with_file_name(candidate);
+                if !self.has_extension(&relative) {
+                    return None;
+                }
+                let path = root.join(&relative);
+                if !path.is_file() || !is_within(root, &path) {
+                    return None;
+                }
+                Some((index, relative))
+            })
+        })
+    }
+
+    fn has_extension(&self, relative: &path::Path) -> bool {
+        if self.extensions.is_empty() {
+            return true;
+        }
+        relative
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| self.extensions.iter().any(|ext| ext == e))
+            .unwrap_or(false)
+    }
+
+    /// The shortest name that refers to the file at `relative`.
+    fn partial_name(&self, relative: &path::Path) -> Option<String> {
+        let mut parts = Vec::new();
+        for component in relative.components() {
+            match component {
+                path::Component::Normal(part) => parts.push(part.to_str()?.to_owned()),
+                _ => return None,
+            }
+        }
+        let file_name = parts.last_mut()?;
+        if !self.extensions.is_empty() {
+            let (stem, _) = file_name.rsplit_once('.')?;
+            *file_name = stem.to_owned();
+        }
+        if self.underscore_prefix {
+            if let Some(stem) = file_name.strip_prefix('_') {
+                *file_name = stem.to_owned();
+            }
+        }
+        Some(parts.join("/"))
+    }
+
+    fn list_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for root in &self.roots {
+            let mut dirs = vec![root.clone()];
+            while let Some(dir) = dirs.pop() {
+                let entries = match fs::read_dir(&dir) {
+                    Ok(entries) => entries,
+                    Err(_) => continue,
+                };
+                for entry in entries.filter_map(|e| e.ok()) {
+                    let path = entry.path();
+                    if path.is_dir() {
+                        dirs.push(path);
+                        continue;
+                    }
+                    let name = path
+                        .strip_prefix(root)
+                        .ok()
+                        .filter(|relative| self.has_extension(relative))
+                        .and_then(|relative| self.partial_name(relative));
+                    if let Some(name) = name {
+                        if !names.contains(&name) {
+                            names.push(name);
+                        }
+                    }
+                }
+            }
+        }
+        names.sort_unstable();
+        names
+    }
+}
+
+impl PartialSource for FileSystemSource {
+    fn contains(&self, name: &str) -> bool {
+        self.resolve(name).is_some()
+    }
+
+    fn names(&self) -> Vec<&str> {
+        self.names
+            .get_or_init(|| self.list_names())
+            .iter()
+            .map(|s| s.as_str())
+            .collect()
+    }
+
+    fn canonical_name<'a>(&self, name: &'a str) -> borrow::Cow<'a, str> {
+        self.resolve(name)
+            .and_then(|(_, relative)| self.partial_name(&relative))
+            .map(borrow::Cow::Owned)
+            .unwrap_or_else(|| name.into())
+    }
+
+    fn modified(&self, name: &str) -> Option<Modified> {
+        let (root, relative) = self.resolve(name)?;
+        let metadata = fs::metadata(root.join(relative)).ok()?;
+        Some(Modified {
+            time: metadata.modified().ok()?,
+            len: metadata.len(),
+        })
+    }
+
+    fn try_get<'a>(&'a self, name: &str) -> Option<borrow::Cow<'a, str>> {
+        let (root, relative) = self.resolve(name)?;
+        fs::read_to_string(root.join(relative))
+            .ok()
+            .map(|s| s.into())
+    }
+}
+
+/// Whether `path`, following any symlinks, is inside of `root`.
+fn is_within(root: &path::Path, path: &path::Path) -> bool {
+    match (fs::canonicalize(root), fs::canonicalize(path)) {
+        (Ok(root), Ok(path)) => path.starts_with(root),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::parser::Language;
+    use crate::partials::{EagerCompiler, LazyCompiler, OnDemandCompiler, PartialCompiler};
+    use crate::runtime::{PartialStore, RuntimeBuilder};
+
+    fn fixture() -> tempfile::TempDir {
+        let root = tempfile::tempdir().unwrap();
+        for (file, content) in [
+            ("snippets/card.liquid", "card"),
+            ("snippets/_price.liquid", "price"),
+            ("snippets/product/title.liquid", "title"),
+            ("snippets/notes.txt", "notes"),
+            ("theme/card.liquid", "theme card"),
+            ("theme/badge.liquid", "badge"),
+            ("secret.liquid", "secret"),
+        ] {
+            let path = root.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        root
+    }
+
+    fn source(root: &path::Path) -> FileSystemSource {
+        FileSystemSource::new(root.join("snippets"))
+            .add_root(root.join("theme"))
+            .set_underscore_prefix(true)
+    }
+
+    fn render(store: &dyn PartialStore, name: &str) -> Option<String> {
+        let partial = store.try_get(name)?;
+        let runtime = RuntimeBuilder::new().build();
+        let mut output = Vec::new();
+        partial.render_to(&mut output, &runtime).unwrap();
+        Some(String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn resolves_names() {
+        let fixture = fixture();
+        let root = fixture.path();
+        let source = source(root);
+
+        assert_eq!(source.try_get("card").unwrap(), "card");
+        assert_eq!(source.try_get("card.liquid").unwrap(), "card");
+        assert_eq!(source.try_get("price").unwrap(), "price");
+        assert_eq!(source.try_get("_price.liquid").unwrap(), "price");
+        assert_eq!(source.try_get("product/title").unwrap(), "title");
+        assert_eq!(source.try_get("badge").unwrap(), "badge");
+        assert_eq!(source.canonical_name("_price.liquid"), "price");
+        assert!(!source.contains("notes.txt"));
+        assert!(!source.contains("missing"));
+        assert_eq!(source.names(), ["badge", "card", "price", "product/title"]);
+    }
+
+    #[test]
+    fn refuses_traversal() {
+        let fixture = fixture();
+        let root = fixture.path();
+        let source = source(root);
+
+        assert!(!source.contains("../secret"));
+        assert!(!source.contains("product/../../secret.liquid"));
+        assert!(!source.contains(root.join("secret.liquid").to_str().unwrap()));
+    }
+
+    #[test]
+    fn any_extension() {
+        let fixture = fixture();
+        let root = fixture.path();
+        let source =
+            FileSystemSource::new(root.join("snippets")).set_extensions(Vec::<String>::new());
+
+        assert_eq!(source.try_get("notes.txt").unwrap(), "notes");
+        assert!(!source.contains("notes"));
+        assert!(source.names().contains(&"_price.liquid"));
+    }
+
+    #[test]
+    fn compiles() {
+        let fixture = fixture();
+        let root = fixture.path();
+        let language = sync::Arc::new(Language::empty());
+
+        let stores = [
+            EagerCompiler::new(source(root))
+                .compile(language.clone())
+                .unwrap(),
+            LazyCompiler::new(source(root))
+                .compile(language.clone())
+                .unwrap(),
+            OnDemandCompiler::new(source(root))
+                .compile(language)
+                .unwrap(),
+        ];
+        for store in &stores {
+            assert_eq!(render(store.as_ref(), "card").unwrap(), "card");
+            assert_eq!(render(store.as_ref(), "_price.liquid").unwrap(), "price");
+            assert_eq!(
+                render(store.as_ref(), "product/title.liquid").unwrap(),
+                "title"
+            );
+            assert!(store.contains("badge.liquid"));
+            assert!(render(store.as_ref(), "../secret").is_none());
+        }
+    }
+}
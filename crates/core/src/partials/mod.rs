@@ -1,6 +1,7 @@
 use std::borrow;
 use std::fmt;
 use std::sync;
+use std::time;
 
 use crate::error::Error;
 use crate::error::Result;
@@ -8,14 +9,18 @@ use crate::parser::Language;
 use crate::runtime::PartialStore;
 
 mod eager;
+mod filesystem;
 mod inmemory;
 mod lazy;
 mod ondemand;
+mod reloading;
 
 pub use self::eager::*;
+pub use self::filesystem::*;
 pub use self::inmemory::*;
 pub use self::lazy::*;
 pub use self::ondemand::*;
+pub use self::reloading::*;
 
 /// Compile a `PartialSource` into a `PartialStore` of `Renderable`s.
 ///
@@ -40,6 +45,19 @@ pub trait PartialSource: fmt::Debug {
     /// Enumerate all partial-templates.
     fn names(&self) -> Vec<&str>;
 
+    /// The name, out of `names`, that `name` refers to.
+    ///
+    /// Sources that accept several spellings of a name, like with or without its extension, map
+    /// them to one so compilers only compile and cache each partial-template once.
+    fn canonical_name<'a>(&self, name: &'a str) -> borrow::Cow<'a, str> {
+        name.into()
+    }
+
+    /// When a partial-template last changed, and its length, if the source can tell.
+    fn modified(&self, _name: &str) -> Option<Modified> {
+        None
+    }
+
     /// Access a partial-template.
     fn try_get<'a>(&'a self, name: &str) -> Option<borrow::Cow<'a, str>>;
 
@@ -55,3 +73,12 @@ pub trait PartialSource: fmt::Debug {
         })
     }
 }
+
+/// When a partial-template last changed, see `PartialSource::modified`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Modified {
+    /// The modification time.
+    pub time: time::SystemTime,
+    /// The length of the content, in bytes.
+    pub len: u64,
+}
@@ -0,0 +1,346 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync;
+use std::time;
+
+use crate::error::Result;
+use crate::parser;
+use crate::parser::Language;
+use crate::runtime;
+use crate::runtime::PartialStore;
+use crate::runtime::Renderable;
+
+use super::Modified;
+use super::PartialCompiler;
+use super::PartialSource;
+
+/// A lazily-caching compiler for `PartialSource` that recompiles partial-templates as they change.
+///
+/// This would be useful in cases where:
+/// - Partial-templates are being edited while they're used, like by a preview server.
+///
+/// Each time a partial-template is requested, it is checked against the source and only
+/// recompiled if it changed.  When the source supports `PartialSource::modified`, an unchanged
+/// modification time and length are trusted, unless the partial-template was cached so soon after
+/// that time that a later edit could have kept it; then, and for sources without modification
+/// times, the content is compared to what was compiled.  Renders already using the old version
+/// keep it until they finish.
+///
+/// Note: partial-compilation error reporting is deferred to render-time so content can still be
+/// generated even when the content is in an intermediate-state.
+#[derive(Debug)]
+pub struct ReloadingCompiler<S: PartialSource> {
+    source: S,
+}
+
+impl<S> ReloadingCompiler<S>
+where
+    S: PartialSource,
+{
+    /// Create a reloading compiler for `PartialSource`.
+    pub fn new(source: S) -> Self {
+        ReloadingCompiler { source }
+    }
+}
+
+impl<S> ReloadingCompiler<S>
+where
+    S: PartialSource + Default,
+{
+    /// Create an empty compiler for `PartialSource`.
+    pub fn empty() -> Self {
+        Default::default()
+    }
+}
+
+impl<S> Default for ReloadingCompiler<S>
+where
+    S: PartialSource + Default,
+{
+    fn default() -> Self {
+        Self {
+            source: Default::default(),
+        }
+    }
+}
+
+impl<S> ::std::ops::Deref for ReloadingCompiler<S>
+where
+    S: PartialSource,
+{
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        &self.source
+    }
+}
+
+impl<S> ::std::ops::DerefMut for ReloadingCompiler<S>
+where
+    S: PartialSource,
+{
+    fn deref_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+}
+
+impl<S> PartialCompiler for ReloadingCompiler<S>
+where
+    S: PartialSource + Send + Sync + 'static,
+{
+    fn compile(self, language: sync::Arc<Language>) -> Result<Box<dyn PartialStore + Send + Sync>> {
+        let store = ReloadingStore {
+            language,
+            source: self.source,
+            cache: sync::RwLock::new(Default::default()),
+        };
+        Ok(Box::new(store))
+    }
+
+    fn source(&self) -> &dyn PartialSource {
+        &self.source
+    }
+}
+
+/// How long after a modification time another edit may leave it unchanged, for file systems that
+/// store it coarsely.
+const TICK: time::Duration = time::Duration::from_secs(2);
+
+/// A compiled partial-template, with what it was compiled from.
+struct Compiled {
+    modified: Option<Modified>,
+    /// When the content was last read, before reading it.
+    checked: time::SystemTime,
+    text: String,
+    template: Result<sync::Arc<dyn Renderable>>,
+}
+
+impl Compiled {
+    /// Whether `modified` shows the partial-template is unchanged, without reading it.
+    fn is_current(&self, modified: Option<Modified>) -> bool {
+        match (self.modified, modified) {
+            (Some(cached), Some(modified)) => {
+                cached == modified
+                    && modified
+                        .time
+                        .checked_add(TICK)
+                        .is_some_and(|settled| settled <= self.checked)
+            }
+            _ => false,
+        }
+    }
+}
+
+struct ReloadingStore<S: PartialSource> {
+    language: sync::Arc<Language>,
+    source: S,
+    cache: sync::RwLock<HashMap<String, Compiled>>,
+}
+
+impl<S> ReloadingStore<S>
+where
+    S: PartialSource,
+{
+    fn get_or_compile(&self, name: &str) -> Result<sync::Arc<dyn Renderable>> {
+        let canonical = self.source.canonical_name(name);
+        let canonical = canonical.as_ref();
+
+        let modified = self.source.modified(canonical);
+        {
+            let cache = self.cache.read().expect("not to be poisoned and reused");
+            if let Some(compiled) = cache.get(canonical) {
+                if compiled.is_current(modified) {
+                    return compiled.template.clone();
+                }
+            }
+        }
+
+        let checked = time::SystemTime::now();
+        let text = self.source.get(canonical)?;
+        {
+            let mut cache = self.cache.write().expect("not to be poisoned and reused");
+            if let Some(compiled) = cache.get_mut(canonical) {
+                if compiled.text == text {
+                    compiled.modified = modified;
+                    compiled.checked = checked;
+                    return compiled.template.clone();
+                }
+            }
+        }
+
+        let template = parser::parse(&text, &self.language)
+            .map(runtime::Template::new)
+            .map(sync::Arc::new)
+            .map(|t| t as sync::Arc<dyn Renderable>);
+        let mut cache = self.cache.write().expect("not to be poisoned and reused");
+        cache.insert(
+            canonical.to_owned(),
+            Compiled {
+                modified,
+                checked,
+                text: text.into_owned(),
+                template: template.clone(),
+            },
+        );
+        template
+    }
+}
+
+impl<S> PartialStore for ReloadingStore<S>
+where
+    S: PartialSource,
+{
+    fn contains(&self, name: &str) -> bool {
+        self.source.contains(name)
+    }
+
+    fn names(&self) -> Vec<&str> {
+        self.source.names()
+    }
+
+    fn try_get(&self, name: &str) -> Option<sync::Arc<dyn Renderable>> {
+        if !self.source.contains(name) {
+            return None;
+        }
+        self.get_or_compile(name).ok()
+    }
+
+    fn get(&self, name: &str) -> Result<sync::Arc<dyn Renderable>> {
+        self.get_or_compile(name)
+    }
+}
+
+impl<S> fmt::Debug for ReloadingStore<S>
+where
+    S: PartialSource,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.source.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::borrow;
+    use std::fs;
+
+    use crate::partials::FileSystemSource;
+    use crate::runtime::RuntimeBuilder;
+
+    #[derive(Default, Debug)]
+    struct TestSource {
+        data: sync::Mutex<HashMap<String, String>>,
+    }
+
+    impl TestSource {
+        fn set(&self, name: &str, source: &str) {
+            self.data
+                .lock()
+                .unwrap()
+                .insert(name.to_owned(), source.to_owned());
+        }
+    }
+
+    impl PartialSource for TestSource {
+        fn contains(&self, name: &str) -> bool {
+            self.data.lock().unwrap().contains_key(name)
+        }
+
+        fn names(&self) -> Vec<&str> {
+            vec![]
+        }
+
+        fn canonical_name<'a>(&self, name: &'a str) -> borrow::Cow<'a, str> {
+            match name.strip_suffix(".txt") {
+                Some(_) => name.into(),
+                None => format!("{}.txt", name).into(),
+            }
+        }
+
+        fn try_get<'a>(&'a self, name: &str) -> Option<borrow::Cow<'a, str>> {
+            self.data
+                .lock()
+                .unwrap()
+                .get(name)
+                .cloned()
+                .map(|s| s.into())
+        }
+    }
+
+    fn render(partial: &dyn Renderable) -> String {
+        let runtime = RuntimeBuilder::new().build();
+        let mut output = Vec::new();
+        partial.render_to(&mut output, &runtime).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn store<S: PartialSource>(source: S) -> ReloadingStore<S> {
+        ReloadingStore {
+            language: sync::Arc::new(Language::empty()),
+            source,
+            cache: sync::RwLock::new(Default::default()),
+        }
+    }
+
+    #[test]
+    fn recompiles_changed_content() {
+        let store = store(TestSource::default());
+        store.source.set("example.txt", "first");
+
+        let first = store.get("example.txt").unwrap();
+        assert!(sync::Arc::ptr_eq(
+            &first,
+            &store.get("example.txt").unwrap()
+        ));
+        assert!(sync::Arc::ptr_eq(&first, &store.get("example").unwrap()));
+
+        store.source.set("example.txt", "second");
+        let second = store.get("example.txt").unwrap();
+        assert_eq!(render(second.as_ref()), "second");
+        assert_eq!(render(first.as_ref()), "first");
+
+        assert!(store.try_get("missing.txt").is_none());
+    }
+
+    #[test]
+    fn recompiles_modified_files() {
+        let root = std::env::temp_dir().join(format!("liquid-reloading-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let path = root.join("card.liquid");
+        let set = |content: &str, secs: u64| {
+            fs::write(&path, content).unwrap();
+            let modified = time::UNIX_EPOCH + time::Duration::from_secs(secs);
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        };
+        let store = store(FileSystemSource::new(&root));
+
+        set("first", 1_000);
+        let first = store.get("card").unwrap();
+        assert!(sync::Arc::ptr_eq(
+            &first,
+            &store.get("card.liquid").unwrap()
+        ));
+
+        set("second", 2_000);
+        assert_eq!(render(store.get("card").unwrap().as_ref()), "second");
+        assert_eq!(render(first.as_ref()), "first");
+
+        // An edit within the same tick keeps the modification time, and here the length.
+        let now = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        set("third", now);
+        assert_eq!(render(store.get("card").unwrap().as_ref()), "third");
+        set("fifth", now);
+        assert_eq!(render(store.get("card").unwrap().as_ref()), "fifth");
+    }
+}
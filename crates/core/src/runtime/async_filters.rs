@@ -0,0 +1,87 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::error::Result;
+use crate::model::{BoxFuture, Value, ValueView};
+use crate::parser::AsyncFilter;
+
+use super::join::join_all;
+use super::Runtime;
+
+/// Results of `AsyncFilter`s, carried between the passes of an asynchronous render.
+///
+/// Each pass renders the template synchronously (see `RuntimeBuilder::set_async_filters`).  An
+/// async filter whose result isn't known yet evaluates to `nil` and is queued, for `resolve` to
+/// await, all at once, before the next pass.  A call is matched up with its result by its input
+/// and filter, and by how many times that was called before within the pass, so a call that only
+/// happens in a later pass doesn't invalidate the results of others.
+#[derive(Default)]
+pub struct AsyncFilters {
+    state: RefCell<AsyncFiltersState>,
+}
+
+#[derive(Default)]
+struct AsyncFiltersState {
+    calls: HashMap<String, usize>,
+    resolved: HashMap<String, Vec<Value>>,
+    pending: Vec<(String, BoxFuture<'static, Result<Value>>)>,
+}
+
+impl AsyncFilters {
+    /// Create with no results.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Whether the last pass had every result it asked for, so its output is final.
+    pub fn is_complete(&self) -> bool {
+        self.state.borrow().pending.is_empty()
+    }
+
+    /// Await the filters queued by the last pass, ready for the next.
+    pub async fn resolve(&mut self) -> Result<()> {
+        let state = self.state.get_mut();
+        state.calls.clear();
+        let (keys, futures): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut state.pending).into_iter().unzip();
+        let values = join_all(futures).await;
+        let state = self.state.get_mut();
+        for (key, value) in keys.into_iter().zip(values) {
+            state.resolved.entry(key).or_default().push(value?);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn evaluate(
+        &self,
+        filter: &dyn AsyncFilter,
+        input: &dyn ValueView,
+        runtime: &dyn Runtime,
+    ) -> Result<Value> {
+        let key = format!("{} | {}", input.source(), filter);
+        {
+            let mut state = self.state.borrow_mut();
+            let calls = state.calls.entry(key.clone()).or_default();
+            let index = *calls;
+            *calls += 1;
+            if let Some(value) = state.resolved.get(&key).and_then(|r| r.get(index)) {
+                return Ok(value.clone());
+            }
+        }
+
+        let future = filter.evaluate_async(input, runtime)?;
+        self.state.borrow_mut().pending.push((key, future));
+        Ok(Value::Nil)
+    }
+}
+
+impl fmt::Debug for AsyncFilters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.borrow();
+        f.debug_struct("AsyncFilters")
+            .field("resolved", &state.resolved)
+            .field("pending", &state.pending.len())
+            .finish()
+    }
+}
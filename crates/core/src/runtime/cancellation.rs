@@ -0,0 +1,92 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::error::{Error, ErrorKind, Result};
+
+/// Stops a render early, once a deadline passes or a flag is raised.
+///
+/// Rendering checks for cancellation between loop iterations, partials and filters.
+#[derive(Clone, Debug, Default)]
+pub struct Cancellation {
+    flag: Option<Arc<AtomicBool>>,
+    deadline: Option<Instant>,
+}
+
+impl Cancellation {
+    /// Create a cancellation that never triggers.
+    pub const fn new() -> Self {
+        Self {
+            flag: None,
+            deadline: None,
+        }
+    }
+
+    /// Cancel once `flag` is set to `true`, for example from another thread.
+    pub fn set_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.flag = Some(flag);
+        self
+    }
+
+    /// Cancel once `deadline` has passed.
+    pub fn set_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Whether the render should stop.
+    pub fn is_cancelled(&self) -> bool {
+        let flagged = self
+            .flag
+            .as_ref()
+            .map(|flag| flag.load(Ordering::Relaxed))
+            .unwrap_or(false);
+        let expired = self
+            .deadline
+            .map(|deadline| deadline <= Instant::now())
+            .unwrap_or(false);
+        flagged || expired
+    }
+
+    /// Fail with an `ErrorKind::Cancelled` error if the render should stop.
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Error::with_msg("Render cancelled")
+                .with_kind(ErrorKind::Cancelled)
+                .into_err()
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn default_never_cancels() {
+        assert!(Cancellation::new().check().is_ok());
+    }
+
+    #[test]
+    fn flag_cancels() {
+        let flag = Arc::new(AtomicBool::new(false));
+        let cancellation = Cancellation::new().set_flag(flag.clone());
+        assert!(cancellation.check().is_ok());
+        flag.store(true, Ordering::Relaxed);
+        assert_eq!(
+            cancellation.check().unwrap_err().kind(),
+            ErrorKind::Cancelled
+        );
+    }
+
+    #[test]
+    fn deadline_cancels() {
+        let cancellation = Cancellation::new().set_deadline(Instant::now());
+        assert_eq!(
+            cancellation.check().unwrap_err().kind(),
+            ErrorKind::Cancelled
+        );
+    }
+}
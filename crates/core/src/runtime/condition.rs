@@ -0,0 +1,365 @@
+use std::fmt;
+use std::io::Write;
+
+use crate::error::{Error, Result, ResultLiquidReplaceExt};
+use crate::model::{State, Value, ValueCow, ValueView, ValueViewCmp};
+use crate::parser::FilterChain;
+
+use super::Expression;
+use super::Renderable;
+use super::Runtime;
+use super::SerializeRenderable;
+use super::Visitor;
+
+/// A boolean expression, like `a == b and c`, as used by `if`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Condition {
+    /// A comparison of two values.
+    Binary(BinaryCondition),
+    /// Whether a value is truthy.
+    Existence(ExistenceCondition),
+    /// Both conditions hold.
+    Conjunction(Box<Condition>, Box<Condition>),
+    /// Either condition holds.
+    Disjunction(Box<Condition>, Box<Condition>),
+}
+
+impl Condition {
+    /// Evaluate the condition within `runtime`.
+    pub fn evaluate(&self, runtime: &dyn Runtime) -> Result<bool> {
+        match *self {
+            Condition::Binary(ref c) => c.evaluate(runtime),
+            Condition::Existence(ref c) => c.evaluate(runtime),
+            Condition::Conjunction(ref left, ref right) => {
+                let rt = right.evaluate(runtime)?;
+                let lt = left.evaluate(runtime)?;
+                Ok(rt && lt)
+            }
+            Condition::Disjunction(ref left, ref right) => {
+                let rt = right.evaluate(runtime)?;
+                let lt = left.evaluate(runtime)?;
+                Ok(rt || lt)
+            }
+        }
+    }
+
+    /// Walk the values compared or checked by this condition.
+    pub fn walk<V: Visitor + ?Sized>(&self, visitor: &mut V) {
+        match *self {
+            Condition::Binary(ref c) => {
+                let operator = c.comparison.to_string();
+                visitor.visit_compared(&operator, &c.lh);
+                visitor.visit_compared(&operator, &c.rh);
+            }
+            Condition::Existence(ref c) => visitor.visit_expression(&c.lh),
+            Condition::Conjunction(ref left, ref right)
+            | Condition::Disjunction(ref left, ref right) => {
+                left.walk(visitor);
+                right.walk(visitor);
+            }
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Condition::Binary(ref c) => write!(f, "({})", c),
+            Condition::Existence(ref c) => write!(f, "({})", c),
+            Condition::Conjunction(ref left, ref right) => write!(f, "({} and {})", left, right),
+            Condition::Disjunction(ref left, ref right) => write!(f, "({} or {})", left, right),
+        }
+    }
+}
+
+/// A comparison of two values, like `a < b`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct BinaryCondition {
+    lh: Expression,
+    comparison: ComparisonOperator,
+    rh: Expression,
+}
+
+impl BinaryCondition {
+    /// Create a comparison of `lh` to `rh`.
+    pub fn new(lh: Expression, comparison: ComparisonOperator, rh: Expression) -> Self {
+        Self { lh, comparison, rh }
+    }
+
+    /// The left-hand value.
+    pub fn lh(&self) -> &Expression {
+        &self.lh
+    }
+
+    /// How the values are compared.
+    pub fn comparison(&self) -> ComparisonOperator {
+        self.comparison
+    }
+
+    /// The right-hand value.
+    pub fn rh(&self) -> &Expression {
+        &self.rh
+    }
+
+    /// Evaluate the comparison within `runtime`.
+    pub fn evaluate(&self, runtime: &dyn Runtime) -> Result<bool> {
+        let a = self.lh.evaluate(runtime)?;
+        let ca = ValueViewCmp::new(a.as_view());
+        let b = self.rh.evaluate(runtime)?;
+        let cb = ValueViewCmp::new(b.as_view());
+
+        let result = match self.comparison {
+            ComparisonOperator::Equals => ca == cb,
+            ComparisonOperator::NotEquals => ca != cb,
+            ComparisonOperator::LessThan => ca < cb,
+            ComparisonOperator::GreaterThan => ca > cb,
+            ComparisonOperator::LessThanEquals => ca <= cb,
+            ComparisonOperator::GreaterThanEquals => ca >= cb,
+            ComparisonOperator::Contains => contains_check(a.as_view(), b.as_view())?,
+        };
+        Ok(result)
+    }
+}
+
+impl fmt::Display for BinaryCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.lh, self.comparison, self.rh)
+    }
+}
+
+fn contains_check(a: &dyn ValueView, b: &dyn ValueView) -> Result<bool> {
+    if let Some(a) = a.as_scalar() {
+        let b = b.to_kstr();
+        Ok(a.to_kstr().contains(b.as_str()))
+    } else if let Some(a) = a.as_object() {
+        let b = b.as_scalar();
+        let check = b
+            .map(|b| a.contains_key(b.to_kstr().as_str()))
+            .unwrap_or(false);
+        Ok(check)
+    } else if let Some(a) = a.as_array() {
+        for elem in a.values() {
+            if ValueViewCmp::new(elem) == ValueViewCmp::new(b) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    } else {
+        Err(Error::with_msg(format!(
+            "Expected string | array | object, found `{}`",
+            a.type_name()
+        )))
+    }
+}
+
+/// The operator of a `BinaryCondition`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ComparisonOperator {
+    /// `==`
+    Equals,
+    /// `!=` or `<>`
+    NotEquals,
+    /// `<`
+    LessThan,
+    /// `>`
+    GreaterThan,
+    /// `<=`
+    LessThanEquals,
+    /// `>=`
+    GreaterThanEquals,
+    /// `contains`
+    Contains,
+}
+
+impl ComparisonOperator {
+    /// The operator written as `s`, if any.
+    pub(crate) fn from_token(s: &str) -> Option<Self> {
+        match s {
+            "==" => Some(ComparisonOperator::Equals),
+            "!=" | "<>" => Some(ComparisonOperator::NotEquals),
+            "<" => Some(ComparisonOperator::LessThan),
+            ">" => Some(ComparisonOperator::GreaterThan),
+            "<=" => Some(ComparisonOperator::LessThanEquals),
+            ">=" => Some(ComparisonOperator::GreaterThanEquals),
+            "contains" => Some(ComparisonOperator::Contains),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ComparisonOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let out = match *self {
+            ComparisonOperator::Equals => "==",
+            ComparisonOperator::NotEquals => "!=",
+            ComparisonOperator::LessThanEquals => "<=",
+            ComparisonOperator::GreaterThanEquals => ">=",
+            ComparisonOperator::LessThan => "<",
+            ComparisonOperator::GreaterThan => ">",
+            ComparisonOperator::Contains => "contains",
+        };
+        write!(f, "{}", out)
+    }
+}
+
+/// Whether a value is truthy, like `a` in `a and b`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ExistenceCondition {
+    lh: Expression,
+}
+
+impl ExistenceCondition {
+    /// Create a check of whether `lh` is truthy.
+    pub fn new(lh: Expression) -> Self {
+        Self { lh }
+    }
+
+    /// The value checked.
+    pub fn lh(&self) -> &Expression {
+        &self.lh
+    }
+
+    /// Evaluate the check within `runtime`; a missing value is falsy.
+    pub fn evaluate(&self, runtime: &dyn Runtime) -> Result<bool> {
+        let a = self.lh.try_evaluate(runtime);
+        let a = a.unwrap_or_default();
+        let is_truthy = a.query_state(State::Truthy);
+        Ok(is_truthy)
+    }
+}
+
+impl fmt::Display for ExistenceCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.lh)
+    }
+}
+
+/// The value of a tag argument, like the source of `assign`.
+///
+/// Besides a `FilterChain`, this may be a `Condition`, evaluating to `true` or `false`, or an
+/// inline conditional like `a if c else b`.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub enum TagExpression {
+    /// A value with filters applied, like `a | upcase`.
+    FilterChain(FilterChain),
+    /// A boolean expression, like `a < b`.
+    Condition(Condition),
+    /// `if_true if condition else if_false`; without `else`, it is `nil` when `condition` is false.
+    Ternary {
+        /// The value when `condition` holds.
+        if_true: FilterChain,
+        /// Which value to take.
+        condition: Condition,
+        /// The value otherwise.
+        if_false: Option<Box<TagExpression>>,
+    },
+}
+
+impl TagExpression {
+    /// Walk the expressions, filters and conditions.
+    pub fn walk<V: Visitor + ?Sized>(&self, visitor: &mut V) {
+        match self {
+            TagExpression::FilterChain(chain) => chain.walk(visitor),
+            TagExpression::Condition(condition) => condition.walk(visitor),
+            TagExpression::Ternary {
+                if_true,
+                condition,
+                if_false,
+            } => {
+                condition.walk(visitor);
+                if_true.walk(visitor);
+                if let Some(if_false) = if_false {
+                    if_false.walk(visitor);
+                }
+            }
+        }
+    }
+
+    /// Evaluate within `runtime`.
+    pub fn evaluate<'s>(&'s self, runtime: &'s dyn Runtime) -> Result<ValueCow<'s>> {
+        match self {
+            TagExpression::FilterChain(chain) => chain.evaluate(runtime),
+            TagExpression::Condition(condition) => {
+                let result = condition.evaluate(runtime)?;
+                Ok(ValueCow::Owned(Value::scalar(result)))
+            }
+            TagExpression::Ternary {
+                if_true,
+                condition,
+                if_false,
+            } => {
+                if condition.evaluate(runtime)? {
+                    if_true.evaluate(runtime)
+                } else if let Some(if_false) = if_false {
+                    if_false.evaluate(runtime)
+                } else {
+                    Ok(ValueCow::Owned(Value::Nil))
+                }
+            }
+        }
+    }
+}
+
+impl Renderable for TagExpression {
+    fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+        match self {
+            TagExpression::FilterChain(chain) => chain.render_to(writer, runtime),
+            TagExpression::Condition(condition) => {
+                let result = condition.evaluate(runtime)?;
+                write!(writer, "{}", result).replace("Failed to render")?;
+                Ok(())
+            }
+            TagExpression::Ternary {
+                if_true,
+                condition,
+                if_false,
+            } => {
+                if condition.evaluate(runtime)? {
+                    if_true.render_to(writer, runtime)
+                } else if let Some(if_false) = if_false {
+                    if_false.render_to(writer, runtime)
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        self.walk(visitor);
+    }
+
+    fn is_blank(&self) -> bool {
+        false
+    }
+
+    fn as_serialize(&self) -> Option<&dyn SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[typetag::serde(name = "tag_expression")]
+impl SerializeRenderable for TagExpression {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
+}
+
+impl fmt::Display for TagExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TagExpression::FilterChain(chain) => write!(f, "{}", chain),
+            TagExpression::Condition(condition) => write!(f, "{}", condition),
+            TagExpression::Ternary {
+                if_true,
+                condition,
+                if_false: Some(if_false),
+            } => write!(f, "{} if {} else {}", if_true, condition, if_false),
+            TagExpression::Ternary {
+                if_true,
+                condition,
+                if_false: None,
+            } => write!(f, "{} if {}", if_true, condition),
+        }
+    }
+}
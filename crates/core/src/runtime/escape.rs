@@ -0,0 +1,73 @@
+use crate::model::ValueView;
+
+/// Escape the characters of `text` that are special to HTML.
+pub fn escape_html(text: &str) -> String {
+    escape(text, false)
+}
+
+/// Escape the characters of `text` that are special to HTML, leaving existing entities as-is.
+pub fn escape_html_once(text: &str) -> String {
+    escape(text, true)
+}
+
+/// Whether `value` is a string marked as safe to output as HTML.
+pub fn is_safe(value: &dyn ValueView) -> bool {
+    value
+        .as_scalar()
+        .map(|scalar| scalar.is_safe())
+        .unwrap_or(false)
+}
+
+/// Returns the number of already escaped characters.
+fn nr_escaped(text: &str) -> usize {
+    for prefix in &["lt;", "gt;", "#39;", "quot;", "amp;"] {
+        if text.starts_with(prefix) {
+            return prefix.len();
+        }
+    }
+    0
+}
+
+// The code is adapted from
+// https://github.com/rust-lang/rust/blob/master/src/librustdoc/html/escape.rs
+// Retrieved 2016-11-19.
+fn escape(s: &str, once_p: bool) -> String {
+    let mut result = String::new();
+    let mut last = 0;
+    let mut skip = 0;
+    for (i, c) in s.char_indices() {
+        if skip > 0 {
+            skip -= 1;
+            continue;
+        }
+        match c {
+            '<' | '>' | '\'' | '"' | '&' => {
+                result.push_str(&s[last..i]);
+                last = i + 1;
+                let escaped = match c {
+                    '<' => "&lt;",
+                    '>' => "&gt;",
+                    '\'' => "&#39;",
+                    '"' => "&quot;",
+                    '&' => {
+                        if once_p {
+                            skip = nr_escaped(&s[last..]);
+                        }
+                        if skip == 0 {
+                            "&amp;"
+                        } else {
+                            "&"
+                        }
+                    }
+                    _ => unreachable!(),
+                };
+                result.push_str(escaped);
+            }
+            _ => {}
+        }
+    }
+    if last < s.len() {
+        result.push_str(&s[last..]);
+    }
+    result
+}
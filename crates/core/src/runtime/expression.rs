@@ -1,21 +1,33 @@
 use std::fmt;
 
 use crate::error::Result;
+use crate::model::KString;
 use crate::model::Scalar;
+use crate::model::ScalarCow;
 use crate::model::Value;
 use crate::model::ValueCow;
 use crate::model::ValueView;
 
 use super::variable::Variable;
+use super::Condition;
 use super::Runtime;
+use super::Strictness;
+use super::Visitor;
 
 /// An un-evaluated `Value`.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[non_exhaustive]
 pub enum Expression {
     /// Un-evaluated.
     Variable(Variable),
     /// Evaluated.
-    Literal(Value),
+    Literal(#[serde(with = "super::serialize::literal")] Value),
+    /// An array literal with un-evaluated entries, like `[1, x]`.
+    Array(Vec<Expression>),
+    /// A hash literal with un-evaluated values, like `{"a": x}`.
+    Object(Vec<(KString, Expression)>),
+    /// A condition, evaluating to `true` or `false`, like the filter argument `(a < b)`.
+    Condition(Box<Condition>),
 }
 
 impl Expression {
@@ -28,15 +40,34 @@ impl Expression {
     pub fn into_literal(self) -> Option<Value> {
         match self {
             Expression::Literal(x) => Some(x),
-            Expression::Variable(_) => None,
+            _ => None,
         }
     }
 
     /// Convert into a variable, if possible.
     pub fn into_variable(self) -> Option<Variable> {
         match self {
-            Expression::Literal(_) => None,
             Expression::Variable(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    /// Walk the variables referenced by this expression.
+    pub fn walk<V: Visitor + ?Sized>(&self, visitor: &mut V) {
+        match self {
+            Expression::Literal(_) => {}
+            Expression::Variable(ref x) => visitor.visit_variable(x),
+            Expression::Array(ref entries) => {
+                for entry in entries {
+                    visitor.visit_expression(entry);
+                }
+            }
+            Expression::Object(ref entries) => {
+                for (_, entry) in entries {
+                    visitor.visit_expression(entry);
+                }
+            }
+            Expression::Condition(ref condition) => condition.walk(visitor),
         }
     }
 
@@ -48,6 +79,24 @@ impl Expression {
                 let path = x.try_evaluate(runtime)?;
                 runtime.try_get(&path)
             }
+            Expression::Array(ref entries) => {
+                let array = entries
+                    .iter()
+                    .map(|e| e.try_evaluate(runtime).map(ValueCow::into_owned))
+                    .collect::<Option<_>>()?;
+                Some(ValueCow::Owned(Value::Array(array)))
+            }
+            Expression::Object(ref entries) => {
+                let object = entries
+                    .iter()
+                    .map(|(k, e)| Some((k.clone(), e.try_evaluate(runtime)?.into_owned())))
+                    .collect::<Option<_>>()?;
+                Some(ValueCow::Owned(Value::Object(object)))
+            }
+            Expression::Condition(ref condition) => {
+                let result = condition.evaluate(runtime).ok()?;
+                Some(ValueCow::Owned(Value::scalar(result)))
+            }
         }
     }
 
@@ -58,36 +107,114 @@ impl Expression {
             Expression::Variable(ref x) => {
                 let path = x.evaluate(runtime)?;
 
-                match runtime.render_mode() {
-                    super::RenderingMode::Lax => {
-                        runtime.try_get(&path).unwrap_or_else(|| Value::Nil.into())
+                match runtime.try_get(&path) {
+                    Some(val) => val,
+                    None => {
+                        missing(&path, runtime)?;
+                        Value::Nil.into()
                     }
-                    _ => runtime.get(&path)?,
                 }
             }
+            Expression::Array(ref entries) => {
+                let array = entries
+                    .iter()
+                    .map(|e| e.evaluate(runtime).map(ValueCow::into_owned))
+                    .collect::<Result<_>>()?;
+                ValueCow::Owned(Value::Array(array))
+            }
+            Expression::Object(ref entries) => {
+                let object = entries
+                    .iter()
+                    .map(|(k, e)| Ok((k.clone(), e.evaluate(runtime)?.into_owned())))
+                    .collect::<Result<_>>()?;
+                ValueCow::Owned(Value::Object(object))
+            }
+            Expression::Condition(ref condition) => {
+                ValueCow::Owned(Value::scalar(condition.evaluate(runtime)?))
+            }
         };
         Ok(val)
     }
 }
 
+/// Applies the `RenderOptions` to `path` being missing, as an out-of-range index or an
+/// undefined variable.
+///
+/// The error is only built when it will be returned or recorded.
+fn missing(path: &[ScalarCow<'_>], runtime: &dyn Runtime) -> Result<()> {
+    let options = runtime.render_options();
+    if options.undefined_variables() == Strictness::Empty
+        && options.out_of_range() == Strictness::Empty
+    {
+        return Ok(());
+    }
+
+    let parent = (1..path.len())
+        .rev()
+        .find_map(|end| runtime.try_get(&path[..end]).map(|parent| (end, parent)));
+    let (strictness, kind) = match parent {
+        Some((end, parent)) if parent.as_array().is_some() && path[end].to_integer().is_some() => {
+            (options.out_of_range(), super::WarningKind::OutOfRange)
+        }
+        _ => (
+            options.undefined_variables(),
+            super::WarningKind::UndefinedVariable,
+        ),
+    };
+    if strictness == Strictness::Empty {
+        return Ok(());
+    }
+    match runtime.get(path) {
+        Ok(_) => Ok(()),
+        Err(err) => super::handle_strictness(strictness, kind, err, runtime),
+    }
+}
+
 impl fmt::Display for Expression {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Expression::Literal(ref x) => write!(f, "{}", x.source()),
             Expression::Variable(ref x) => write!(f, "{}", x),
+            Expression::Array(ref entries) => write!(f, "[{}]", itertools::join(entries, ", ")),
+            Expression::Object(ref entries) => {
+                let entries = entries
+                    .iter()
+                    .map(|(k, e)| format!("{}: {}", quote_key(k), e));
+                write!(f, "{{{}}}", itertools::join(entries, ", "))
+            }
+            Expression::Condition(ref condition) => write!(f, "{}", condition),
         }
     }
 }
 
+/// Quote a hash key so it parses back, picking the quote it doesn't contain.
+///
+/// A string literal can't hold both quotes without escapes, so a key with both is escaped, and only
+/// parses back with `Language::string_escapes`.
+fn quote_key(key: &str) -> String {
+    if !key.contains('"') {
+        format!("\"{}\"", key)
+    } else if !key.contains('\'') {
+        format!("'{}'", key)
+    } else {
+        let escaped = key.replace('\\', "\\\\").replace('"', "\\\"");
+        format!("\"{}\"", escaped)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
     use crate::model::Object;
     use crate::model::Value;
+    use crate::parser::{parse, Language};
+    use crate::runtime::Renderable;
     use crate::runtime::RenderingMode;
     use crate::runtime::RuntimeBuilder;
     use crate::runtime::StackFrame;
+    use crate::runtime::Template;
+    use crate::runtime::{RenderOptions, Strictness, Warnings};
 
     #[test]
     fn test_rendering_mode() {
@@ -106,4 +233,80 @@ mod test {
         let runtime = StackFrame::new(&runtime, &globals);
         assert_eq!(expression.evaluate(&runtime).unwrap(), Value::Nil);
     }
+
+    #[test]
+    fn test_render_options() {
+        let globals = crate::object!({ "list": [1, 2] });
+        let index = Expression::Variable(Variable::with_literal("list").push_literal(5i64));
+        let missing = Expression::Variable(Variable::with_literal("test"));
+
+        let options = RenderOptions::new().set_out_of_range(Strictness::Empty);
+        let runtime = RuntimeBuilder::new().set_render_options(options).build();
+        let runtime = StackFrame::new(&runtime, &globals);
+        assert_eq!(index.evaluate(&runtime).unwrap(), Value::Nil);
+        assert!(missing.evaluate(&runtime).is_err());
+
+        let warnings = Warnings::new();
+        let options = RenderOptions::new().set_undefined_variables(Strictness::Warn);
+        let runtime = RuntimeBuilder::new()
+            .set_render_options(options)
+            .set_warnings(&warnings)
+            .build();
+        let runtime = StackFrame::new(&runtime, &globals);
+        assert!(index.evaluate(&runtime).is_err());
+        assert_eq!(missing.evaluate(&runtime).unwrap(), Value::Nil);
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_array_and_object() {
+        let globals = crate::object!({ "x": 1 });
+        let runtime = RuntimeBuilder::new().build();
+        let runtime = StackFrame::new(&runtime, &globals);
+
+        let x = Expression::Variable(Variable::with_literal("x"));
+        let array = Expression::Array(vec![x.clone(), Expression::with_literal("a")]);
+        let object = Expression::Object(vec![("k".into(), array)]);
+        assert_eq!(object.to_string(), r#"{"k": [x, "a"]}"#);
+        assert_eq!(
+            object.evaluate(&runtime).unwrap(),
+            crate::value!({ "k": [1, "a"] })
+        );
+
+        let missing = Expression::Array(vec![Expression::Variable(Variable::with_literal("y"))]);
+        assert!(missing.evaluate(&runtime).is_err());
+        assert_eq!(missing.try_evaluate(&runtime), None);
+    }
+
+    #[test]
+    fn test_object_keys_are_quoted() {
+        let x = Expression::Variable(Variable::with_literal("x"));
+        let object = Expression::Object(vec![
+            (r#"say "hi""#.into(), x.clone()),
+            ("it's".into(), x.clone()),
+        ]);
+        assert_eq!(object.to_string(), r#"{'say "hi"': x, "it's": x}"#);
+        assert_parses_back(&object, &Language::default());
+
+        let object = Expression::Object(vec![(r#"it's "hi""#.into(), x)]);
+        assert_eq!(object.to_string(), r#"{"it's \"hi\"": x}"#);
+        let text = format!("{{{{ {} }}}}", object);
+        assert!(parse(&text, &Language::default()).is_err());
+        let options = Language {
+            string_escapes: true,
+            ..Default::default()
+        };
+        assert_parses_back(&object, &options);
+    }
+
+    fn assert_parses_back(expression: &Expression, options: &Language) {
+        let globals = crate::object!({ "x": 1 });
+        let runtime = RuntimeBuilder::new().build();
+        let runtime = StackFrame::new(&runtime, &globals);
+
+        let text = format!("{{{{ {} }}}}", expression);
+        let template = parse(&text, options).map(Template::new).unwrap();
+        let expected = expression.evaluate(&runtime).unwrap().render().to_string();
+        assert_eq!(template.render(&runtime).unwrap(), expected);
+    }
 }
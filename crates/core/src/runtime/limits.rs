@@ -0,0 +1,269 @@
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::error::{Error, ErrorKind, Result};
+
+/// Bounds on the work a single render may do.
+///
+/// Everything is unbounded by default.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    max_output_bytes: Option<usize>,
+    max_iterations: Option<usize>,
+    max_depth: Option<usize>,
+    max_assign_size: Option<usize>,
+}
+
+impl Limits {
+    /// Create unbounded limits.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Bound the number of bytes rendered, enforced by `LimitedWriter`.
+    ///
+    /// Output rendered into a buffer, like by `capture`, counts as well as what is written out, so
+    /// a captured string written out again is counted twice.
+    pub fn set_max_output_bytes(mut self, max: usize) -> Self {
+        self.max_output_bytes = Some(max);
+        self
+    }
+
+    /// Bound the number of `for` and `tablerow` iterations, across the whole render.
+    pub fn set_max_iterations(mut self, max: usize) -> Self {
+        self.max_iterations = Some(max);
+        self
+    }
+
+    /// Bound how deeply `include` and `render` may nest.
+    pub fn set_max_depth(mut self, max: usize) -> Self {
+        self.max_depth = Some(max);
+        self
+    }
+
+    /// Bound the length, in bytes, of strings stored by `assign` and `capture`.
+    pub fn set_max_assign_size(mut self, max: usize) -> Self {
+        self.max_assign_size = Some(max);
+        self
+    }
+
+    /// Maximum number of bytes rendered.
+    pub fn max_output_bytes(&self) -> Option<usize> {
+        self.max_output_bytes
+    }
+
+    /// Maximum number of loop iterations.
+    pub fn max_iterations(&self) -> Option<usize> {
+        self.max_iterations
+    }
+
+    /// Maximum nesting of partials.
+    pub fn max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    /// Maximum length of an assigned string.
+    pub fn max_assign_size(&self) -> Option<usize> {
+        self.max_assign_size
+    }
+}
+
+/// Tracks a render's usage against its `Limits`.
+///
+/// Only bounded usage is counted, so an unbounded tracker is never modified.
+#[derive(Debug, Default)]
+pub struct LimitTracker {
+    limits: Limits,
+    output: AtomicUsize,
+    iterations: AtomicUsize,
+    depth: AtomicUsize,
+}
+
+impl LimitTracker {
+    /// Start tracking usage against `limits`.
+    pub fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            ..Default::default()
+        }
+    }
+
+    /// Create a tracker that enforces nothing.
+    pub const fn unbounded() -> Self {
+        Self {
+            limits: Limits {
+                max_output_bytes: None,
+                max_iterations: None,
+                max_depth: None,
+                max_assign_size: None,
+            },
+            output: AtomicUsize::new(0),
+            iterations: AtomicUsize::new(0),
+            depth: AtomicUsize::new(0),
+        }
+    }
+
+    /// The limits being enforced.
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// Record `len` bytes of output, failing without recording them if they exceed the limit.
+    fn count_output(&self, len: usize) -> bool {
+        let Some(max) = self.limits.max_output_bytes else {
+            return true;
+        };
+        self.output
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |output| {
+                output.checked_add(len).filter(|output| *output <= max)
+            })
+            .is_ok()
+    }
+
+    /// Record one iteration of a loop.
+    pub fn count_iteration(&self) -> Result<()> {
+        let Some(max) = self.limits.max_iterations else {
+            return Ok(());
+        };
+        let iterations = self.iterations.fetch_add(1, Ordering::Relaxed) + 1;
+        if max < iterations {
+            Err(limit_error("Loop iteration limit exceeded").context("limit", max.to_string()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Record entering a partial, until the returned guard is dropped.
+    pub fn enter_partial(&self) -> Result<DepthGuard<'_>> {
+        let Some(max) = self.limits.max_depth else {
+            return Ok(DepthGuard { depth: None });
+        };
+        let depth = self.depth.load(Ordering::Relaxed) + 1;
+        if max < depth {
+            return Err(
+                limit_error("Partial nesting limit exceeded").context("limit", max.to_string())
+            );
+        }
+        self.depth.store(depth, Ordering::Relaxed);
+        Ok(DepthGuard {
+            depth: Some(&self.depth),
+        })
+    }
+
+    /// Check the length of a string about to be assigned.
+    pub fn check_assign_size(&self, size: usize) -> Result<()> {
+        match self.limits.max_assign_size {
+            Some(max) if max < size => Err(limit_error("Assign size limit exceeded")
+                .context("limit", max.to_string())
+                .context("size", size.to_string())),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Marks a partial as being rendered, see `LimitTracker::enter_partial`.
+#[derive(Debug)]
+pub struct DepthGuard<'t> {
+    depth: Option<&'t AtomicUsize>,
+}
+
+impl<'t> Drop for DepthGuard<'t> {
+    fn drop(&mut self) {
+        if let Some(depth) = self.depth {
+            depth.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// A writer that fails once more than `Limits::max_output_bytes` have been written, counting the
+/// output of every `LimitedWriter` of the render.
+///
+/// Wrap the top-level writer in one, and any buffer a renderable renders into, so output thrown
+/// away is still bounded.
+pub struct LimitedWriter<'w> {
+    writer: &'w mut dyn io::Write,
+    tracker: &'w LimitTracker,
+    exceeded: bool,
+}
+
+impl<'w> LimitedWriter<'w> {
+    /// Bound the output written to `writer` by `tracker`.
+    pub fn new(writer: &'w mut dyn io::Write, tracker: &'w LimitTracker) -> Self {
+        Self {
+            writer,
+            tracker,
+            exceeded: false,
+        }
+    }
+
+    /// Replace the error of a render that wrote to this writer, if it failed for exceeding the
+    /// output limit.
+    pub fn check(&self, result: Result<()>) -> Result<()> {
+        match result {
+            Err(err) if self.exceeded => {
+                let limit = limit_error("Output size limit exceeded");
+                let limit = match err.location() {
+                    Some(location) => limit.with_location(location.clone()),
+                    None => limit,
+                };
+                Err(limit)
+            }
+            result => result,
+        }
+    }
+}
+
+impl<'w> io::Write for LimitedWriter<'w> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.tracker.count_output(buf.len()) {
+            self.exceeded = true;
+            return Err(io::Error::other("output size limit exceeded"));
+        }
+        // Everything is counted up front, so a short write can't be retried.
+        self.writer.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+fn limit_error(msg: &'static str) -> Error {
+    Error::with_msg(msg).with_kind(ErrorKind::ResourceLimit)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::Write;
+
+    #[test]
+    fn depth_is_released() {
+        let tracker = LimitTracker::new(Limits::new().set_max_depth(1));
+        {
+            let _guard = tracker.enter_partial().unwrap();
+            let err = tracker.enter_partial().unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::ResourceLimit);
+        }
+        tracker.enter_partial().unwrap();
+    }
+
+    #[test]
+    fn writer_stops_at_limit() {
+        let mut output = Vec::new();
+        let mut buffer = Vec::new();
+        let tracker = LimitTracker::new(Limits::new().set_max_output_bytes(4));
+        let mut writer = LimitedWriter::new(&mut output, &tracker);
+        writer.write_all(b"abc").unwrap();
+        let mut nested = LimitedWriter::new(&mut buffer, &tracker);
+        assert!(nested.write_all(b"de").is_err());
+        let err = nested
+            .check(Err(Error::with_msg("Failed to render")))
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ResourceLimit);
+        writer.write_all(b"d").unwrap();
+        assert_eq!(output, b"abcd");
+    }
+}
@@ -3,18 +3,45 @@
 #![warn(missing_docs)]
 #![warn(unused_extern_crates)]
 
+mod async_filters;
+mod cancellation;
+mod condition;
+mod escape;
 mod expression;
+mod join;
+mod limits;
+mod options;
+mod output;
 mod partials;
+mod prefetch;
+mod profiler;
 mod renderable;
 mod runtime;
+mod serialize;
 mod stack;
 mod template;
+mod usage;
 mod variable;
+mod visitor;
+mod warnings;
 
+pub use self::async_filters::*;
+pub use self::cancellation::*;
+pub use self::condition::*;
+pub use self::escape::*;
 pub use self::expression::*;
+pub use self::limits::*;
+pub use self::options::*;
+pub use self::output::*;
 pub use self::partials::*;
+pub use self::prefetch::*;
+pub use self::profiler::*;
 pub use self::renderable::*;
 pub use self::runtime::*;
+pub use self::serialize::*;
 pub use self::stack::*;
 pub use self::template::*;
+pub use self::usage::*;
 pub use self::variable::*;
+pub use self::visitor::*;
+pub use self::warnings::*;
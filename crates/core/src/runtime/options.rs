@@ -0,0 +1,253 @@
+use std::cell::RefCell;
+
+use crate::error::{Error, Location, Result};
+
+use super::{Warning, WarningKind};
+
+/// What to do when a render hits a problem that can be recovered from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Strictness {
+    /// Fail the render.
+    Error,
+    /// Carry on, as if the value was `nil`, or for an undefined filter, as if it wasn't there.
+    Empty,
+    /// Like `Empty`, but record a `Warning` in the render's `Warnings`.
+    Warn,
+}
+
+/// How strict a render is about undefined names and bad values.
+///
+/// The default matches `RenderingMode::Strict`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RenderOptions {
+    undefined_variables: Strictness,
+    undefined_filters: Option<Strictness>,
+    out_of_range: Strictness,
+    filter_type_mismatches: Strictness,
+    cycle_mismatches: Strictness,
+}
+
+impl RenderOptions {
+    /// Create options matching `RenderingMode::Strict`.
+    pub const fn new() -> Self {
+        Self {
+            undefined_variables: Strictness::Error,
+            undefined_filters: None,
+            out_of_range: Strictness::Error,
+            filter_type_mismatches: Strictness::Error,
+            cycle_mismatches: Strictness::Error,
+        }
+    }
+
+    /// Create options matching `mode`.
+    pub const fn with_mode(mode: &super::RenderingMode) -> Self {
+        match mode {
+            super::RenderingMode::Strict => Self::new(),
+            super::RenderingMode::Lax => Self {
+                undefined_variables: Strictness::Empty,
+                out_of_range: Strictness::Empty,
+                ..Self::new()
+            },
+        }
+    }
+
+    /// How to treat variables, and object keys, that don't exist.
+    pub fn set_undefined_variables(mut self, strictness: Strictness) -> Self {
+        self.undefined_variables = strictness;
+        self
+    }
+
+    /// How to treat filters that don't exist.
+    ///
+    /// Rather than becoming `nil`, the input is passed through unchanged, like Ruby Liquid.  By
+    /// default, this follows the `ParseMode` of the template: `Strict` is `Strictness::Error`
+    /// and `Lax` is `Strictness::Empty`.
+    ///
+    /// `ParseMode::Strict` fails the parse on unknown filters, so this only applies to it with
+    /// `Language::defer_unknown_filters`.
+    pub fn set_undefined_filters(mut self, strictness: Strictness) -> Self {
+        self.undefined_filters = Some(strictness);
+        self
+    }
+
+    /// How to treat array indexes past either end of the array.
+    pub fn set_out_of_range(mut self, strictness: Strictness) -> Self {
+        self.out_of_range = strictness;
+        self
+    }
+
+    /// How to treat filters given input of the wrong type, see `ErrorKind::InvalidInput`.
+    pub fn set_filter_type_mismatches(mut self, strictness: Strictness) -> Self {
+        self.filter_type_mismatches = strictness;
+        self
+    }
+
+    /// How to treat a `cycle` reusing a name with fewer values, which `Strictness::Empty` restarts.
+    ///
+    /// Unlike the other options, this is an error in `RenderingMode::Lax` too.
+    pub fn set_cycle_mismatches(mut self, strictness: Strictness) -> Self {
+        self.cycle_mismatches = strictness;
+        self
+    }
+
+    /// Set every option to `strictness`.
+    pub fn set_all(self, strictness: Strictness) -> Self {
+        self.set_undefined_variables(strictness)
+            .set_undefined_filters(strictness)
+            .set_out_of_range(strictness)
+            .set_filter_type_mismatches(strictness)
+            .set_cycle_mismatches(strictness)
+    }
+
+    /// How to treat variables that don't exist.
+    pub fn undefined_variables(&self) -> Strictness {
+        self.undefined_variables
+    }
+
+    /// How to treat filters that don't exist, if not following the `ParseMode` of the template.
+    pub fn undefined_filters(&self) -> Option<Strictness> {
+        self.undefined_filters
+    }
+
+    /// How to treat out-of-range array indexes.
+    pub fn out_of_range(&self) -> Strictness {
+        self.out_of_range
+    }
+
+    /// How to treat filters given input of the wrong type.
+    pub fn filter_type_mismatches(&self) -> Strictness {
+        self.filter_type_mismatches
+    }
+
+    /// How to treat a `cycle` reusing a name with fewer values.
+    pub fn cycle_mismatches(&self) -> Strictness {
+        self.cycle_mismatches
+    }
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Problems a render carried on past, for `Strictness::Warn`.
+#[derive(Debug, Default)]
+pub struct Warnings {
+    warnings: RefCell<Vec<Warning>>,
+}
+
+impl Warnings {
+    /// Create an empty collection.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Record a problem.
+    pub fn push(&self, warning: Warning) {
+        self.warnings.borrow_mut().push(warning);
+    }
+
+    /// How many problems were recorded.
+    pub fn len(&self) -> usize {
+        self.warnings.borrow().len()
+    }
+
+    /// Whether any problems were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.warnings.borrow().is_empty()
+    }
+
+    /// Give the problems recorded since the first `start` a location, unless they already have
+    /// one.
+    pub fn locate(&self, start: usize, location: impl Fn() -> Location) {
+        let mut warnings = self.warnings.borrow_mut();
+        for warning in warnings.iter_mut().skip(start) {
+            if warning.location().is_none() {
+                let error = warning.error().clone().with_location(location());
+                *warning = Warning::new(warning.kind(), error);
+            }
+        }
+    }
+
+    /// The recorded problems, in the order they happened.
+    pub fn into_vec(self) -> Vec<Warning> {
+        self.warnings.into_inner()
+    }
+}
+
+/// Apply `strictness` to `error`, returning it only for `Strictness::Error`.
+pub fn handle_strictness(
+    strictness: Strictness,
+    kind: WarningKind,
+    error: Error,
+    runtime: &dyn super::Runtime,
+) -> Result<()> {
+    match strictness {
+        Strictness::Error => Err(error),
+        Strictness::Empty => Ok(()),
+        Strictness::Warn => {
+            warn(kind, error, runtime);
+            Ok(())
+        }
+    }
+}
+
+/// Record a problem, if the render is collecting them.
+pub fn warn(kind: WarningKind, error: Error, runtime: &dyn super::Runtime) {
+    if let Some(warnings) = runtime.warnings() {
+        warnings.push(Warning::new(kind, error));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::runtime::{RenderingMode, RuntimeBuilder};
+
+    #[test]
+    fn lax_mode_forgives_missing_values() {
+        let options = RenderOptions::with_mode(&RenderingMode::Lax);
+        assert_eq!(options.undefined_variables(), Strictness::Empty);
+        assert_eq!(options.out_of_range(), Strictness::Empty);
+        assert_eq!(options.filter_type_mismatches(), Strictness::Error);
+        assert_eq!(options.cycle_mismatches(), Strictness::Error);
+    }
+
+    #[test]
+    fn warn_records_into_runtime() {
+        let warnings = Warnings::new();
+        let runtime = RuntimeBuilder::new().set_warnings(&warnings).build();
+        let kind = WarningKind::UndefinedVariable;
+        handle_strictness(Strictness::Warn, kind, Error::with_msg("one"), &runtime).unwrap();
+        handle_strictness(Strictness::Empty, kind, Error::with_msg("two"), &runtime).unwrap();
+        let three = Error::with_msg("three");
+        assert!(handle_strictness(Strictness::Error, kind, three, &runtime).is_err());
+        drop(runtime);
+
+        let warnings = warnings.into_vec();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind(), kind);
+        assert!(warnings[0].to_string().contains("one"));
+    }
+
+    #[test]
+    fn locate_keeps_innermost() {
+        let warnings = Warnings::new();
+        warnings.push(Warning::new(
+            WarningKind::UndefinedVariable,
+            Error::with_msg("inner"),
+        ));
+        warnings.locate(0, || Location::new(None, 2, 3));
+        warnings.push(Warning::new(
+            WarningKind::OutOfRange,
+            Error::with_msg("outer"),
+        ));
+        warnings.locate(0, || Location::new(None, 1, 1));
+
+        let warnings = warnings.into_vec();
+        assert_eq!(warnings[0].location().unwrap().line(), 2);
+        assert_eq!(warnings[1].location().unwrap().line(), 1);
+    }
+}
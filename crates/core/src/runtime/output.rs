@@ -0,0 +1,140 @@
+use std::fmt;
+use std::io;
+
+/// Renders into a `fmt::Write`, like a `String` or a `fmt::Formatter`.
+///
+/// Writes that end part way through a character are held back until the rest of it arrives.
+pub struct FmtWriter<'w> {
+    writer: &'w mut dyn fmt::Write,
+    partial: Vec<u8>,
+}
+
+impl<'w> FmtWriter<'w> {
+    /// Write the rendered output to `writer`.
+    pub fn new(writer: &'w mut dyn fmt::Write) -> Self {
+        Self {
+            writer,
+            partial: Vec::new(),
+        }
+    }
+
+    fn write_utf8(&mut self, buf: &[u8]) -> io::Result<()> {
+        let (text, rest) = split_utf8(buf)?;
+        self.writer.write_str(text).map_err(io::Error::other)?;
+        self.partial.extend_from_slice(rest);
+        Ok(())
+    }
+}
+
+impl<'w> io::Write for FmtWriter<'w> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.partial.is_empty() {
+            self.write_utf8(buf)?;
+        } else {
+            let mut joined = std::mem::take(&mut self.partial);
+            joined.extend_from_slice(buf);
+            self.write_utf8(&joined)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Collects rendered output, handing it to `on_chunk` each time it is flushed.
+///
+/// Paired with `Template::render_chunked_to`, each chunk is the output of one or more top-level
+/// elements of the template, so a large page can be sent on while the rest is rendered.
+pub struct ChunkedWriter<F> {
+    buffer: Vec<u8>,
+    on_chunk: F,
+}
+
+impl<F> ChunkedWriter<F>
+where
+    F: FnMut(&str) -> io::Result<()>,
+{
+    /// Pass each chunk of output to `on_chunk`.
+    pub fn new(on_chunk: F) -> Self {
+        Self {
+            buffer: Vec::new(),
+            on_chunk,
+        }
+    }
+}
+
+impl<F> io::Write for ChunkedWriter<F>
+where
+    F: FnMut(&str) -> io::Result<()>,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let (chunk, rest) = split_utf8(&self.buffer)?;
+        if !chunk.is_empty() {
+            (self.on_chunk)(chunk)?;
+        }
+        let rest = rest.len();
+        self.buffer.drain(..self.buffer.len() - rest);
+        Ok(())
+    }
+}
+
+/// Split `buf` into its text and any incomplete character at the end.
+fn split_utf8(buf: &[u8]) -> io::Result<(&str, &[u8])> {
+    match std::str::from_utf8(buf) {
+        Ok(text) => Ok((text, &[])),
+        Err(err) if err.error_len().is_none() => {
+            let (text, rest) = buf.split_at(err.valid_up_to());
+            let text = std::str::from_utf8(text).expect("checked by `valid_up_to`");
+            Ok((text, rest))
+        }
+        Err(err) => Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::Write;
+
+    #[test]
+    fn fmt_writer_joins_split_characters() {
+        let mut output = String::new();
+        let mut writer = FmtWriter::new(&mut output);
+        let text = "añb".as_bytes();
+        writer.write_all(&text[..2]).unwrap();
+        writer.write_all(&text[2..]).unwrap();
+        assert_eq!(output, "añb");
+    }
+
+    #[test]
+    fn fmt_writer_rejects_invalid_utf8() {
+        let mut output = String::new();
+        let mut writer = FmtWriter::new(&mut output);
+        assert!(writer.write_all(b"a\xffb").is_err());
+    }
+
+    #[test]
+    fn chunked_writer_hands_off_on_flush() {
+        let mut chunks = Vec::new();
+        let mut writer = ChunkedWriter::new(|chunk: &str| {
+            chunks.push(chunk.to_owned());
+            Ok(())
+        });
+        writer.write_all(b"a").unwrap();
+        writer.write_all(b"b").unwrap();
+        writer.flush().unwrap();
+        writer.flush().unwrap();
+        writer.write_all(b"c").unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+        assert_eq!(chunks, ["ab", "c"]);
+    }
+}
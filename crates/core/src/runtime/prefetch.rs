@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+
+use crate::model::{ArrayView, BoxFuture, KString, KStringCow, Object, ObjectView, State};
+use crate::model::{DisplayCow, Value, ValueCow, ValueView};
+
+use super::join::join_all;
+use super::variable_usage;
+use super::PartialStore;
+use super::PathSegment;
+use super::Renderable;
+
+/// Fetch what `template`, and the partials it names, read from `globals`, through
+/// `ObjectView::get_async`.
+///
+/// The values read are found as by `variable_usage`, so reads through loop variables and partial
+/// arguments are followed back to the globals.  An element read through a loop variable or a
+/// computed index is fetched for every element of the array; a field read through a computed
+/// index can't be fetched.  Only the values `get_async` returns are kept, everything else is read
+/// from `globals` when rendering; the fetches are all awaited at once.
+pub fn prefetch<'g>(
+    template: &dyn Renderable,
+    partials: Option<&dyn PartialStore>,
+    globals: &'g (dyn ObjectView + Sync),
+) -> impl Future<Output = PrefetchedValues> + Send + 'g {
+    let mut paths = PathTree::default();
+    for path in variable_usage(template, partials).paths() {
+        let root = PathSegment::Key(KString::from_ref(path.root()));
+        path.segments()
+            .iter()
+            .fold(paths.child(root), |node, segment| {
+                node.child(segment.clone())
+            });
+    }
+
+    async move {
+        let fetches = {
+            let mut fetches = HashMap::new();
+            start_fetches(globals.as_value(), &paths, &mut Vec::new(), &mut fetches);
+            fetches
+        };
+        let (steps, futures): (Vec<_>, Vec<_>) = fetches.into_iter().unzip();
+        let values = join_all(futures).await;
+        let values = steps
+            .into_iter()
+            .zip(values)
+            .filter_map(|(steps, value)| Some((steps, value?)))
+            .collect();
+        PrefetchedValues { values }
+    }
+}
+
+fn start_fetches<'f>(
+    view: &'f dyn ValueView,
+    paths: &'f PathTree,
+    steps: &mut Vec<Step>,
+    fetches: &mut HashMap<Vec<Step>, BoxFuture<'f, Option<Value>>>,
+) {
+    for (segment, key, children) in &paths.children {
+        if let Some(array) = view.as_array() {
+            for position in positions(array, segment) {
+                if let Some(element) = array.get(position as i64) {
+                    steps.push(Step::Position(position));
+                    start_fetches(element, children, steps, fetches);
+                    steps.pop();
+                }
+            }
+        } else if let Some(object) = view.as_object() {
+            if *segment == PathSegment::Any {
+                continue;
+            }
+            steps.push(Step::Key(key.clone()));
+            if fetches.contains_key(steps.as_slice()) {
+                // Read by another path too, like `products.first` and `products[0]`.
+            } else if let Some(future) = object.get_async(key.as_str()) {
+                fetches.insert(steps.clone(), future);
+            } else if let Some(ValueCow::Borrowed(child)) = object.get(key.as_str()) {
+                // Owned values are plain data, with nothing left to fetch.
+                start_fetches(child, children, steps, fetches);
+            }
+            steps.pop();
+        }
+    }
+}
+
+fn positions(array: &dyn ArrayView, segment: &PathSegment) -> std::ops::Range<usize> {
+    let len = array.size().max(0) as usize;
+    let position = match segment {
+        PathSegment::Any => return 0..len,
+        PathSegment::Index(index) => position(len, *index),
+        PathSegment::Key(key) if key == "first" => position(len, 0),
+        PathSegment::Key(key) if key == "last" => position(len, -1),
+        PathSegment::Key(_) => None,
+    };
+    match position {
+        Some(position) => position..position + 1,
+        None => 0..0,
+    }
+}
+
+fn position(len: usize, index: i64) -> Option<usize> {
+    let index = if index < 0 { index + len as i64 } else { index };
+    usize::try_from(index).ok().filter(|p| *p < len)
+}
+
+/// The paths read, merged from the globals down.
+#[derive(Debug, Default)]
+struct PathTree {
+    /// Each step, with the key it is read by from an object.
+    children: Vec<(PathSegment, KString, PathTree)>,
+}
+
+impl PathTree {
+    fn child(&mut self, segment: PathSegment) -> &mut PathTree {
+        let position = match self.children.iter().position(|(s, _, _)| *s == segment) {
+            Some(position) => position,
+            None => {
+                let key = match &segment {
+                    PathSegment::Key(key) => key.clone(),
+                    PathSegment::Index(index) => KString::from_string(index.to_string()),
+                    PathSegment::Any => KString::default(),
+                };
+                self.children.push((segment, key, PathTree::default()));
+                self.children.len() - 1
+            }
+        };
+        &mut self.children[position].2
+    }
+}
+
+/// A step from a value to one within it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Step {
+    Key(KString),
+    Position(usize),
+}
+
+/// The values `prefetch` fetched, see `Prefetched`.
+#[derive(Debug, Default)]
+pub struct PrefetchedValues {
+    values: Vec<(Vec<Step>, Value)>,
+}
+
+/// Globals, overridden by the values `prefetch` fetched from them.
+///
+/// Only the objects and arrays leading to a fetched value are wrapped; everything else is read
+/// from the globals directly.
+#[derive(Debug)]
+pub struct Prefetched<'g> {
+    view: &'g dyn ValueView,
+    children: Vec<(&'g Step, Node<'g>)>,
+}
+
+#[derive(Debug)]
+enum Node<'g> {
+    Fetched(&'g Value),
+    Prefetched(Prefetched<'g>),
+}
+
+impl<'g> Node<'g> {
+    fn as_view(&self) -> &dyn ValueView {
+        match self {
+            Node::Fetched(value) => *value,
+            Node::Prefetched(prefetched) => prefetched,
+        }
+    }
+}
+
+impl<'g> Prefetched<'g> {
+    /// Layer `fetched` over `globals`.
+    pub fn new(fetched: &'g PrefetchedValues, globals: &'g dyn ObjectView) -> Self {
+        let mut prefetched = Self::wrap(globals.as_value());
+        for (steps, value) in &fetched.values {
+            prefetched.insert(steps, value);
+        }
+        prefetched
+    }
+
+    fn wrap(view: &'g dyn ValueView) -> Self {
+        Self {
+            view,
+            children: Vec::new(),
+        }
+    }
+
+    fn insert(&mut self, steps: &'g [Step], value: &'g Value) {
+        let Some((step, rest)) = steps.split_first() else {
+            return;
+        };
+        let position = self.children.iter().position(|(s, _)| *s == step);
+        if rest.is_empty() {
+            match position {
+                Some(position) => self.children[position].1 = Node::Fetched(value),
+                None => self.children.push((step, Node::Fetched(value))),
+            }
+            return;
+        }
+
+        let position = match position {
+            Some(position) => position,
+            None => {
+                let Some(view) = self.child_view(step) else {
+                    return;
+                };
+                self.children
+                    .push((step, Node::Prefetched(Self::wrap(view))));
+                self.children.len() - 1
+            }
+        };
+        if let Node::Prefetched(child) = &mut self.children[position].1 {
+            child.insert(rest, value);
+        }
+    }
+
+    fn child_view(&self, step: &Step) -> Option<&'g dyn ValueView> {
+        match step {
+            Step::Key(key) => match self.view.as_object()?.get(key.as_str())? {
+                ValueCow::Borrowed(view) => Some(view),
+                ValueCow::Owned(_) => None,
+            },
+            Step::Position(position) => self.view.as_array()?.get(*position as i64),
+        }
+    }
+
+    fn field(&self, index: &str) -> Option<&Node<'g>> {
+        self.children.iter().find_map(|(step, node)| match step {
+            Step::Key(key) if key == index => Some(node),
+            _ => None,
+        })
+    }
+
+    fn element(&self, position: usize) -> Option<&Node<'g>> {
+        self.children.iter().find_map(|(step, node)| match step {
+            Step::Position(p) if *p == position => Some(node),
+            _ => None,
+        })
+    }
+}
+
+impl<'g> ValueView for Prefetched<'g> {
+    fn as_debug(&self) -> &dyn fmt::Debug {
+        self
+    }
+
+    fn render(&self) -> DisplayCow<'_> {
+        self.view.render()
+    }
+    fn source(&self) -> DisplayCow<'_> {
+        self.view.source()
+    }
+    fn type_name(&self) -> &'static str {
+        self.view.type_name()
+    }
+    fn query_state(&self, state: State) -> bool {
+        self.view.query_state(state)
+    }
+
+    fn to_kstr(&self) -> KStringCow<'_> {
+        self.view.to_kstr()
+    }
+    fn to_value(&self) -> Value {
+        if self.view.as_array().is_some() {
+            Value::Array(ArrayView::values(self).map(|v| v.to_value()).collect())
+        } else if self.view.as_object().is_some() {
+            let object: Object = ObjectView::iter(self)
+                .map(|(k, v)| (k.into_owned(), v.to_value()))
+                .collect();
+            Value::Object(object)
+        } else {
+            self.view.to_value()
+        }
+    }
+
+    fn as_array(&self) -> Option<&dyn ArrayView> {
+        self.view.as_array().map(|_| self as &dyn ArrayView)
+    }
+
+    fn as_object(&self) -> Option<&dyn ObjectView> {
+        self.view.as_object().map(|_| self as &dyn ObjectView)
+    }
+}
+
+impl<'g> ArrayView for Prefetched<'g> {
+    fn as_value(&self) -> &dyn ValueView {
+        self
+    }
+
+    fn size(&self) -> i64 {
+        self.view.as_array().map(|a| a.size()).unwrap_or(0)
+    }
+
+    fn values<'k>(&'k self) -> Box<dyn Iterator<Item = &'k dyn ValueView> + 'k> {
+        let Some(array) = self.view.as_array() else {
+            return Box::new(std::iter::empty());
+        };
+        let values =
+            array
+                .values()
+                .enumerate()
+                .map(|(position, value)| match self.element(position) {
+                    Some(node) => node.as_view(),
+                    None => value,
+                });
+        Box::new(values)
+    }
+
+    fn contains_key(&self, index: i64) -> bool {
+        self.view.as_array().is_some_and(|a| a.contains_key(index))
+    }
+
+    fn get(&self, index: i64) -> Option<&dyn ValueView> {
+        let array = self.view.as_array()?;
+        let node = position(array.size().max(0) as usize, index).and_then(|p| self.element(p));
+        match node {
+            Some(node) => Some(node.as_view()),
+            None => array.get(index),
+        }
+    }
+}
+
+impl<'g> ObjectView for Prefetched<'g> {
+    fn as_value(&self) -> &dyn ValueView {
+        self
+    }
+
+    fn size(&self) -> i64 {
+        ObjectView::keys(self).count() as i64
+    }
+
+    fn keys<'k>(&'k self) -> Box<dyn Iterator<Item = KStringCow<'k>> + 'k> {
+        Box::new(ObjectView::iter(self).map(|(k, _)| k))
+    }
+
+    fn values<'k>(&'k self) -> Box<dyn Iterator<Item = &'k dyn ValueView> + 'k> {
+        Box::new(ObjectView::iter(self).map(|(_, v)| v))
+    }
+
+    fn iter<'k>(&'k self) -> Box<dyn Iterator<Item = (KStringCow<'k>, &'k dyn ValueView)> + 'k> {
+        let Some(object) = self.view.as_object() else {
+            return Box::new(std::iter::empty());
+        };
+        let fetched = self.children.iter().filter_map(|(step, node)| match step {
+            Step::Key(key) => Some((KStringCow::from(key.as_ref()), node.as_view())),
+            Step::Position(_) => None,
+        });
+        let rest = object
+            .iter()
+            .filter(move |(k, _)| self.field(k.as_str()).is_none());
+        Box::new(fetched.chain(rest))
+    }
+
+    fn contains_key(&self, index: &str) -> bool {
+        self.field(index).is_some() || self.view.as_object().is_some_and(|o| o.contains_key(index))
+    }
+
+    fn get<'s>(&'s self, index: &str) -> Option<ValueCow<'s>> {
+        match self.field(index) {
+            Some(node) => Some(ValueCow::Borrowed(node.as_view())),
+            None => self.view.as_object()?.get(index),
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use std::cell::RefCell;
+use std::time::Duration;
+
+use crate::parser::SourceSpan;
+
+/// Collects render timings for a `Runtime`.
+///
+/// Every tag, block and expression rendered while a `Profiler` is attached (see
+/// `RuntimeBuilder::set_profiler`) is recorded under the element that rendered it, so partials show
+/// up as children of the `include` or `render` that pulled them in.
+#[derive(Debug, Default)]
+pub struct Profiler {
+    state: RefCell<ProfilerState>,
+}
+
+#[derive(Debug, Default)]
+struct ProfilerState {
+    entries: Vec<Entry>,
+    roots: Vec<usize>,
+    stack: Vec<usize>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    span: SourceSpan,
+    code: String,
+    partial: Option<String>,
+    calls: u64,
+    total_time: Duration,
+    children: Vec<usize>,
+}
+
+impl Profiler {
+    /// Create an empty profiler.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Start timing the element at `span` of the template named `partial`.
+    ///
+    /// Repeated renders of the same element under the same parent, like the body of a `for`
+    /// loop, are aggregated into one entry.  Elements are identified by where they are in the
+    /// source, so a recompiled partial keeps adding to the same entries.
+    pub(crate) fn enter(&self, span: &SourceSpan, code: &str, partial: Option<&str>) {
+        let mut state = self.state.borrow_mut();
+        let state = &mut *state;
+        let siblings = match state.stack.last() {
+            Some(&parent) => &state.entries[parent].children,
+            None => &state.roots,
+        };
+        let existing = siblings.iter().copied().find(|&i| {
+            let entry = &state.entries[i];
+            entry.span == *span && entry.partial.as_deref() == partial
+        });
+        let index = match existing {
+            Some(index) => index,
+            None => {
+                let index = state.entries.len();
+                state.entries.push(Entry {
+                    span: *span,
+                    code: code.to_owned(),
+                    partial: partial.map(|p| p.to_owned()),
+                    calls: 0,
+                    total_time: Duration::ZERO,
+                    children: Vec::new(),
+                });
+                match state.stack.last() {
+                    Some(&parent) => state.entries[parent].children.push(index),
+                    None => state.roots.push(index),
+                }
+                index
+            }
+        };
+        state.stack.push(index);
+    }
+
+    /// Stop timing the element last entered.
+    pub(crate) fn leave(&self, elapsed: Duration) {
+        let mut state = self.state.borrow_mut();
+        let index = state
+            .stack
+            .pop()
+            .expect("`leave` is always paired with `enter`");
+        let entry = &mut state.entries[index];
+        entry.calls += 1;
+        entry.total_time += elapsed;
+    }
+
+    /// The timings collected so far.
+    pub fn to_profile(&self) -> Profile {
+        let state = self.state.borrow();
+        let children: Vec<_> = state
+            .roots
+            .iter()
+            .map(|&i| ProfileNode::new(&state.entries, i))
+            .collect();
+        let total_time = children.iter().map(|c| c.total_time).sum();
+        Profile {
+            total_time,
+            children,
+        }
+    }
+}
+
+/// Render timings, aggregated into a tree.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Profile {
+    total_time: Duration,
+    children: Vec<ProfileNode>,
+}
+
+impl Profile {
+    /// Time spent rendering the template.
+    pub fn total_time(&self) -> Duration {
+        self.total_time
+    }
+
+    /// The top-level elements of the template, in the order they were first rendered.
+    pub fn children(&self) -> &[ProfileNode] {
+        &self.children
+    }
+
+    /// Set the time spent rendering the template, including anything not covered by its elements.
+    pub fn with_total_time(mut self, total_time: Duration) -> Self {
+        self.total_time = total_time;
+        self
+    }
+}
+
+/// Render timings of a tag, block or expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProfileNode {
+    code: String,
+    line: usize,
+    partial: Option<String>,
+    calls: u64,
+    total_time: Duration,
+    children: Vec<ProfileNode>,
+}
+
+impl ProfileNode {
+    fn new(entries: &[Entry], index: usize) -> Self {
+        let entry = &entries[index];
+        Self {
+            code: entry.code.clone(),
+            line: entry.span.line(),
+            partial: entry.partial.clone(),
+            calls: entry.calls,
+            total_time: entry.total_time,
+            children: entry
+                .children
+                .iter()
+                .map(|&i| ProfileNode::new(entries, i))
+                .collect(),
+        }
+    }
+
+    /// The source of the element, without its delimiters.
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// The line the element starts on, starting from 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The name of the partial the element belongs to, if not the top-level template.
+    pub fn partial(&self) -> Option<&str> {
+        self.partial.as_deref()
+    }
+
+    /// Number of times the element was rendered.
+    pub fn calls(&self) -> u64 {
+        self.calls
+    }
+
+    /// Time spent rendering the element, across all calls.
+    pub fn total_time(&self) -> Duration {
+        self.total_time
+    }
+
+    /// Time spent rendering the element, excluding its children.
+    pub fn self_time(&self) -> Duration {
+        let children: Duration = self.children.iter().map(|c| c.total_time).sum();
+        self.total_time.saturating_sub(children)
+    }
+
+    /// Elements rendered by this one, such as the body of a block or the content of a partial.
+    pub fn children(&self) -> &[ProfileNode] {
+        &self.children
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn aggregates_repeated_calls() {
+        let profiler = Profiler::new();
+        profiler.enter(&SourceSpan::new(0, 30, 1, 1), "for x in xs", None);
+        for _ in 0..3 {
+            profiler.enter(&SourceSpan::new(16, 21, 1, 17), "x", None);
+            profiler.leave(Duration::from_millis(1));
+        }
+        profiler.leave(Duration::from_millis(5));
+
+        let profile = profiler.to_profile();
+        assert_eq!(profile.total_time(), Duration::from_millis(5));
+        let node = &profile.children()[0];
+        assert_eq!(node.calls(), 1);
+        assert_eq!(node.self_time(), Duration::from_millis(2));
+        assert_eq!(node.children().len(), 1);
+        assert_eq!(node.children()[0].calls(), 3);
+    }
+
+    #[test]
+    fn separates_same_element_under_different_parents() {
+        let profiler = Profiler::new();
+        for start in [0, 20] {
+            let parent = SourceSpan::new(start, start + 17, 1, start + 1);
+            profiler.enter(&parent, "include 'a'", None);
+            profiler.enter(&SourceSpan::new(0, 5, 1, 1), "x", Some("a"));
+            profiler.leave(Duration::ZERO);
+            profiler.leave(Duration::ZERO);
+        }
+
+        let profile = profiler.to_profile();
+        assert_eq!(profile.children().len(), 2);
+        for node in profile.children() {
+            assert_eq!(node.children()[0].partial(), Some("a"));
+        }
+    }
+
+    #[test]
+    fn identifies_elements_by_partial_and_span() {
+        let profiler = Profiler::new();
+        let span = SourceSpan::new(0, 5, 1, 1);
+        for partial in [Some("a"), Some("a"), Some("b")] {
+            profiler.enter(&span, "x", partial);
+            profiler.leave(Duration::ZERO);
+        }
+
+        let profile = profiler.to_profile();
+        assert_eq!(profile.children().len(), 2);
+        assert_eq!(profile.children()[0].calls(), 2);
+        assert_eq!(profile.children()[1].partial(), Some("b"));
+    }
+}
@@ -3,26 +3,42 @@ use std::io::Write;
 
 use crate::error::Result;
 
+use super::LimitedWriter;
 use super::Runtime;
+use super::SerializeRenderable;
+use super::Visitor;
 
 /// Any object (tag/block) that can be rendered by liquid must implement this trait.
 pub trait Renderable: Send + Sync + Debug {
     /// Renders the Renderable instance given a Liquid runtime.
+    ///
+    /// The output counts against the runtime's output limit, see `LimitedWriter`.
     fn render(&self, runtime: &dyn Runtime) -> Result<String> {
         let mut data = Vec::new();
-        self.render_to(&mut data, runtime)?;
+        let mut writer = LimitedWriter::new(&mut data, runtime.limits());
+        let result = self.render_to(&mut writer, runtime);
+        writer.check(result)?;
         Ok(String::from_utf8(data).expect("render only writes UTF-8"))
     }
 
     /// Renders the Renderable instance given a Liquid runtime.
     fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()>;
 
+    /// Walks the expressions and nested elements of the Renderable instance.
+    ///
+    /// Elements that reference no variables, filters or partials can rely on the default.
+    fn visit(&self, _visitor: &mut dyn Visitor) {}
 
     /// Checks if a block can be blank or not.
     fn is_blank(&self) -> bool {
         false
     }
 
+    /// This, if it supports serialization, see `SerializeRenderable`.
+    fn as_serialize(&self) -> Option<&dyn SerializeRenderable> {
+        None
+    }
+
     /// Checks if a block is just Text or something else. We remove empty text in if,unless,case in some cases
     fn is_text(&self) -> bool {
         false
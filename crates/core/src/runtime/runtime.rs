@@ -4,8 +4,13 @@ use crate::error::Error;
 use crate::error::Result;
 use crate::model::{Object, ObjectView, Scalar, ScalarCow, Value, ValueCow, ValueView};
 
+use super::AsyncFilters;
+use super::Cancellation;
 use super::PartialStore;
+use super::Profiler;
 use super::Renderable;
+use super::{LimitTracker, Limits};
+use super::{RenderOptions, Warnings};
 
 /// What mode to use when rendering.
 pub enum RenderingMode {
@@ -47,6 +52,54 @@ pub trait Runtime {
 
     /// Used to set the mode when rendering
     fn render_mode(&self) -> &RenderingMode;
+
+    /// How strict to be about undefined names and bad values
+    ///
+    /// Defaults to the options matching `render_mode`.
+    fn render_options(&self) -> &RenderOptions {
+        const STRICT: RenderOptions = RenderOptions::with_mode(&RenderingMode::Strict);
+        const LAX: RenderOptions = RenderOptions::with_mode(&RenderingMode::Lax);
+        match self.render_mode() {
+            RenderingMode::Strict => &STRICT,
+            RenderingMode::Lax => &LAX,
+        }
+    }
+
+    /// Problems the render carried on past, when collecting them
+    fn warnings(&self) -> Option<&Warnings> {
+        None
+    }
+
+    /// Collects render timings, when profiling
+    fn profiler(&self) -> Option<&Profiler> {
+        None
+    }
+
+    /// Usage against the render's resource limits
+    ///
+    /// Defaults to enforcing no limits.
+    fn limits(&self) -> &LimitTracker {
+        static UNBOUNDED: LimitTracker = LimitTracker::unbounded();
+        &UNBOUNDED
+    }
+
+    /// Whether the render should stop early
+    ///
+    /// Defaults to never stopping.
+    fn cancellation(&self) -> &Cancellation {
+        static NEVER: Cancellation = Cancellation::new();
+        &NEVER
+    }
+
+    /// Whether output expressions are HTML-escaped
+    fn auto_escape(&self) -> bool {
+        false
+    }
+
+    /// Results of async filters, when rendering asynchronously
+    fn async_filters(&self) -> Option<&AsyncFilters> {
+        None
+    }
 }
 
 impl<'r, R: Runtime + ?Sized> Runtime for &'r R {
@@ -93,6 +146,34 @@ impl<'r, R: Runtime + ?Sized> Runtime for &'r R {
     fn render_mode(&self) -> &RenderingMode {
         <R as Runtime>::render_mode(self)
     }
+
+    fn render_options(&self) -> &RenderOptions {
+        <R as Runtime>::render_options(self)
+    }
+
+    fn warnings(&self) -> Option<&Warnings> {
+        <R as Runtime>::warnings(self)
+    }
+
+    fn profiler(&self) -> Option<&Profiler> {
+        <R as Runtime>::profiler(self)
+    }
+
+    fn limits(&self) -> &LimitTracker {
+        <R as Runtime>::limits(self)
+    }
+
+    fn cancellation(&self) -> &Cancellation {
+        <R as Runtime>::cancellation(self)
+    }
+
+    fn auto_escape(&self) -> bool {
+        <R as Runtime>::auto_escape(self)
+    }
+
+    fn async_filters(&self) -> Option<&AsyncFilters> {
+        <R as Runtime>::async_filters(self)
+    }
 }
 
 /// Create processing runtime for a template.
@@ -100,6 +181,13 @@ pub struct RuntimeBuilder<'g, 'p> {
     globals: Option<&'g dyn ObjectView>,
     partials: Option<&'p dyn PartialStore>,
     render_mode: RenderingMode,
+    render_options: Option<RenderOptions>,
+    warnings: Option<&'p Warnings>,
+    profiler: Option<&'p Profiler>,
+    limits: Limits,
+    cancellation: Cancellation,
+    auto_escape: bool,
+    async_filters: Option<&'p AsyncFilters>,
 }
 
 impl<'c, 'g: 'c, 'p: 'c> RuntimeBuilder<'g, 'p> {
@@ -109,6 +197,13 @@ impl<'c, 'g: 'c, 'p: 'c> RuntimeBuilder<'g, 'p> {
             globals: None,
             partials: None,
             render_mode: RenderingMode::Strict,
+            render_options: None,
+            warnings: None,
+            profiler: None,
+            limits: Limits::new(),
+            cancellation: Cancellation::new(),
+            auto_escape: false,
+            async_filters: None,
         }
     }
 
@@ -118,24 +213,173 @@ impl<'c, 'g: 'c, 'p: 'c> RuntimeBuilder<'g, 'p> {
             globals: Some(values),
             partials: self.partials,
             render_mode: self.render_mode,
+            render_options: self.render_options,
+            warnings: self.warnings,
+            profiler: self.profiler,
+            limits: self.limits,
+            cancellation: self.cancellation,
+            auto_escape: self.auto_escape,
+            async_filters: self.async_filters,
         }
     }
 
     /// Initialize partial-templates available for including.
-    pub fn set_partials<'n>(self, values: &'n dyn PartialStore) -> RuntimeBuilder<'g, 'n> {
+    pub fn set_partials<'n>(self, values: &'n dyn PartialStore) -> RuntimeBuilder<'g, 'n>
+    where
+        'p: 'n,
+    {
         RuntimeBuilder {
             globals: self.globals,
             partials: Some(values),
             render_mode: self.render_mode,
+            render_options: self.render_options,
+            warnings: self.warnings,
+            profiler: self.profiler,
+            limits: self.limits,
+            cancellation: self.cancellation,
+            auto_escape: self.auto_escape,
+            async_filters: self.async_filters,
         }
     }
 
     /// Initialize with the provided rendering mode.
+    ///
+    /// Unless `set_render_options` is used, the `RenderOptions` match `mode`.
     pub fn set_render_mode(self, mode: RenderingMode) -> RuntimeBuilder<'g, 'p> {
         RuntimeBuilder {
             globals: self.globals,
             partials: self.partials,
+            render_options: self.render_options,
             render_mode: mode,
+            warnings: self.warnings,
+            profiler: self.profiler,
+            limits: self.limits,
+            cancellation: self.cancellation,
+            auto_escape: self.auto_escape,
+            async_filters: self.async_filters,
+        }
+    }
+
+    /// Choose how strict to be about undefined names and bad values.
+    ///
+    /// These take precedence over the options of `set_render_mode`, whichever is set first.
+    pub fn set_render_options(self, options: RenderOptions) -> RuntimeBuilder<'g, 'p> {
+        RuntimeBuilder {
+            globals: self.globals,
+            partials: self.partials,
+            render_mode: self.render_mode,
+            render_options: Some(options),
+            warnings: self.warnings,
+            profiler: self.profiler,
+            limits: self.limits,
+            cancellation: self.cancellation,
+            auto_escape: self.auto_escape,
+            async_filters: self.async_filters,
+        }
+    }
+
+    /// Record problems covered by `Strictness::Warn` into `warnings`.
+    pub fn set_warnings<'n>(self, warnings: &'n Warnings) -> RuntimeBuilder<'g, 'n>
+    where
+        'p: 'n,
+    {
+        RuntimeBuilder {
+            globals: self.globals,
+            partials: self.partials,
+            render_mode: self.render_mode,
+            render_options: self.render_options,
+            warnings: Some(warnings),
+            profiler: self.profiler,
+            limits: self.limits,
+            cancellation: self.cancellation,
+            auto_escape: self.auto_escape,
+            async_filters: self.async_filters,
+        }
+    }
+
+    /// Record render timings into `profiler`.
+    pub fn set_profiler<'n>(self, profiler: &'n Profiler) -> RuntimeBuilder<'g, 'n>
+    where
+        'p: 'n,
+    {
+        RuntimeBuilder {
+            globals: self.globals,
+            partials: self.partials,
+            render_mode: self.render_mode,
+            render_options: self.render_options,
+            warnings: self.warnings,
+            profiler: Some(profiler),
+            limits: self.limits,
+            cancellation: self.cancellation,
+            auto_escape: self.auto_escape,
+            async_filters: self.async_filters,
+        }
+    }
+
+    /// Bound the work the render may do.
+    pub fn set_limits(self, limits: Limits) -> RuntimeBuilder<'g, 'p> {
+        RuntimeBuilder {
+            globals: self.globals,
+            partials: self.partials,
+            render_mode: self.render_mode,
+            render_options: self.render_options,
+            warnings: self.warnings,
+            profiler: self.profiler,
+            limits,
+            cancellation: self.cancellation,
+            auto_escape: self.auto_escape,
+            async_filters: self.async_filters,
+        }
+    }
+
+    /// Stop the render early once `cancellation` triggers.
+    pub fn set_cancellation(self, cancellation: Cancellation) -> RuntimeBuilder<'g, 'p> {
+        RuntimeBuilder {
+            globals: self.globals,
+            partials: self.partials,
+            render_mode: self.render_mode,
+            render_options: self.render_options,
+            warnings: self.warnings,
+            profiler: self.profiler,
+            limits: self.limits,
+            cancellation,
+            auto_escape: self.auto_escape,
+            async_filters: self.async_filters,
+        }
+    }
+
+    /// HTML-escape the output of every `{{ }}` that isn't marked as safe.
+    pub fn set_auto_escape(self, auto_escape: bool) -> RuntimeBuilder<'g, 'p> {
+        RuntimeBuilder {
+            globals: self.globals,
+            partials: self.partials,
+            render_mode: self.render_mode,
+            render_options: self.render_options,
+            warnings: self.warnings,
+            profiler: self.profiler,
+            limits: self.limits,
+            cancellation: self.cancellation,
+            auto_escape,
+            async_filters: self.async_filters,
+        }
+    }
+
+    /// Evaluate async filters with, and queue them into, `filters`.
+    pub fn set_async_filters<'n>(self, filters: &'n AsyncFilters) -> RuntimeBuilder<'g, 'n>
+    where
+        'p: 'n,
+    {
+        RuntimeBuilder {
+            globals: self.globals,
+            partials: self.partials,
+            render_mode: self.render_mode,
+            render_options: self.render_options,
+            warnings: self.warnings,
+            profiler: self.profiler,
+            limits: self.limits,
+            cancellation: self.cancellation,
+            auto_escape: self.auto_escape,
+            async_filters: Some(filters),
         }
     }
 
@@ -144,7 +388,16 @@ impl<'c, 'g: 'c, 'p: 'c> RuntimeBuilder<'g, 'p> {
         let partials = self.partials.unwrap_or(&NullPartials);
         let runtime = RuntimeCore {
             partials,
+            render_options: self
+                .render_options
+                .unwrap_or_else(|| RenderOptions::with_mode(&self.render_mode)),
             render_mode: self.render_mode,
+            warnings: self.warnings,
+            profiler: self.profiler,
+            limits: LimitTracker::new(self.limits),
+            cancellation: self.cancellation,
+            auto_escape: self.auto_escape,
+            async_filters: self.async_filters,
             ..Default::default()
         };
         let runtime = super::IndexFrame::new(runtime);
@@ -239,6 +492,20 @@ pub struct RuntimeCore<'g> {
     registers: Registers,
 
     render_mode: RenderingMode,
+
+    render_options: RenderOptions,
+
+    warnings: Option<&'g Warnings>,
+
+    profiler: Option<&'g Profiler>,
+
+    limits: LimitTracker,
+
+    cancellation: Cancellation,
+
+    auto_escape: bool,
+
+    async_filters: Option<&'g AsyncFilters>,
 }
 
 impl<'g> RuntimeCore<'g> {
@@ -303,6 +570,34 @@ impl<'g> Runtime for RuntimeCore<'g> {
     fn render_mode(&self) -> &RenderingMode {
         &self.render_mode
     }
+
+    fn render_options(&self) -> &RenderOptions {
+        &self.render_options
+    }
+
+    fn warnings(&self) -> Option<&Warnings> {
+        self.warnings
+    }
+
+    fn profiler(&self) -> Option<&Profiler> {
+        self.profiler
+    }
+
+    fn limits(&self) -> &LimitTracker {
+        &self.limits
+    }
+
+    fn cancellation(&self) -> &Cancellation {
+        &self.cancellation
+    }
+
+    fn auto_escape(&self) -> bool {
+        self.auto_escape
+    }
+
+    fn async_filters(&self) -> Option<&AsyncFilters> {
+        self.async_filters
+    }
 }
 
 impl<'g> Default for RuntimeCore<'g> {
@@ -311,6 +606,13 @@ impl<'g> Default for RuntimeCore<'g> {
             partials: &NullPartials,
             registers: Default::default(),
             render_mode: RenderingMode::Strict,
+            render_options: Default::default(),
+            warnings: None,
+            profiler: None,
+            limits: Default::default(),
+            cancellation: Default::default(),
+            auto_escape: false,
+            async_filters: None,
         }
     }
 }
@@ -411,6 +713,7 @@ mod test {
     use crate::model::Scalar;
     use crate::model::Value;
     use crate::model::ValueViewCmp;
+    use crate::runtime::Strictness;
 
     #[test]
     fn mask_variables() {
@@ -432,6 +735,25 @@ mod test {
         //assert_eq!(&rt.get(&test_path).unwrap(), &ValueViewCmp::new(&42));
     }
 
+    #[test]
+    fn render_options_override_mode() {
+        let rt = RuntimeBuilder::new()
+            .set_render_mode(RenderingMode::Lax)
+            .build();
+        assert_eq!(
+            rt.render_options(),
+            &RenderOptions::with_mode(&RenderingMode::Lax)
+        );
+
+        let options = RenderOptions::new().set_cycle_mismatches(Strictness::Warn);
+        let rt = RuntimeBuilder::new()
+            .set_render_options(options)
+            .set_render_mode(RenderingMode::Lax)
+            .build();
+        assert!(matches!(rt.render_mode(), RenderingMode::Lax));
+        assert_eq!(rt.render_options(), &options);
+    }
+
     #[test]
     fn global_variables() {
         let global_path = [Scalar::new("global")];
@@ -450,4 +772,73 @@ mod test {
             &ValueViewCmp::new(&"some value")
         );
     }
+
+    /// A runtime implementing only the required methods, as one written before the optional
+    /// ones were added would.
+    struct LaxRuntime<'r>(&'r dyn Runtime);
+
+    impl<'r> Runtime for LaxRuntime<'r> {
+        fn partials(&self) -> &dyn PartialStore {
+            self.0.partials()
+        }
+
+        fn name(&self) -> Option<crate::model::KStringRef<'_>> {
+            self.0.name()
+        }
+
+        fn roots(&self) -> std::collections::BTreeSet<crate::model::KStringCow<'_>> {
+            self.0.roots()
+        }
+
+        fn try_get(&self, path: &[ScalarCow<'_>]) -> Option<ValueCow<'_>> {
+            self.0.try_get(path)
+        }
+
+        fn get(&self, path: &[ScalarCow<'_>]) -> Result<ValueCow<'_>> {
+            self.0.get(path)
+        }
+
+        fn set_global(
+            &self,
+            name: crate::model::KString,
+            val: crate::model::Value,
+        ) -> Option<crate::model::Value> {
+            self.0.set_global(name, val)
+        }
+
+        fn set_index(&self, name: crate::model::KString, val: Value) -> Option<Value> {
+            self.0.set_index(name, val)
+        }
+
+        fn get_index<'a>(&'a self, name: &str) -> Option<ValueCow<'a>> {
+            self.0.get_index(name)
+        }
+
+        fn registers(&self) -> &Registers {
+            self.0.registers()
+        }
+
+        fn render_mode(&self) -> &RenderingMode {
+            &RenderingMode::Lax
+        }
+    }
+
+    #[test]
+    fn optional_methods_default() {
+        let rt = RuntimeBuilder::new().build();
+        let rt = LaxRuntime(&rt);
+        assert_eq!(
+            rt.render_options(),
+            &RenderOptions::with_mode(&RenderingMode::Lax)
+        );
+        assert!(rt.warnings().is_none());
+        assert!(rt.profiler().is_none());
+        for _ in 0..3 {
+            rt.limits().count_iteration().unwrap();
+            let _depth = rt.limits().enter_partial().unwrap();
+        }
+        assert!(!rt.cancellation().is_cancelled());
+        assert!(!rt.auto_escape());
+        assert!(rt.async_filters().is_none());
+    }
 }
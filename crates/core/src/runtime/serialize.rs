@@ -0,0 +1,213 @@
+use std::cell::RefCell;
+use std::sync;
+
+use serde::{de, ser, Deserialize, Serialize};
+
+use crate::model::{KString, Scalar, State, Value};
+use crate::parser::Language;
+
+use super::Renderable;
+
+/// A `Renderable` that can be serialized, so a parsed template can be saved and loaded back
+/// without re-parsing it.
+///
+/// To opt a tag or block in, derive `Serialize` and `Deserialize` for the `Renderable` it parses
+/// into, implement this trait with `#[typetag::serde(name = "...")]`, and return `Some(self)` from
+/// `Renderable::as_serialize`.  Filters need nothing extra, as they are re-created from their
+/// arguments by the `Language` passed to `with_language`.
+#[typetag::serde]
+pub trait SerializeRenderable: Renderable {
+    /// Convert into a plain `Renderable`.
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable>;
+}
+
+thread_local! {
+    static LANGUAGE: RefCell<Option<sync::Arc<Language>>> = const { RefCell::new(None) };
+}
+
+/// Run `f`, deserializing the filters of any `SerializeRenderable`s it loads with `language`.
+pub fn with_language<R, F: FnOnce() -> R>(language: sync::Arc<Language>, f: F) -> R {
+    struct Restore(Option<sync::Arc<Language>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            LANGUAGE.with(|l| *l.borrow_mut() = previous);
+        }
+    }
+
+    let previous = LANGUAGE.with(|l| l.borrow_mut().replace(language));
+    let _restore = Restore(previous);
+    f()
+}
+
+pub(crate) fn current_language() -> Option<sync::Arc<Language>> {
+    LANGUAGE.with(|l| l.borrow().clone())
+}
+
+/// Serializes a `Renderable` through `Renderable::as_serialize`.
+pub(crate) struct SerializeElement<'e>(pub(crate) &'e dyn Renderable);
+
+impl<'e> Serialize for SerializeElement<'e> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0.as_serialize() {
+            Some(renderable) => renderable.serialize(serializer),
+            None => Err(ser::Error::custom(
+                "Tag or block doesn't support serialization",
+            )),
+        }
+    }
+}
+
+pub(crate) fn deserialize_renderable<'de, D>(
+    deserializer: D,
+) -> Result<Box<dyn Renderable>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    Box::<dyn SerializeRenderable>::deserialize(deserializer).map(|r| r.into_renderable())
+}
+
+/// A literal, as written in a template, tagged by its type so it round-trips exactly.
+#[derive(Serialize, Deserialize)]
+enum Literal {
+    Nil,
+    Empty,
+    Blank,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    Str(KString),
+    Array(Vec<Literal>),
+    Object(Vec<(KString, Literal)>),
+}
+
+impl Literal {
+    fn from_scalar(scalar: &Scalar) -> Self {
+        if let Some(x) = scalar.as_bool() {
+            Literal::Bool(x)
+        } else if let Some(x) = scalar.as_integer() {
+            Literal::Integer(x)
+        } else if let Some(x) = scalar.as_float() {
+            Literal::Float(x)
+        } else {
+            Literal::Str(scalar.clone().into_string())
+        }
+    }
+
+    fn from_value<E: ser::Error>(value: &Value) -> Result<Self, E> {
+        let literal = match value {
+            Value::Nil => Literal::Nil,
+            Value::State(State::Empty) => Literal::Empty,
+            Value::State(State::Blank) => Literal::Blank,
+            Value::Scalar(scalar) => Literal::from_scalar(scalar),
+            Value::Array(array) => Literal::Array(
+                array
+                    .iter()
+                    .map(Literal::from_value)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Object(object) => Literal::Object(
+                object
+                    .iter()
+                    .map(|(k, v)| Ok((k.clone(), Literal::from_value(v)?)))
+                    .collect::<Result<_, _>>()?,
+            ),
+            _ => return Err(E::custom("Only literals can be serialized")),
+        };
+        Ok(literal)
+    }
+
+    fn into_value(self) -> Value {
+        match self {
+            Literal::Nil => Value::Nil,
+            Literal::Empty => Value::State(State::Empty),
+            Literal::Blank => Value::State(State::Blank),
+            Literal::Bool(x) => Value::scalar(x),
+            Literal::Integer(x) => Value::scalar(x),
+            Literal::Float(x) => Value::scalar(x),
+            Literal::Str(x) => Value::scalar(x),
+            Literal::Array(x) => Value::Array(x.into_iter().map(Literal::into_value).collect()),
+            Literal::Object(x) => {
+                Value::Object(x.into_iter().map(|(k, v)| (k, v.into_value())).collect())
+            }
+        }
+    }
+}
+
+/// Serialize a literal `Value`, for `#[serde(with = "...")]`.
+pub(crate) mod literal {
+    use super::*;
+
+    pub(crate) fn serialize<S: ser::Serializer>(
+        value: &Value,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        Literal::from_value(value)?.serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: de::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Value, D::Error> {
+        Literal::deserialize(deserializer).map(Literal::into_value)
+    }
+}
+
+/// Serialize a `Scalar`, for `#[serde(with = "...")]`.
+pub(crate) mod scalar {
+    use super::*;
+
+    pub(crate) fn serialize<S: ser::Serializer>(
+        value: &Scalar,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        Literal::from_scalar(value).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: de::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Scalar, D::Error> {
+        match Literal::deserialize(deserializer)?.into_value() {
+            Value::Scalar(scalar) => Ok(scalar),
+            _ => Err(de::Error::custom("Expected a scalar")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Wrapper(#[serde(with = "literal")] Value);
+
+    fn round_trip(value: Value) -> Value {
+        let saved = serde_json::to_string(&Wrapper(value)).unwrap();
+        serde_json::from_str::<Wrapper>(&saved).unwrap().0
+    }
+
+    #[test]
+    fn literals_keep_their_type() {
+        let values = vec![
+            Value::Nil,
+            Value::State(State::Empty),
+            Value::State(State::Blank),
+            Value::scalar(true),
+            Value::scalar(1i64),
+            Value::scalar(1f64),
+            Value::scalar("1"),
+            Value::Array(vec![Value::scalar(1i64), Value::Nil]),
+        ];
+        for value in values {
+            assert_eq!(round_trip(value.clone()), value);
+        }
+    }
+
+    #[test]
+    fn filters_need_a_language() {
+        assert!(current_language().is_none());
+        let language = sync::Arc::new(Language::empty());
+        with_language(language, || assert!(current_language().is_some()));
+        assert!(current_language().is_none());
+    }
+}
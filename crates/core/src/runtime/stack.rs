@@ -93,6 +93,34 @@ impl<P: super::Runtime, O: ObjectView> super::Runtime for StackFrame<P, O> {
     fn render_mode(&self) -> &super::RenderingMode {
         self.parent.render_mode()
     }
+
+    fn render_options(&self) -> &super::RenderOptions {
+        self.parent.render_options()
+    }
+
+    fn warnings(&self) -> Option<&super::Warnings> {
+        self.parent.warnings()
+    }
+
+    fn profiler(&self) -> Option<&super::Profiler> {
+        self.parent.profiler()
+    }
+
+    fn limits(&self) -> &super::LimitTracker {
+        self.parent.limits()
+    }
+
+    fn cancellation(&self) -> &super::Cancellation {
+        self.parent.cancellation()
+    }
+
+    fn auto_escape(&self) -> bool {
+        self.parent.auto_escape()
+    }
+
+    fn async_filters(&self) -> Option<&super::AsyncFilters> {
+        self.parent.async_filters()
+    }
 }
 
 /// A stack frame that only provides a sandboxed set of globals
@@ -174,6 +202,34 @@ impl<P: super::Runtime> super::Runtime for GlobalFrame<P> {
     fn render_mode(&self) -> &super::RenderingMode {
         self.parent.render_mode()
     }
+
+    fn render_options(&self) -> &super::RenderOptions {
+        self.parent.render_options()
+    }
+
+    fn warnings(&self) -> Option<&super::Warnings> {
+        self.parent.warnings()
+    }
+
+    fn profiler(&self) -> Option<&super::Profiler> {
+        self.parent.profiler()
+    }
+
+    fn limits(&self) -> &super::LimitTracker {
+        self.parent.limits()
+    }
+
+    fn cancellation(&self) -> &super::Cancellation {
+        self.parent.cancellation()
+    }
+
+    fn auto_escape(&self) -> bool {
+        self.parent.auto_escape()
+    }
+
+    fn async_filters(&self) -> Option<&super::AsyncFilters> {
+        self.parent.async_filters()
+    }
 }
 
 pub(crate) struct IndexFrame<P> {
@@ -253,6 +309,34 @@ impl<P: super::Runtime> super::Runtime for IndexFrame<P> {
     fn render_mode(&self) -> &super::RenderingMode {
         self.parent.render_mode()
     }
+
+    fn render_options(&self) -> &super::RenderOptions {
+        self.parent.render_options()
+    }
+
+    fn warnings(&self) -> Option<&super::Warnings> {
+        self.parent.warnings()
+    }
+
+    fn profiler(&self) -> Option<&super::Profiler> {
+        self.parent.profiler()
+    }
+
+    fn limits(&self) -> &super::LimitTracker {
+        self.parent.limits()
+    }
+
+    fn cancellation(&self) -> &super::Cancellation {
+        self.parent.cancellation()
+    }
+
+    fn auto_escape(&self) -> bool {
+        self.parent.auto_escape()
+    }
+
+    fn async_filters(&self) -> Option<&super::AsyncFilters> {
+        self.parent.async_filters()
+    }
 }
 
 /// A [`StackFrame`] where variables are not recursively searched for,
@@ -291,6 +375,14 @@ impl<P: super::Runtime, O: ObjectView> super::Runtime for SandboxedStackFrame<P,
              self.parent.render_mode()
     }
 
+    fn render_options(&self) -> &super::RenderOptions {
+        self.parent.render_options()
+    }
+
+    fn warnings(&self) -> Option<&super::Warnings> {
+        self.parent.warnings()
+    }
+
     fn name(&self) -> Option<crate::model::KStringRef<'_>> {
         self.name
             .as_ref()
@@ -343,6 +435,26 @@ impl<P: super::Runtime, O: ObjectView> super::Runtime for SandboxedStackFrame<P,
     fn registers(&self) -> &super::Registers {
         &self.registers
     }
+
+    fn profiler(&self) -> Option<&super::Profiler> {
+        self.parent.profiler()
+    }
+
+    fn limits(&self) -> &super::LimitTracker {
+        self.parent.limits()
+    }
+
+    fn cancellation(&self) -> &super::Cancellation {
+        self.parent.cancellation()
+    }
+
+    fn auto_escape(&self) -> bool {
+        self.parent.auto_escape()
+    }
+
+    fn async_filters(&self) -> Option<&super::AsyncFilters> {
+        self.parent.async_filters()
+    }
 }
 
 #[cfg(test)]
@@ -1,9 +1,12 @@
 use std::io::Write;
 
-use crate::error::Result;
+use crate::error::{Result, ResultLiquidReplaceExt};
 
 use super::Renderable;
 use super::Runtime;
+use super::SerializeElement;
+use super::SerializeRenderable;
+use super::Visitor;
 
 /// An executable template block.
 #[derive(Debug)]
@@ -16,12 +19,24 @@ impl Template {
     pub fn new(elements: Vec<Box<dyn Renderable>>) -> Template {
         Template { elements }
     }
-}
 
-impl Renderable for Template {
-    fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+    /// Render, flushing `writer` after each top-level element so the output can be sent on as it
+    /// is produced.
+    pub fn render_chunked_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+        self.render_elements(writer, runtime, true)
+    }
+
+    fn render_elements(
+        &self,
+        writer: &mut dyn Write,
+        runtime: &dyn Runtime,
+        flush: bool,
+    ) -> Result<()> {
         for el in &self.elements {
             el.render_to(writer, runtime)?;
+            if flush {
+                writer.flush().replace("Failed to flush")?;
+            }
 
             // Did the last element we processed set an interrupt? If so, we
             // need to abandon the rest of our child elements and just
@@ -37,8 +52,46 @@ impl Renderable for Template {
         }
         Ok(())
     }
+}
+
+impl Renderable for Template {
+    fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+        self.render_elements(writer, runtime, false)
+    }
+
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        for el in &self.elements {
+            el.visit(visitor);
+        }
+    }
 
     fn is_blank(&self) -> bool {
         false
     }
+
+    fn as_serialize(&self) -> Option<&dyn SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[typetag::serde(name = "template")]
+impl SerializeRenderable for Template {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
+}
+
+impl serde::Serialize for Template {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let elements = self.elements.iter().map(|e| SerializeElement(e.as_ref()));
+        serializer.collect_seq(elements)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Template {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let elements = Vec::<Box<dyn SerializeRenderable>>::deserialize(deserializer)?;
+        let elements = elements.into_iter().map(|e| e.into_renderable()).collect();
+        Ok(Template { elements })
+    }
 }
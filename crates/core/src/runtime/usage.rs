@@ -0,0 +1,368 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+use crate::model::{KString, ValueView};
+
+use super::Binding;
+use super::Expression;
+use super::PartialStore;
+use super::Renderable;
+use super::Variable;
+use super::Visitor;
+
+/// List the globals `template`, and the partials it names, read.
+///
+/// Names bound by `for`, `tablerow`, `assign`, `capture` and the arguments of `include` and
+/// `render` aren't globals; reads through loop variables and partial arguments are traced back to
+/// the globals they came from.  A name counts as assigned from its first `assign` or `capture`
+/// onwards, whichever branch that is in.  Partials rendered with `render` only see their
+/// arguments, so their other names aren't reported.
+///
+/// Only partials named by a literal can be followed, and a partial isn't followed into itself.
+pub fn variable_usage(
+    template: &dyn Renderable,
+    partials: Option<&dyn PartialStore>,
+) -> VariableUsage {
+    let mut collector = UsageCollector {
+        partials,
+        scopes: vec![Scope::root(false)],
+        active_partials: Vec::new(),
+        paths: BTreeSet::new(),
+    };
+    template.visit(&mut collector);
+    VariableUsage {
+        paths: collector.paths,
+    }
+}
+
+/// What a template reads from its globals, see `variable_usage`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VariableUsage {
+    paths: BTreeSet<VariablePath>,
+}
+
+impl VariableUsage {
+    /// The globals read, in order.
+    pub fn roots(&self) -> Vec<&str> {
+        let mut roots: Vec<_> = self.paths.iter().map(|p| p.root()).collect();
+        roots.dedup();
+        roots
+    }
+
+    /// Every path read into the globals, in order.
+    pub fn paths(&self) -> impl Iterator<Item = &VariablePath> {
+        self.paths.iter()
+    }
+
+    /// Whether the global `root` is read.
+    pub fn contains_root(&self, root: &str) -> bool {
+        self.paths.iter().any(|p| p.root() == root)
+    }
+}
+
+/// A step of a `VariablePath`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PathSegment {
+    /// An object key, like `title` in `product.title`.
+    Key(KString),
+    /// An array index, like `0` in `products[0]`.
+    Index(i64),
+    /// An index only known when rendering, like `i` in `products[i]`, or each element in turn,
+    /// as read through a `for` loop variable.
+    Any,
+}
+
+/// A global and the path read into it, like `products[].title`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VariablePath {
+    root: KString,
+    segments: Vec<PathSegment>,
+}
+
+impl VariablePath {
+    /// Create a path to the global `root`.
+    pub fn new(root: KString) -> Self {
+        Self {
+            root,
+            segments: Vec::new(),
+        }
+    }
+
+    /// Extend the path by `segment`.
+    pub fn push(mut self, segment: PathSegment) -> Self {
+        self.segments.push(segment);
+        self
+    }
+
+    /// The global read.
+    pub fn root(&self) -> &str {
+        self.root.as_str()
+    }
+
+    /// The path read into the global.
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+}
+
+impl fmt::Display for VariablePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.root)?;
+        for segment in &self.segments {
+            match segment {
+                PathSegment::Key(key) if is_identifier(key) => write!(f, ".{}", key)?,
+                PathSegment::Key(key) => write!(f, "[{:?}]", key.as_str())?,
+                PathSegment::Index(index) => write!(f, "[{}]", index)?,
+                PathSegment::Any => write!(f, "[]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+#[derive(Debug, Default)]
+struct Scope {
+    /// Names local to the scope, with the global path they refer to, if known.
+    locals: HashMap<String, Option<VariablePath>>,
+    /// Names assigned within the template or partial, when the scope is the root of one.
+    assigned: Option<HashSet<String>>,
+    /// Whether names not found here are hidden from outer scopes.
+    isolated: bool,
+}
+
+impl Scope {
+    fn root(isolated: bool) -> Self {
+        Self {
+            assigned: Some(HashSet::new()),
+            isolated,
+            ..Default::default()
+        }
+    }
+}
+
+struct UsageCollector<'p> {
+    partials: Option<&'p dyn PartialStore>,
+    scopes: Vec<Scope>,
+    active_partials: Vec<String>,
+    paths: BTreeSet<VariablePath>,
+}
+
+impl<'p> UsageCollector<'p> {
+    /// The global path `name` refers to, or `None` if it is local.
+    fn resolve(&self, name: &str) -> Option<VariablePath> {
+        for scope in self.scopes.iter().rev() {
+            if let Some(alias) = scope.locals.get(name) {
+                return alias.clone();
+            }
+            if scope.assigned.as_ref().is_some_and(|a| a.contains(name)) || scope.isolated {
+                return None;
+            }
+        }
+        Some(VariablePath::new(KString::from_ref(name)))
+    }
+
+    fn variable_path(&self, variable: &Variable) -> Option<VariablePath> {
+        let path = self.resolve(variable.name().to_kstr().as_str())?;
+        let path = variable
+            .indexes()
+            .iter()
+            .fold(path, |path, index| path.push(segment(index)));
+        Some(path)
+    }
+
+    fn binding_path(&self, binding: &Binding<'_>) -> Option<VariablePath> {
+        match binding {
+            Binding::Value(Expression::Variable(variable)) => self.variable_path(variable),
+            Binding::Element(Expression::Variable(variable)) => self
+                .variable_path(variable)
+                .map(|path| path.push(PathSegment::Any)),
+            Binding::Value(_) | Binding::Element(_) | Binding::Other => None,
+        }
+    }
+}
+
+fn segment(index: &Expression) -> PathSegment {
+    let Expression::Literal(literal) = index else {
+        return PathSegment::Any;
+    };
+    match literal.as_scalar() {
+        Some(scalar) => match scalar.as_integer() {
+            Some(index) => PathSegment::Index(index),
+            None => PathSegment::Key(scalar.to_kstr().into_owned()),
+        },
+        None => PathSegment::Any,
+    }
+}
+
+impl<'p> Visitor for UsageCollector<'p> {
+    fn enter_scope(&mut self, locals: &[&str]) {
+        let locals = locals.iter().map(|l| ((*l).to_owned(), None)).collect();
+        self.scopes.push(Scope {
+            locals,
+            ..Default::default()
+        });
+    }
+
+    fn leave_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn visit_binding(&mut self, name: &str, value: Binding<'_>) {
+        // What the name is bound to is read from outside of the scope it is bound in.
+        let scope = self.scopes.pop().expect("bindings are made within a scope");
+        let path = self.binding_path(&value);
+        self.scopes.push(scope);
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.locals.insert(name.to_owned(), path);
+        }
+    }
+
+    fn visit_variable(&mut self, variable: &Variable) {
+        if let Some(path) = self.variable_path(variable) {
+            self.paths.insert(path);
+        }
+        variable.walk(self);
+    }
+
+    fn visit_assign(&mut self, name: &str) {
+        let assigned = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.assigned.as_mut());
+        if let Some(assigned) = assigned {
+            assigned.insert(name.to_owned());
+        }
+    }
+
+    fn visit_partial_call(
+        &mut self,
+        name: &Expression,
+        bindings: &[(&str, Binding<'_>)],
+        isolated: bool,
+    ) {
+        self.visit_expression(name);
+
+        let Expression::Literal(name) = name else {
+            return;
+        };
+        let name = name.to_kstr().into_owned();
+        if self.active_partials.iter().any(|n| n == name.as_str()) {
+            return;
+        }
+        let Some(partial) = self.partials.and_then(|p| p.try_get(name.as_str())) else {
+            return;
+        };
+
+        let locals = bindings
+            .iter()
+            .map(|(local, binding)| ((*local).to_owned(), self.binding_path(binding)))
+            .collect();
+        let mut scope = if isolated {
+            Scope::root(true)
+        } else {
+            Scope::default()
+        };
+        scope.locals = locals;
+
+        self.scopes.push(scope);
+        self.active_partials.push(name.into_string());
+        partial.visit(self);
+        self.active_partials.pop();
+        self.scopes.pop();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::model::Scalar;
+
+    fn read(path: &str) -> Expression {
+        let mut segments = path.split('.');
+        let variable = Variable::with_literal(segments.next().unwrap().to_owned());
+        let variable = segments.fold(variable, |v, s| match s.parse::<i64>() {
+            Ok(i) => v.push_literal(i),
+            Err(_) => v.push_literal(Scalar::new(s.to_owned())),
+        });
+        Expression::Variable(variable)
+    }
+
+    #[derive(Debug)]
+    struct Output(Expression);
+
+    impl Renderable for Output {
+        fn render_to(
+            &self,
+            _writer: &mut dyn std::io::Write,
+            _runtime: &dyn super::super::Runtime,
+        ) -> crate::error::Result<()> {
+            Ok(())
+        }
+
+        fn visit(&self, visitor: &mut dyn Visitor) {
+            visitor.visit_expression(&self.0);
+        }
+    }
+
+    #[derive(Debug)]
+    struct Loop(&'static str, Expression, Vec<Box<dyn Renderable>>);
+
+    impl Renderable for Loop {
+        fn render_to(
+            &self,
+            _writer: &mut dyn std::io::Write,
+            _runtime: &dyn super::super::Runtime,
+        ) -> crate::error::Result<()> {
+            Ok(())
+        }
+
+        fn visit(&self, visitor: &mut dyn Visitor) {
+            visitor.visit_expression(&self.1);
+            visitor.enter_scope(&[self.0]);
+            visitor.visit_binding(self.0, Binding::Element(&self.1));
+            for element in &self.2 {
+                element.visit(visitor);
+            }
+            visitor.leave_scope();
+        }
+    }
+
+    fn paths(template: &dyn Renderable) -> Vec<String> {
+        variable_usage(template, None)
+            .paths()
+            .map(|p| p.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_loop_variables_trace_back() {
+        let template = Loop(
+            "item",
+            read("page.items"),
+            vec![
+                Box::new(Output(read("item.title"))),
+                Box::new(Output(read("title"))),
+            ],
+        );
+        assert_eq!(
+            paths(&template),
+            ["page.items", "page.items[].title", "title"]
+        );
+    }
+
+    #[test]
+    fn test_paths() {
+        let template = Output(read("products.0.my key"));
+        assert_eq!(paths(&template), [r#"products[0]["my key"]"#]);
+    }
+}
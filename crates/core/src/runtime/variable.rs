@@ -7,10 +7,12 @@ use crate::model::{ValueCow, ValueView};
 
 use super::Expression;
 use super::Runtime;
+use super::Visitor;
 
 /// A `Value` reference.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Variable {
+    #[serde(with = "super::serialize::scalar")]
     variable: Scalar,
     indexes: Vec<Expression>,
 }
@@ -30,6 +32,23 @@ impl Variable {
         self
     }
 
+    /// The root variable being referenced.
+    pub fn name(&self) -> &Scalar {
+        &self.variable
+    }
+
+    /// The indexes applied to the root variable.
+    pub fn indexes(&self) -> &[Expression] {
+        &self.indexes
+    }
+
+    /// Walk the expressions used to index into the root variable.
+    pub fn walk<V: Visitor + ?Sized>(&self, visitor: &mut V) {
+        for expr in &self.indexes {
+            visitor.visit_expression(expr);
+        }
+    }
+
     /// Convert to a `Path`.
     pub fn try_evaluate<'c>(&'c self, runtime: &'c dyn Runtime) -> Option<Path<'c>> {
         let mut path = Path::with_index(self.variable.as_ref());
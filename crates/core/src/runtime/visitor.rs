@@ -0,0 +1,87 @@
+use crate::parser::FilterCall;
+use crate::parser::SourceSpan;
+
+use super::Expression;
+use super::Variable;
+
+/// Walks the parse tree of a compiled template.
+///
+/// Every method has a default implementation that continues the walk, so a `Visitor` only needs
+/// to override the callbacks it cares about.
+///
+/// Tags and blocks drive the walk through [`Renderable::visit`][super::Renderable::visit].
+pub trait Visitor {
+    /// Entering a tag or block, e.g. `if`, `for` or `assign`.
+    ///
+    /// Branches of a block, like `else` or `when`, are entered as nested tags.
+    fn enter_tag(&mut self, _name: &str) {}
+
+    /// Leaving the tag or block last entered.
+    fn leave_tag(&mut self, _name: &str) {}
+
+    /// Entering an element parsed from `span` of the template source.
+    fn enter_span(&mut self, _span: &SourceSpan) {}
+
+    /// Leaving the element last entered.
+    fn leave_span(&mut self) {}
+
+    /// Entering a body where `locals` shadow any outer variable, e.g. a `for` loop variable.
+    fn enter_scope(&mut self, _locals: &[&str]) {}
+
+    /// Leaving the scope last entered.
+    fn leave_scope(&mut self) {}
+
+    /// Within the scope last entered, `name` refers to `value`.
+    fn visit_binding(&mut self, _name: &str, _value: Binding<'_>) {}
+
+    /// A value read by the template.
+    fn visit_expression(&mut self, expression: &Expression) {
+        expression.walk(self);
+    }
+
+    /// A value compared by `operator`, e.g. `==` or `contains`, in a condition.
+    fn visit_compared(&mut self, _operator: &str, operand: &Expression) {
+        self.visit_expression(operand);
+    }
+
+    /// A variable read by the template.
+    fn visit_variable(&mut self, variable: &Variable) {
+        variable.walk(self);
+    }
+
+    /// A filter applied to a value.
+    fn visit_filter(&mut self, filter: &FilterCall) {
+        filter.walk(self);
+    }
+
+    /// A partial rendered by `include` or `render`.
+    fn visit_partial(&mut self, name: &Expression) {
+        self.visit_expression(name);
+    }
+
+    /// A partial rendered with `bindings` in scope, after the expressions they read were visited.
+    ///
+    /// The partial can read the caller's variables too, unless `isolated`, as with `render`.
+    fn visit_partial_call(
+        &mut self,
+        name: &Expression,
+        _bindings: &[(&str, Binding<'_>)],
+        _isolated: bool,
+    ) {
+        self.visit_partial(name);
+    }
+
+    /// A variable written by the template, e.g. by `assign` or `capture`.
+    fn visit_assign(&mut self, _name: &str) {}
+}
+
+/// What a name introduced by a tag or block refers to, see `Visitor::visit_binding`.
+#[derive(Copy, Clone, Debug)]
+pub enum Binding<'e> {
+    /// The value of an expression, e.g. a `render` argument.
+    Value(&'e Expression),
+    /// Each element of an expression's value in turn, e.g. a `for` loop variable.
+    Element(&'e Expression),
+    /// A value computed by the tag or block, e.g. a number from a range.
+    Other,
+}
@@ -0,0 +1,61 @@
+use std::fmt;
+
+use crate::error::{Error, Location};
+
+/// The category of a `Warning`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum WarningKind {
+    /// A variable, or object key, that doesn't exist was rendered as `nil`.
+    UndefinedVariable,
+    /// An array index past either end of the array was rendered as `nil`.
+    OutOfRange,
+    /// A filter that doesn't exist was skipped.
+    UndefinedFilter,
+    /// A filter given input of the wrong type was rendered as `nil`.
+    FilterTypeMismatch,
+    /// An `increment` or `decrement` of a counter that wasn't started yet, so it started from 0.
+    UnknownCounter,
+    /// A `cycle` reusing a name with fewer values was restarted.
+    CycleMismatch,
+}
+
+/// A problem a render carried on past.
+#[derive(Clone, Debug)]
+pub struct Warning {
+    kind: WarningKind,
+    error: Error,
+}
+
+impl Warning {
+    /// Create a warning, with `error` describing the problem.
+    pub fn new(kind: WarningKind, error: Error) -> Self {
+        Self { kind, error }
+    }
+
+    /// The category of the warning.
+    pub fn kind(&self) -> WarningKind {
+        self.kind
+    }
+
+    /// Where in the template source the problem occurred, if known.
+    pub fn location(&self) -> Option<&Location> {
+        self.error.location()
+    }
+
+    /// The problem, as it would have been reported had the render failed.
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+
+    /// Convert into the problem, as it would have been reported had the render failed.
+    pub fn into_error(self) -> Error {
+        self.error
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
@@ -0,0 +1,608 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Arc;
+
+use liquid_core::error::ResultLiquidExt;
+use liquid_core::model::{KString, Scalar, Value};
+use liquid_core::runtime::{LimitedWriter, StackFrame, Variable};
+use liquid_core::Expression;
+use liquid_core::Language;
+use liquid_core::Renderable;
+use liquid_core::Template;
+use liquid_core::ValueView;
+use liquid_core::Visitor;
+use liquid_core::{BlockReflection, ParseBlock, TagBlock, TagTokenIter};
+use liquid_core::{Error, Result, Runtime};
+
+/// `{% extends 'parent' %}`: render `parent` in place of this template, with any `{% block %}`
+/// defined after it overriding the block of the same name in `parent`.
+///
+/// The rest of the template is the body of `extends`, so it has no end tag.  It must come first, as
+/// nothing before it would be rendered.  Anything in the body outside of a `{% block %}` is
+/// evaluated but not rendered.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ExtendsBlock;
+
+impl ExtendsBlock {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl BlockReflection for ExtendsBlock {
+    fn start_tag(&self) -> &str {
+        "extends"
+    }
+
+    fn end_tag(&self) -> &str {
+        ""
+    }
+
+    fn description(&self) -> &str {
+        "Renders a parent template, overriding its blocks."
+    }
+}
+
+impl ParseBlock for ExtendsBlock {
+    fn parse(
+        &self,
+        arguments: TagTokenIter<'_>,
+        tokens: TagBlock<'_, '_>,
+        options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        let (parent, body) = parse_parent("extends", arguments, tokens, options)?;
+        Ok(Box::new(Extends { parent, body }))
+    }
+
+    fn reflection(&self) -> &dyn BlockReflection {
+        self
+    }
+}
+
+/// Parses the parent and the body of `{% extends %}` or `{% layout %}`.
+fn parse_parent(
+    tag: &str,
+    mut arguments: TagTokenIter<'_>,
+    mut tokens: TagBlock<'_, '_>,
+    options: &Language,
+) -> Result<(Expression, Template)> {
+    if !arguments.starts_source() {
+        return Err(arguments.raise_error(&format!("`{}` must come first in the template.", tag)));
+    }
+
+    let parent = arguments
+        .expect_next("Identifier or literal expected.")?
+        .expect_value()
+        .into_result()?;
+
+    // no more arguments should be supplied, trying to supply them is an error
+    arguments.expect_nothing()?;
+
+    let body = Template::new(
+        tokens
+            .parse_to_end(options)
+            .trace_with(|| format!("{{% {} {} %}}", tag, parent).into())?,
+    );
+
+    tokens.assert_empty();
+    Ok((parent, body))
+}
+
+/// The name of the template `parent` evaluates to.
+fn parent_name(tag: &str, parent: &Expression, runtime: &dyn Runtime) -> Result<KString> {
+    let value = parent.evaluate(runtime)?;
+    if !value.is_scalar() {
+        return Error::with_msg(format!("Can only `{}` strings", tag))
+            .context("parent", format!("{}", value.source()))
+            .into_err();
+    }
+    Ok(value.to_kstr().into_owned())
+}
+
+/// Renders the template `name` with the variables of `scope` set.
+fn render_parent(
+    name: &KString,
+    scope: &HashMap<KString, Value>,
+    writer: &mut dyn Write,
+    runtime: &dyn Runtime,
+) -> Result<()> {
+    runtime.cancellation().check()?;
+    let _depth = runtime.limits().enter_partial()?;
+    let scope = StackFrame::new(runtime, scope).with_name(name.clone());
+    let parent = scope.partials().get(name)?;
+    parent.render_to(writer, &scope)
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Extends {
+    parent: Expression,
+    body: Template,
+}
+
+impl Extends {
+    fn trace(&self) -> String {
+        format!("{{% extends {} %}}", self.parent)
+    }
+}
+
+impl Renderable for Extends {
+    fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+        let name = parent_name("extends", &self.parent, runtime)?;
+
+        // Our blocks are more derived than those of our parent, so they're collected first.
+        let collecting = std::mem::replace(
+            &mut runtime.registers().get_mut::<BlockRegister>().collecting,
+            true,
+        );
+        let result = self.body.render_to(&mut std::io::sink(), runtime);
+        runtime.registers().get_mut::<BlockRegister>().collecting = collecting;
+        result.trace_with(|| self.trace().into())?;
+
+        let result = render_parent(&name, &HashMap::new(), writer, runtime);
+        if !collecting {
+            // The overrides were used up by rendering the parent, so they shouldn't leak into
+            // later blocks.
+            runtime
+                .registers()
+                .get_mut::<BlockRegister>()
+                .overrides
+                .clear();
+        }
+        result
+            .trace_with(|| self.trace().into())
+            .context_key("parent")
+            .value_with(|| name.to_string().into())
+    }
+
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.enter_tag("extends");
+        self.body.visit(visitor);
+        visitor.visit_partial_call(&self.parent, &[], false);
+        visitor.leave_tag("extends");
+    }
+
+    #[cfg(feature = "serde")]
+    fn as_serialize(&self) -> Option<&dyn liquid_core::runtime::SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[typetag::serde(name = "extends")]
+impl liquid_core::runtime::SerializeRenderable for Extends {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
+}
+
+/// `{% layout 'name' %}`: render the layout `name` in place of this template, with the rest of
+/// this template rendered into its `content` variable.
+///
+/// Like `extends`, it must come first and has no end tag.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LayoutBlock;
+
+impl LayoutBlock {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl BlockReflection for LayoutBlock {
+    fn start_tag(&self) -> &str {
+        "layout"
+    }
+
+    fn end_tag(&self) -> &str {
+        ""
+    }
+
+    fn description(&self) -> &str {
+        "Renders a layout around the rest of the template."
+    }
+}
+
+impl ParseBlock for LayoutBlock {
+    fn parse(
+        &self,
+        arguments: TagTokenIter<'_>,
+        tokens: TagBlock<'_, '_>,
+        options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        let (layout, body) = parse_parent("layout", arguments, tokens, options)?;
+        Ok(Box::new(Layout { layout, body }))
+    }
+
+    fn reflection(&self) -> &dyn BlockReflection {
+        self
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Layout {
+    layout: Expression,
+    body: Template,
+}
+
+impl Layout {
+    fn trace(&self) -> String {
+        format!("{{% layout {} %}}", self.layout)
+    }
+}
+
+impl Renderable for Layout {
+    fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+        let name = parent_name("layout", &self.layout, runtime)?;
+
+        let content = self
+            .body
+            .render(runtime)
+            .trace_with(|| self.trace().into())?;
+        let mut scope = HashMap::<KString, Value>::new();
+        // Already rendered, so it mustn't be escaped again.
+        scope.insert("content".into(), Value::Scalar(Scalar::new_safe(content)));
+
+        render_parent(&name, &scope, writer, runtime)
+            .trace_with(|| self.trace().into())
+            .context_key("layout")
+            .value_with(|| name.to_string().into())
+    }
+
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.enter_tag("layout");
+        self.body.visit(visitor);
+        visitor.visit_partial_call(&self.layout, &[], false);
+        visitor.leave_tag("layout");
+    }
+
+    #[cfg(feature = "serde")]
+    fn as_serialize(&self) -> Option<&dyn liquid_core::runtime::SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[typetag::serde(name = "layout")]
+impl liquid_core::runtime::SerializeRenderable for Layout {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
+}
+
+/// `{% block name %}...{% endblock %}`: a region of a template that templates extending it can
+/// override.
+///
+/// Within an override, `{{ super }}` is the content of the block being overridden.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct BlockBlock;
+
+impl BlockBlock {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl BlockReflection for BlockBlock {
+    fn start_tag(&self) -> &str {
+        "block"
+    }
+
+    fn end_tag(&self) -> &str {
+        "endblock"
+    }
+
+    fn description(&self) -> &str {
+        "A region that templates extending this one can override."
+    }
+}
+
+impl ParseBlock for BlockBlock {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        mut tokens: TagBlock<'_, '_>,
+        options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        let name: KString = arguments
+            .expect_next("Identifier expected")?
+            .expect_identifier()
+            .into_result()?
+            .to_string()
+            .into();
+
+        // no more arguments should be supplied, trying to supply them is an error
+        arguments.expect_nothing()?;
+
+        let template = Template::new(
+            tokens
+                .parse_all(options)
+                .trace_with(|| format!("{{% block {} %}}", &name).into())?,
+        );
+
+        tokens.assert_empty();
+
+        let mut finder = SuperFinder::default();
+        template.visit(&mut finder);
+        let content = Arc::new(BlockContent {
+            template,
+            uses_super: finder.found,
+        });
+        Ok(Box::new(Block { name, content }))
+    }
+
+    fn reflection(&self) -> &dyn BlockReflection {
+        self
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct BlockContent {
+    template: Template,
+    uses_super: bool,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Block {
+    name: KString,
+    content: Arc<BlockContent>,
+}
+
+impl Block {
+    fn trace(&self) -> String {
+        format!("{{% block {} %}}", self.name)
+    }
+}
+
+impl Renderable for Block {
+    fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+        let overrides = {
+            let mut register = runtime.registers().get_mut::<BlockRegister>();
+            if register.collecting {
+                register
+                    .overrides
+                    .entry(self.name.clone())
+                    .or_default()
+                    .push(self.content.clone());
+                return Ok(());
+            }
+            register
+                .overrides
+                .get(&self.name)
+                .cloned()
+                .unwrap_or_default()
+        };
+
+        let mut levels = overrides;
+        levels.push(self.content.clone());
+        render_level(&levels, writer, runtime).trace_with(|| self.trace().into())
+    }
+
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.enter_tag("block");
+        self.content.template.visit(visitor);
+        visitor.leave_tag("block");
+    }
+
+    #[cfg(feature = "serde")]
+    fn as_serialize(&self) -> Option<&dyn liquid_core::runtime::SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[typetag::serde(name = "block")]
+impl liquid_core::runtime::SerializeRenderable for Block {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
+}
+
+/// Render the most derived of `levels`, with the rest available through `super`.
+fn render_level(
+    levels: &[Arc<BlockContent>],
+    writer: &mut dyn Write,
+    runtime: &dyn Runtime,
+) -> Result<()> {
+    let (content, parents) = levels.split_first().expect("a block always has content");
+    if !content.uses_super {
+        return content.template.render_to(writer, runtime);
+    }
+
+    let mut parent = Vec::new();
+    if !parents.is_empty() {
+        let mut writer = LimitedWriter::new(&mut parent, runtime.limits());
+        let result = render_level(parents, &mut writer, runtime);
+        writer.check(result)?;
+    }
+    let parent = String::from_utf8(parent).expect("render only writes UTF-8");
+    let mut scope = HashMap::<KString, Value>::new();
+    // Already rendered, so it mustn't be escaped again.
+    scope.insert("super".into(), Value::Scalar(Scalar::new_safe(parent)));
+    let scope = StackFrame::new(runtime, &scope);
+    content.template.render_to(writer, &scope)
+}
+
+/// Block overrides of the templates being extended, most derived first.
+#[derive(Debug, Default)]
+struct BlockRegister {
+    collecting: bool,
+    overrides: HashMap<KString, Vec<Arc<BlockContent>>>,
+}
+
+#[derive(Default)]
+struct SuperFinder {
+    found: bool,
+}
+
+impl Visitor for SuperFinder {
+    fn visit_variable(&mut self, variable: &Variable) {
+        self.found |= variable.name().to_kstr() == "super";
+        variable.walk(self);
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "stdlib")]
+mod test {
+    use std::sync;
+
+    use liquid_core::parser;
+    use liquid_core::partials;
+    use liquid_core::partials::PartialCompiler;
+    use liquid_core::runtime;
+    use liquid_core::runtime::RuntimeBuilder;
+    use liquid_core::Object;
+
+    use crate::stdlib;
+
+    use super::*;
+
+    fn options() -> Language {
+        let mut options = Language::default();
+        options
+            .blocks
+            .register("extends".to_string(), ExtendsBlock.into());
+        options
+            .blocks
+            .register("layout".to_string(), LayoutBlock.into());
+        options
+            .blocks
+            .register("block".to_string(), BlockBlock.into());
+        options
+            .tags
+            .register("assign".to_string(), stdlib::AssignTag.into());
+        options
+    }
+
+    fn render(text: &str, partials: &[(&str, &str)]) -> Result<String> {
+        let options = sync::Arc::new(options());
+        let mut source = partials::InMemorySource::new();
+        for (name, text) in partials {
+            source.add(*name, *text);
+        }
+        let partials = partials::EagerCompiler::new(source).compile(options.clone())?;
+        let template = parser::parse(text, &options).map(runtime::Template::new)?;
+
+        let runtime = RuntimeBuilder::new()
+            .set_partials(partials.as_ref())
+            .build();
+        template.render(&runtime)
+    }
+
+    const BASE: &str = "<title>{% block title %}Site{% endblock %}</title>\
+        <main>{% block content %}{% endblock %}</main>";
+
+    #[test]
+    fn block_renders_own_content() {
+        let output = render("{% block title %}Site{% endblock %}", &[]).unwrap();
+        assert_eq!(output, "Site");
+    }
+
+    #[test]
+    fn extends_overrides_blocks() {
+        let output = render(
+            "{% extends 'base' %}ignored{% block content %}Hello{% endblock %}",
+            &[("base", BASE)],
+        )
+        .unwrap();
+        assert_eq!(output, "<title>Site</title><main>Hello</main>");
+    }
+
+    #[test]
+    fn extends_must_come_first() {
+        let output = render(
+            "before{% extends 'base' %}{% block content %}Hello{% endblock %}",
+            &[("base", BASE)],
+        );
+        assert!(output.is_err());
+
+        let output = render(
+            "  {%- extends 'base' %}{% block content %}Hello{% endblock %}",
+            &[("base", BASE)],
+        )
+        .unwrap();
+        assert_eq!(output, "<title>Site</title><main>Hello</main>");
+    }
+
+    #[test]
+    fn super_renders_parent_block() {
+        let output = render(
+            "{% extends 'base' %}{% block title %}Page - {{ super }}{% endblock %}",
+            &[("base", BASE)],
+        )
+        .unwrap();
+        assert_eq!(output, "<title>Page - Site</title><main></main>");
+    }
+
+    #[test]
+    fn extends_chains() {
+        let layout = "{% extends 'base' %}\
+            {% block title %}Blog | {{ super }}{% endblock %}\
+            {% block content %}<article>{% block post %}{% endblock %}</article>{% endblock %}";
+        let output = render(
+            "{% extends 'layout' %}\
+             {% assign author = 'Ed' %}\
+             {% block title %}Post | {{ super }}{% endblock %}\
+             {% block post %}By {{ author }}{% endblock %}",
+            &[("base", BASE), ("layout", layout)],
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            "<title>Post | Blog | Site</title><main><article>By Ed</article></main>"
+        );
+    }
+
+    #[test]
+    fn overrides_do_not_leak() {
+        let options = sync::Arc::new(options());
+        let mut source = partials::InMemorySource::new();
+        source.add("base", BASE);
+        let partials = partials::EagerCompiler::new(source)
+            .compile(options.clone())
+            .unwrap();
+        let extends = parser::parse(
+            "{% extends 'base' %}{% block title %}Page{% endblock %}",
+            &options,
+        )
+        .map(runtime::Template::new)
+        .unwrap();
+        let standalone = parser::parse("{% block title %}Site{% endblock %}", &options)
+            .map(runtime::Template::new)
+            .unwrap();
+
+        let globals = Object::new();
+        let runtime = RuntimeBuilder::new()
+            .set_globals(&globals)
+            .set_partials(partials.as_ref())
+            .build();
+        assert_eq!(
+            extends.render(&runtime).unwrap(),
+            "<title>Page</title><main></main>"
+        );
+        assert_eq!(standalone.render(&runtime).unwrap(), "Site");
+    }
+
+    #[test]
+    fn layout_renders_content() {
+        let output = render(
+            "{% layout 'page' %}{% assign title = 'Home' %}<p>Hello</p>",
+            &[("page", "<h1>{{ title }}</h1>{{ content }}")],
+        )
+        .unwrap();
+        assert_eq!(output, "<h1>Home</h1><p>Hello</p>");
+
+        let output = render("<p>{% layout 'page' %}</p>", &[("page", "{{ content }}")]);
+        assert!(output.is_err());
+    }
+
+    #[test]
+    fn extends_missing_parent() {
+        let output = render("{% extends 'base' %}", &[]);
+        assert!(output.is_err());
+    }
+}
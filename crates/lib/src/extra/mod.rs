@@ -1,3 +1,5 @@
 mod date;
+mod inheritance;
 
 pub use self::date::*;
+pub use self::inheritance::*;
@@ -3,10 +3,12 @@ use std::io::Write;
 use liquid_core::error::ResultLiquidExt;
 use liquid_core::model::KString;
 use liquid_core::parser::TryMatchToken;
+use liquid_core::runtime::Binding;
 use liquid_core::Expression;
 use liquid_core::Language;
 use liquid_core::Renderable;
 use liquid_core::ValueView;
+use liquid_core::Visitor;
 use liquid_core::{runtime::StackFrame, Runtime};
 use liquid_core::{Error, Result};
 use liquid_core::{ParseTag, TagReflection, TagTokenIter};
@@ -77,6 +79,7 @@ impl ParseTag for IncludeTag {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Include {
     partial: Expression,
     vars: Vec<(KString, Expression)>,
@@ -105,7 +108,15 @@ impl Renderable for Include {
                 pass_through.insert("include".into(), &helper_vars);
             }
 
-            let scope = StackFrame::new(runtime, &pass_through);
+            runtime
+                .cancellation()
+                .check()
+                .trace_with(|| format!("{{% include {} %}}", self.partial).into())?;
+            let _depth = runtime
+                .limits()
+                .enter_partial()
+                .trace_with(|| format!("{{% include {} %}}", self.partial).into())?;
+            let scope = StackFrame::new(runtime, &pass_through).with_name(name.clone());
             let partial = scope
                 .partials()
                 .get(&name)
@@ -120,6 +131,29 @@ impl Renderable for Include {
 
         Ok(())
     }
+
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.enter_tag("include");
+        for (_, val) in &self.vars {
+            visitor.visit_expression(val);
+        }
+        // The variables are passed as the fields of `include`.
+        visitor.visit_partial_call(&self.partial, &[("include", Binding::Other)], false);
+        visitor.leave_tag("include");
+    }
+
+    #[cfg(feature = "serde")]
+    fn as_serialize(&self) -> Option<&dyn liquid_core::runtime::SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[typetag::serde(name = "jekyll_include")]
+impl liquid_core::runtime::SerializeRenderable for Include {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
 }
 
 #[cfg(test)]
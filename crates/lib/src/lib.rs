@@ -10,13 +10,16 @@ pub mod shopify;
 #[cfg(feature = "stdlib")]
 pub mod stdlib;
 
+use liquid_core::error::ErrorKind;
 use liquid_core::Error;
 
 pub(crate) fn invalid_input<S>(cause: S) -> Error
 where
     S: Into<liquid_core::model::KString>,
 {
-    Error::with_msg("Invalid input").context("cause", cause)
+    Error::with_msg("Invalid input")
+        .context("cause", cause)
+        .with_kind(ErrorKind::InvalidInput)
 }
 
 pub(crate) fn invalid_argument<S>(argument: S, cause: S) -> Error
@@ -1,12 +1,13 @@
 use std::io::Write;
 
 use liquid_core::error::ResultLiquidExt;
-use liquid_core::model::Value;
+use liquid_core::model::{Scalar, Value};
 use liquid_core::Language;
 use liquid_core::Renderable;
 use liquid_core::Result;
 use liquid_core::Runtime;
 use liquid_core::Template;
+use liquid_core::Visitor;
 use liquid_core::{BlockReflection, ParseBlock, TagBlock, TagTokenIter};
 
 #[derive(Copy, Clone, Debug, Default)]
@@ -65,6 +66,7 @@ impl ParseBlock for CaptureBlock {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Capture {
     id: liquid_core::model::KString,
     template: Template,
@@ -78,19 +80,48 @@ impl Capture {
 
 impl Renderable for Capture {
     fn render_to(&self, _writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
-        let mut captured = Vec::new();
-        self.template
-            .render_to(&mut captured, runtime)
+        let output = self
+            .template
+            .render(runtime)
             .trace_with(|| self.trace().into())?;
 
-        let output = String::from_utf8(captured).expect("render only writes UTF-8");
-        runtime.set_global(self.id.clone(), Value::scalar(output));
+        runtime
+            .limits()
+            .check_assign_size(output.len())
+            .trace_with(|| self.trace().into())?;
+        // What was captured was escaped as it was rendered, so it mustn't be escaped again.
+        let output = if runtime.auto_escape() {
+            Value::Scalar(Scalar::new_safe(output))
+        } else {
+            Value::scalar(output)
+        };
+        runtime.set_global(self.id.clone(), output);
         Ok(())
     }
 
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.enter_tag("capture");
+        self.template.visit(visitor);
+        visitor.visit_assign(self.id.as_str());
+        visitor.leave_tag("capture");
+    }
+
     fn is_blank(&self) -> bool {
         true
     }
+
+    #[cfg(feature = "serde")]
+    fn as_serialize(&self) -> Option<&dyn liquid_core::runtime::SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[typetag::serde(name = "capture")]
+impl liquid_core::runtime::SerializeRenderable for Capture {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
 }
 
 #[cfg(test)]
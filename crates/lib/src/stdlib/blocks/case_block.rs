@@ -4,12 +4,14 @@ use liquid_core::error::ResultLiquidExt;
 use liquid_core::model::{ValueView, ValueViewCmp};
 use liquid_core::parser::BlockElement;
 use liquid_core::parser::TryMatchToken;
+use liquid_core::runtime::Condition;
 use liquid_core::Expression;
 use liquid_core::Language;
 use liquid_core::Renderable;
 use liquid_core::Result;
 use liquid_core::Runtime;
 use liquid_core::Template;
+use liquid_core::Visitor;
 use liquid_core::{BlockReflection, ParseBlock, TagBlock, TagTokenIter};
 
 #[derive(Copy, Clone, Debug, Default)]
@@ -49,10 +51,11 @@ impl ParseBlock for CaseBlock {
         mut tokens: TagBlock<'_, '_>,
         options: &Language,
     ) -> Result<Box<dyn Renderable>> {
-        let target = arguments
-            .expect_next("Value expected.")?
-            .expect_value()
-            .into_result()?;
+        // A condition, like `case x > 5`, is matched by `when true` or `when false`.
+        let target = match arguments.expect_condition()? {
+            Condition::Existence(target) => target.lh().clone(),
+            condition => Expression::Condition(Box::new(condition)),
+        };
 
         // no more arguments should be supplied, trying to supply them is an error
         arguments.expect_nothing()?;
@@ -154,6 +157,7 @@ fn parse_condition(arguments: &mut TagTokenIter<'_>) -> Result<Vec<Expression>>
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Case {
     is_blank: bool,
     target: Expression,
@@ -194,13 +198,45 @@ impl Renderable for Case {
         Ok(())
     }
 
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.enter_tag("case");
+        visitor.visit_compared("==", &self.target);
+        for case in &self.cases {
+            visitor.enter_tag("when");
+            for arg in &case.args {
+                visitor.visit_compared("==", arg);
+            }
+            case.template.visit(visitor);
+            visitor.leave_tag("when");
+        }
+        if let Some(ref t) = self.else_block {
+            visitor.enter_tag("else");
+            t.visit(visitor);
+            visitor.leave_tag("else");
+        }
+        visitor.leave_tag("case");
+    }
+
     fn is_blank(&self) -> bool {
         self.is_blank
     }
 
+    #[cfg(feature = "serde")]
+    fn as_serialize(&self) -> Option<&dyn liquid_core::runtime::SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[typetag::serde(name = "case")]
+impl liquid_core::runtime::SerializeRenderable for Case {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct CaseOption {
     args: Vec<Expression>,
     template: Template,
@@ -297,6 +333,37 @@ mod test {
         assert_eq!(template.render(&runtime).unwrap(), "");
     }
 
+    #[test]
+    fn test_case_condition() {
+        let text = concat!(
+            "{% case x < 10 and x != 5 %}",
+            "{% when true %}",
+            "small",
+            "{% when false %}",
+            "large",
+            "{% endcase %}"
+        );
+        let options = options();
+        let template = parser::parse(text, &options)
+            .map(runtime::Template::new)
+            .unwrap();
+
+        let runtime = RuntimeBuilder::new().build();
+        runtime.set_global("x".into(), Value::scalar(3));
+        assert_eq!(template.render(&runtime).unwrap(), "small");
+
+        runtime.set_global("x".into(), Value::scalar(5));
+        assert_eq!(template.render(&runtime).unwrap(), "large");
+    }
+
+    #[test]
+    fn case_without_target_is_an_error() {
+        let text = "{% case %}{% when true %}yes{% endcase %}";
+        let options = options();
+        let template = parser::parse(text, &options).map(runtime::Template::new);
+        assert!(template.is_err());
+    }
+
     #[test]
     fn multiple_else_blocks_is_an_error() {
         let text = concat!(
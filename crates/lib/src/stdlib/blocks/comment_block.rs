@@ -64,6 +64,7 @@ impl ParseBlock for CommentBlock {
 }
 
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Comment;
 
 impl Renderable for Comment {
@@ -74,6 +75,19 @@ impl Renderable for Comment {
     fn is_blank(&self) -> bool {
         true
     }
+
+    #[cfg(feature = "serde")]
+    fn as_serialize(&self) -> Option<&dyn liquid_core::runtime::SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[typetag::serde(name = "comment")]
+impl liquid_core::runtime::SerializeRenderable for Comment {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
 }
 
 #[cfg(test)]
@@ -5,11 +5,13 @@ use liquid_core::error::{ResultLiquidExt, ResultLiquidReplaceExt};
 use liquid_core::model::{Object, ObjectView, Value, ValueCow, ValueView};
 use liquid_core::parser::BlockElement;
 use liquid_core::parser::TryMatchToken;
+use liquid_core::runtime::Binding;
 use liquid_core::runtime::{Interrupt, InterruptRegister};
 use liquid_core::Expression;
 use liquid_core::Language;
 use liquid_core::Renderable;
 use liquid_core::Template;
+use liquid_core::Visitor;
 use liquid_core::{runtime::StackFrame, Runtime};
 use liquid_core::{BlockReflection, ParseBlock, TagBlock, TagTokenIter};
 use liquid_core::{Error, Result};
@@ -71,7 +73,7 @@ impl ParseBlock for ForBlock {
         while let Some(token) = arguments.next() {
             match token.as_str() {
                 "limit" => limit = Some(parse_attr(&mut arguments)?),
-                "offset" => offset = Some(parse_attr(&mut arguments)?),
+                "offset" => offset = Some(parse_offset(&mut arguments)?),
                 "reversed" => reversed = true,
                 _ => {
                     return token
@@ -118,6 +120,7 @@ impl ParseBlock for ForBlock {
         tokens.assert_empty();
         Ok(Box::new(For {
             var_name: liquid_core::model::KString::from_ref(var_name),
+            offset_key: range.to_string(),
             range,
             item_template,
             else_template,
@@ -134,14 +137,17 @@ impl ParseBlock for ForBlock {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct For {
     var_name: liquid_core::model::KString,
+    /// Where this loop's stopping point is remembered, for `offset: continue`.
+    offset_key: String,
     range: RangeExpression,
     item_template: Template,
     else_template: Option<Template>,
     is_blank: bool,
     limit: Option<Expression>,
-    offset: Option<Expression>,
+    offset: Option<OffsetExpression>,
     reversed: bool,
 }
 
@@ -161,7 +167,7 @@ fn trace_for_tag(
     var_name: &str,
     range: &RangeExpression,
     limit: &Option<Expression>,
-    offset: &Option<Expression>,
+    offset: &Option<OffsetExpression>,
     reversed: bool,
 ) -> String {
     let mut parameters = vec![];
@@ -188,10 +194,27 @@ impl Renderable for For {
             .range
             .evaluate(runtime)
             .trace_with(|| self.trace().into())?;
-        let array = range.evaluate()?;
         let limit = evaluate_attr(&self.limit, runtime)?;
-        let offset = evaluate_attr(&self.offset, runtime)?.unwrap_or(0);
-        let array = iter_array(array, limit, offset, self.reversed);
+        let offset = match self.offset {
+            Some(OffsetExpression::Continue) => runtime
+                .registers()
+                .get_mut::<OffsetRegister>()
+                .offset(&self.offset_key),
+            Some(OffsetExpression::Value(ref offset)) => evaluate_int(offset, runtime)?,
+            None => 0,
+        };
+        let array = range.iter(limit, offset, self.reversed)?;
+        {
+            let stop = offset + array.len();
+            let mut offsets = runtime.registers().get_mut::<OffsetRegister>();
+            if limit.is_some() || self.offset.is_some() {
+                offsets.stopped_at(&self.offset_key, stop);
+            } else {
+                // A loop over the whole collection only needs to move a stopping point that an
+                // earlier, partial loop left behind.
+                offsets.moved_to(&self.offset_key, stop);
+            }
+        }
 
         match array.len() {
             0 => {
@@ -205,7 +228,15 @@ impl Renderable for For {
             range_len => {
                 let parentloop = runtime.try_get(&[liquid_core::model::Scalar::new("forloop")]);
                 let parentloop_ref = parentloop.as_ref().map(|v| v.as_view());
-                for (i, v) in array.into_iter().enumerate() {
+                for (i, v) in array.enumerate() {
+                    runtime
+                        .cancellation()
+                        .check()
+                        .trace_with(|| self.trace().into())?;
+                    runtime
+                        .limits()
+                        .count_iteration()
+                        .trace_with(|| self.trace().into())?;
                     let forloop = ForloopObject::new(i, range_len).parentloop(parentloop_ref);
                     let mut root = std::collections::HashMap::<
                         liquid_core::model::KStringRef<'_>,
@@ -236,9 +267,69 @@ impl Renderable for For {
         Ok(())
     }
 
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.enter_tag("for");
+        self.range.walk(visitor);
+        visit_attr(&self.limit, visitor);
+        if let Some(OffsetExpression::Value(ref offset)) = self.offset {
+            visitor.visit_expression(offset);
+        }
+        visitor.enter_scope(&[self.var_name.as_str(), "forloop"]);
+        visitor.visit_binding(self.var_name.as_str(), self.range.binding());
+        self.item_template.visit(visitor);
+        visitor.leave_scope();
+        if let Some(ref t) = self.else_template {
+            visitor.enter_tag("else");
+            t.visit(visitor);
+            visitor.leave_tag("else");
+        }
+        visitor.leave_tag("for");
+    }
+
     fn is_blank(&self) -> bool {
         self.is_blank
     }
+
+    #[cfg(feature = "serde")]
+    fn as_serialize(&self) -> Option<&dyn liquid_core::runtime::SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[typetag::serde(name = "for")]
+impl liquid_core::runtime::SerializeRenderable for For {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
+}
+
+/// Remembers where each `for` loop stopped, for `offset: continue`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+struct OffsetRegister {
+    offsets: std::collections::HashMap<String, usize>,
+}
+
+impl OffsetRegister {
+    /// Where the last loop over `collection` stopped, or the start if there was none.
+    fn offset(&self, collection: &str) -> usize {
+        self.offsets.get(collection).copied().unwrap_or(0)
+    }
+
+    fn stopped_at(&mut self, collection: &str, offset: usize) {
+        match self.offsets.get_mut(collection) {
+            Some(stop) => *stop = offset,
+            None => {
+                self.offsets.insert(collection.to_owned(), offset);
+            }
+        }
+    }
+
+    fn moved_to(&mut self, collection: &str, offset: usize) {
+        if let Some(stop) = self.offsets.get_mut(collection) {
+            *stop = offset;
+        }
+    }
 }
 
 #[derive(Debug, Clone, ValueView, ObjectView)]
@@ -366,6 +457,7 @@ impl ParseBlock for TableRowBlock {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct TableRow {
     var_name: liquid_core::model::KString,
     range: RangeExpression,
@@ -418,18 +510,25 @@ impl Renderable for TableRow {
             .range
             .evaluate(runtime)
             .trace_with(|| self.trace().into())?;
-        let array = range.evaluate()?;
         let cols = evaluate_attr(&self.cols, runtime)?;
         let limit = evaluate_attr(&self.limit, runtime)?;
         let offset = evaluate_attr(&self.offset, runtime)?.unwrap_or(0);
-        let array = iter_array(array, limit, offset, false);
+        let array = range.iter(limit, offset, false)?;
 
         let mut helper_vars = Object::new();
 
         let range_len = array.len();
         helper_vars.insert("length".into(), Value::scalar(range_len as i64));
 
-        for (i, v) in array.into_iter().enumerate() {
+        for (i, v) in array.enumerate() {
+            runtime
+                .cancellation()
+                .check()
+                .trace_with(|| self.trace().into())?;
+            runtime
+                .limits()
+                .count_iteration()
+                .trace_with(|| self.trace().into())?;
             let cols = cols.unwrap_or(range_len);
             let col_index = i % cols;
             let row_index = i / cols;
@@ -464,10 +563,35 @@ impl Renderable for TableRow {
         Ok(())
     }
 
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.enter_tag("tablerow");
+        self.range.walk(visitor);
+        visit_attr(&self.cols, visitor);
+        visit_attr(&self.limit, visitor);
+        visit_attr(&self.offset, visitor);
+        visitor.enter_scope(&[self.var_name.as_str(), "tablerow"]);
+        visitor.visit_binding(self.var_name.as_str(), self.range.binding());
+        self.item_template.visit(visitor);
+        visitor.leave_scope();
+        visitor.leave_tag("tablerow");
+    }
+
     fn is_blank(&self) -> bool {
         false
     }
 
+    #[cfg(feature = "serde")]
+    fn as_serialize(&self) -> Option<&dyn liquid_core::runtime::SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[typetag::serde(name = "tablerow")]
+impl liquid_core::runtime::SerializeRenderable for TableRow {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
 }
 
 #[derive(Debug, Clone, ValueView, ObjectView)]
@@ -524,29 +648,92 @@ fn parse_attr(arguments: &mut TagTokenIter<'_>) -> Result<Expression> {
         .into_result()
 }
 
+/// Parses the value of `offset`, which may be `continue`.
+fn parse_offset(arguments: &mut TagTokenIter<'_>) -> Result<OffsetExpression> {
+    arguments
+        .expect_next("\":\" expected.")?
+        .expect_str(":")
+        .into_result_custom_msg("\":\" expected.")?;
+
+    let token = arguments.expect_next("Value expected.")?;
+    if token.as_str() == "continue" {
+        return Ok(OffsetExpression::Continue);
+    }
+    token
+        .expect_value()
+        .into_result()
+        .map(OffsetExpression::Value)
+}
+
 /// Evaluates an attribute, returning Ok(None) if input is also None.
 fn evaluate_attr(attr: &Option<Expression>, runtime: &dyn Runtime) -> Result<Option<usize>> {
-    match attr {
-        Some(attr) => {
-            let value = attr.evaluate(runtime)?;
-            let value = value
-                .as_scalar()
-                .and_then(|s| s.to_integer())
-                .ok_or_else(|| unexpected_value_error("whole number", Some(value.type_name())))?
-                as usize;
-            Ok(Some(value))
+    attr.as_ref()
+        .map(|attr| evaluate_int(attr, runtime))
+        .transpose()
+}
+
+/// Evaluates an attribute that must be a whole number.
+fn evaluate_int(attr: &Expression, runtime: &dyn Runtime) -> Result<usize> {
+    let value = attr.evaluate(runtime)?;
+    let value = value
+        .as_scalar()
+        .and_then(|s| s.to_integer())
+        .ok_or_else(|| unexpected_value_error("whole number", Some(value.type_name())))?
+        as usize;
+    Ok(value)
+}
+
+/// Walks an attribute, if present.
+fn visit_attr(attr: &Option<Expression>, visitor: &mut dyn Visitor) {
+    if let Some(attr) = attr {
+        visitor.visit_expression(attr);
+    }
+}
+
+/// Where a `for` loop starts in its collection.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum OffsetExpression {
+    Value(Expression),
+    /// Where the previous loop over the same collection stopped.
+    Continue,
+}
+
+impl fmt::Display for OffsetExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            OffsetExpression::Value(ref offset) => write!(f, "{}", offset),
+            OffsetExpression::Continue => write!(f, "continue"),
         }
-        None => Ok(None),
     }
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RangeExpression {
     Array(Expression),
     Counted(Expression, Expression),
 }
 
 impl RangeExpression {
+    /// What a loop variable over the range refers to.
+    pub fn binding(&self) -> Binding<'_> {
+        match *self {
+            RangeExpression::Array(ref array_id) => Binding::Element(array_id),
+            RangeExpression::Counted(..) => Binding::Other,
+        }
+    }
+
+    pub fn walk(&self, visitor: &mut dyn Visitor) {
+        match *self {
+            RangeExpression::Array(ref array_id) => visitor.visit_expression(array_id),
+            RangeExpression::Counted(ref start_arg, ref stop_arg) => {
+                visitor.visit_expression(start_arg);
+                visitor.visit_expression(stop_arg);
+            }
+        }
+    }
+
     pub fn evaluate<'r>(&'r self, runtime: &'r dyn Runtime) -> Result<Range<'r>> {
         let range = match *self {
             RangeExpression::Array(ref array_id) => {
@@ -593,8 +780,92 @@ impl<'r> Range<'r> {
 
         Ok(range)
     }
+
+    /// The values of the range, skipping `offset` of them and taking up to `limit`.
+    ///
+    /// Unlike `evaluate`, a counted range is not materialized, so its values are only created as
+    /// they are iterated.
+    pub fn iter(
+        &self,
+        limit: Option<usize>,
+        offset: usize,
+        reversed: bool,
+    ) -> Result<RangeIter<'_>> {
+        let iter = match self {
+            Range::Array(array) => {
+                let array = get_array(array.as_view())?;
+                RangeIter::Array(iter_array(array, limit, offset, reversed).into_iter())
+            }
+
+            Range::Counted(start, stop) => {
+                let len = (i128::from(*stop) - i128::from(*start) + 1).max(0);
+                let offset = (offset as i128).min(len);
+                let len = (len - offset).min(limit.map(|l| l as i128).unwrap_or(len));
+                let first = i128::from(*start) + offset;
+                let (next, step) = if reversed {
+                    (first + len - 1, -1)
+                } else {
+                    (first, 1)
+                };
+                RangeIter::Counted {
+                    next: next as i64,
+                    step,
+                    // Longer ranges are cut short, as no limit could allow that many iterations.
+                    remaining: usize::try_from(len).unwrap_or(usize::MAX),
+                }
+            }
+        };
+
+        Ok(iter)
+    }
+}
+
+/// The values of a `Range`, see `Range::iter`.
+#[derive(Debug)]
+pub enum RangeIter<'r> {
+    Array(std::vec::IntoIter<ValueCow<'r>>),
+    Counted {
+        next: i64,
+        step: i64,
+        remaining: usize,
+    },
+}
+
+impl<'r> Iterator for RangeIter<'r> {
+    type Item = ValueCow<'r>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            RangeIter::Array(iter) => iter.next(),
+            RangeIter::Counted {
+                next,
+                step,
+                remaining,
+            } => {
+                if *remaining == 0 {
+                    return None;
+                }
+                let value = *next;
+                *remaining -= 1;
+                if *remaining != 0 {
+                    *next += *step;
+                }
+                Some(Value::scalar(value).into())
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = match self {
+            RangeIter::Array(iter) => iter.len(),
+            RangeIter::Counted { remaining, .. } => *remaining,
+        };
+        (len, Some(len))
+    }
 }
 
+impl<'r> ExactSizeIterator for RangeIter<'r> {}
+
 fn get_array(array: &dyn ValueView) -> Result<Vec<ValueCow<'_>>> {
     if let Some(x) = array.as_array() {
         Ok(x.values().map(ValueCow::Borrowed).collect())
@@ -881,7 +1152,6 @@ mod test {
         let output = template.render(&runtime).unwrap();
         assert_eq!(output, "");
 
-
         let text = concat!(
             "{% for i in (1..5) offset: 3 limit: 3 %}",
             "{{ i }} ",
@@ -928,6 +1198,44 @@ mod test {
         assert_eq!(output, "6 5 4 3 2 ");
     }
 
+    #[test]
+    fn offset_continue_loop() {
+        let text = concat!(
+            "{% for i in array limit:3 %}{{ i }} {% endfor %}",
+            "| {% for i in array limit:3 offset:continue %}{{ i }} {% endfor %}",
+            "| {% for i in array offset: continue %}{{ i }} {% endfor %}",
+            "| {% for i in array offset:continue %}{{ i }} {% else %}done{% endfor %}",
+        );
+        let template = parser::parse(text, &options())
+            .map(runtime::Template::new)
+            .unwrap();
+
+        let runtime = RuntimeBuilder::new().build();
+        runtime.set_global(
+            "array".into(),
+            Value::Array((1..=8).map(Value::scalar).collect()),
+        );
+        let output = template.render(&runtime).unwrap();
+        assert_eq!(output, "1 2 3 | 4 5 6 | 7 8 | done");
+    }
+
+    #[test]
+    fn offset_continue_is_per_collection() {
+        let text = concat!(
+            "{% for i in (1..5) limit:2 %}{{ i }} {% endfor %}",
+            "| {% for j in (1..5) limit:2 offset:continue %}{{ j }} {% endfor %}",
+            "| {% for i in (1..3) offset:continue %}{{ i }} {% endfor %}",
+            "| {% for i in (1..5) offset:continue reversed %}{{ i }} {% endfor %}",
+        );
+        let template = parser::parse(text, &options())
+            .map(runtime::Template::new)
+            .unwrap();
+
+        let runtime = RuntimeBuilder::new().build();
+        let output = template.render(&runtime).unwrap();
+        assert_eq!(output, "1 2 | 3 4 | 1 2 3 | 5 ");
+    }
+
     #[test]
     fn empty_loop_invokes_else_template() {
         let text = concat!(
@@ -1244,4 +1552,36 @@ mod test {
         let output = template.render(&runtime).unwrap();
         assert_eq!(output, "1.1 1.2 1.3 2.1 2.2 2.3 ");
     }
+
+    #[test]
+    fn test_counted_range_iter() {
+        let values = |range: Range<'_>, limit, offset, reversed| -> Vec<i64> {
+            range
+                .iter(limit, offset, reversed)
+                .unwrap()
+                .map(|v| v.as_scalar().unwrap().to_integer().unwrap())
+                .collect()
+        };
+        assert_eq!(
+            values(Range::Counted(1, 5), None, 0, false),
+            [1, 2, 3, 4, 5]
+        );
+        assert_eq!(values(Range::Counted(1, 5), Some(2), 1, true), [3, 2]);
+        assert_eq!(values(Range::Counted(1, 5), Some(9), 4, false), [5]);
+        assert_eq!(
+            values(Range::Counted(1, 5), None, 9, false),
+            Vec::<i64>::new()
+        );
+        assert_eq!(
+            values(Range::Counted(5, 1), None, 0, false),
+            Vec::<i64>::new()
+        );
+        assert_eq!(
+            Range::Counted(i64::MIN, i64::MAX)
+                .iter(Some(3), 0, true)
+                .unwrap()
+                .len(),
+            3
+        );
+    }
 }
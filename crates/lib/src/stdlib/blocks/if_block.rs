@@ -1,17 +1,15 @@
-use std::fmt;
 use std::io::Write;
 
 use liquid_core::error::ResultLiquidExt;
-use liquid_core::model::{ValueView, ValueViewCmp};
 use liquid_core::parser::BlockElement;
-use liquid_core::parser::TagToken;
-use liquid_core::Expression;
+use liquid_core::runtime::Condition;
 use liquid_core::Language;
 use liquid_core::Renderable;
+use liquid_core::Result;
 use liquid_core::Runtime;
 use liquid_core::Template;
+use liquid_core::Visitor;
 use liquid_core::{BlockReflection, ParseBlock, TagBlock, TagTokenIter};
-use liquid_core::{Error, Result};
 
 #[derive(Copy, Clone, Debug, Default)]
 pub struct IfBlock;
@@ -55,11 +53,11 @@ impl ParseBlock for IfBlock {
 }
 
 fn parse_if(
-    arguments: TagTokenIter<'_>,
+    mut arguments: TagTokenIter<'_>,
     tokens: &mut TagBlock<'_, '_>,
     options: &Language,
 ) -> Result<Box<dyn Renderable>> {
-    let condition = parse_condition(arguments)?;
+    let condition = arguments.expect_condition()?;
 
     let mut if_true: Vec<Box<dyn Renderable>> = Vec::new();
     let mut if_false = None;
@@ -127,11 +125,11 @@ impl BlockReflection for UnlessBlock {
 impl ParseBlock for UnlessBlock {
     fn parse(
         &self,
-        arguments: TagTokenIter<'_>,
+        mut arguments: TagTokenIter<'_>,
         mut tokens: TagBlock<'_, '_>,
         options: &Language,
     ) -> Result<Box<dyn Renderable>> {
-        let condition = parse_condition(arguments)?;
+        let condition = arguments.expect_condition()?;
 
         let mut if_true = Vec::new();
         let mut if_false = None;
@@ -177,6 +175,7 @@ impl ParseBlock for UnlessBlock {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Conditional {
     condition: Condition,
     mode: bool,
@@ -195,6 +194,14 @@ impl Conditional {
     fn trace(&self) -> String {
         format!("{{% if {} %}}", self.condition)
     }
+
+    fn tag_name(&self) -> &'static str {
+        if self.mode {
+            "if"
+        } else {
+            "unless"
+        }
+    }
 }
 
 impl Renderable for Conditional {
@@ -228,281 +235,34 @@ impl Renderable for Conditional {
         Ok(())
     }
 
-    fn is_blank(&self) -> bool {
-        self.is_blank
-    }
-}
-
-#[derive(Clone, Debug)]
-enum Condition {
-    Binary(BinaryCondition),
-    Existence(ExistenceCondition),
-    Conjunction(Box<Condition>, Box<Condition>),
-    Disjunction(Box<Condition>, Box<Condition>),
-}
-
-impl Condition {
-    pub fn evaluate(&self, runtime: &dyn Runtime) -> Result<bool> {
-
-        let res = match *self {
-            Condition::Binary(ref c) => c.evaluate(runtime),
-            Condition::Existence(ref c) => c.evaluate(runtime),
-            Condition::Conjunction(ref left, ref right) => {
-                let rt = right.evaluate(runtime)?;
-                let lt = left.evaluate(runtime)?;
-                Ok(rt && lt)
-            }
-            Condition::Disjunction(ref left, ref right) => {
-                let rt = right.evaluate(runtime)?;
-                let lt = left.evaluate(runtime)?;
-                Ok(rt || lt)
-            }
-        };
-
-        //std::io::stdout().write_all(&format!("Evaluate self={} res={:?}\n", self, res).as_bytes());
-        res
-    }
-}
-
-impl fmt::Display for Condition {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match *self {
-            Condition::Binary(ref c) => write!(f, "({})", c),
-            Condition::Existence(ref c) => write!(f, "({})", c),
-            Condition::Conjunction(ref left, ref right) => write!(f, "({} and {})", left, right),
-            Condition::Disjunction(ref left, ref right) => write!(f, "({} or {})", left, right),
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.enter_tag(self.tag_name());
+        self.condition.walk(visitor);
+        self.if_true.visit(visitor);
+        if let Some(ref template) = self.if_false {
+            visitor.enter_tag("else");
+            template.visit(visitor);
+            visitor.leave_tag("else");
         }
+        visitor.leave_tag(self.tag_name());
     }
-}
 
-#[derive(Clone, Debug)]
-struct BinaryCondition {
-    lh: Expression,
-    comparison: ComparisonOperator,
-    rh: Expression,
-}
-
-impl BinaryCondition {
-    pub fn evaluate(&self, runtime: &dyn Runtime) -> Result<bool> {
-        let a = self.lh.evaluate(runtime)?;
-        let ca = ValueViewCmp::new(a.as_view());
-        let b = self.rh.evaluate(runtime)?;
-        let cb = ValueViewCmp::new(b.as_view());
-
-        let result = match self.comparison {
-            ComparisonOperator::Equals => ca == cb,
-            ComparisonOperator::NotEquals => ca != cb,
-            ComparisonOperator::LessThan => ca < cb,
-            ComparisonOperator::GreaterThan => ca > cb,
-            ComparisonOperator::LessThanEquals => ca <= cb,
-            ComparisonOperator::GreaterThanEquals => ca >= cb,
-            ComparisonOperator::Contains => contains_check(a.as_view(), b.as_view())?,
-        };
-        Ok(result)
-    }
-}
-
-impl fmt::Display for BinaryCondition {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} {} {}", self.lh, self.comparison, self.rh)
-    }
-}
-
-fn contains_check(a: &dyn ValueView, b: &dyn ValueView) -> Result<bool> {
-    if let Some(a) = a.as_scalar() {
-        let b = b.to_kstr();
-        Ok(a.to_kstr().contains(b.as_str()))
-    } else if let Some(a) = a.as_object() {
-        let b = b.as_scalar();
-        let check = b
-            .map(|b| a.contains_key(b.to_kstr().as_str()))
-            .unwrap_or(false);
-        Ok(check)
-    } else if let Some(a) = a.as_array() {
-        for elem in a.values() {
-            if ValueViewCmp::new(elem) == ValueViewCmp::new(b) {
-                return Ok(true);
-            }
-        }
-        Ok(false)
-    } else {
-        Err(unexpected_value_error(
-            "string | array | object",
-            Some(a.type_name()),
-        ))
-    }
-}
-
-#[derive(Clone, Debug)]
-enum ComparisonOperator {
-    Equals,
-    NotEquals,
-    LessThan,
-    GreaterThan,
-    LessThanEquals,
-    GreaterThanEquals,
-    Contains,
-}
-
-impl fmt::Display for ComparisonOperator {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let out = match *self {
-            ComparisonOperator::Equals => "==",
-            ComparisonOperator::NotEquals => "!=",
-            ComparisonOperator::LessThanEquals => "<=",
-            ComparisonOperator::GreaterThanEquals => ">=",
-            ComparisonOperator::LessThan => "<",
-            ComparisonOperator::GreaterThan => ">",
-            ComparisonOperator::Contains => "contains",
-        };
-        write!(f, "{}", out)
-    }
-}
-
-impl ComparisonOperator {
-    fn from_str(s: &str) -> ::std::result::Result<Self, ()> {
-        match s {
-            "==" => Ok(ComparisonOperator::Equals),
-            "!=" | "<>" => Ok(ComparisonOperator::NotEquals),
-            "<" => Ok(ComparisonOperator::LessThan),
-            ">" => Ok(ComparisonOperator::GreaterThan),
-            "<=" => Ok(ComparisonOperator::LessThanEquals),
-            ">=" => Ok(ComparisonOperator::GreaterThanEquals),
-            "contains" => Ok(ComparisonOperator::Contains),
-            _ => Err(()),
-        }
-    }
-}
-
-#[derive(Clone, Debug)]
-struct ExistenceCondition {
-    lh: Expression,
-}
-
-impl ExistenceCondition {
-    pub fn evaluate(&self, runtime: &dyn Runtime) -> Result<bool> {
-        let a = self.lh.try_evaluate(runtime);
-        let a = a.unwrap_or_default();
-        let is_truthy = a.query_state(liquid_core::model::State::Truthy);
-        Ok(is_truthy)
-    }
-}
-
-impl fmt::Display for ExistenceCondition {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.lh)
-    }
-}
-
-struct PeekableTagTokenIter<'a> {
-    iter: TagTokenIter<'a>,
-    peeked: Option<Option<TagToken<'a>>>,
-}
-
-impl<'a> Iterator for PeekableTagTokenIter<'a> {
-    type Item = TagToken<'a>;
-
-    fn next(&mut self) -> Option<TagToken<'a>> {
-        match self.peeked.take() {
-            Some(v) => v,
-            None => self.iter.next(),
-        }
-    }
-}
-
-impl<'a> PeekableTagTokenIter<'a> {
-    pub fn expect_next(&mut self, error_msg: &str) -> Result<TagToken<'a>> {
-        self.next().ok_or_else(|| self.iter.raise_error(error_msg))
+    fn is_blank(&self) -> bool {
+        self.is_blank
     }
 
-    fn peek(&mut self) -> Option<&TagToken<'a>> {
-        if self.peeked.is_none() {
-            self.peeked = Some(self.iter.next());
-        }
-        match self.peeked {
-            Some(Some(ref value)) => Some(value),
-            Some(None) => None,
-            None => unreachable!(),
-        }
+    #[cfg(feature = "serde")]
+    fn as_serialize(&self) -> Option<&dyn liquid_core::runtime::SerializeRenderable> {
+        Some(self)
     }
 }
 
-fn parse_atom_condition(arguments: &mut PeekableTagTokenIter<'_>) -> Result<Condition> {
-    let lh = arguments
-        .expect_next("Value expected.")?
-        .expect_value()
-        .into_result()?;
-    let cond = match arguments
-        .peek()
-        .map(TagToken::as_str)
-        .and_then(|op| ComparisonOperator::from_str(op).ok())
-    {
-        Some(op) => {
-            arguments.next();
-            let rh = arguments
-                .expect_next("Value expected.")?
-                .expect_value()
-                .into_result()?;
-            Condition::Binary(BinaryCondition {
-                lh,
-                comparison: op,
-                rh,
-            })
-        }
-        None => Condition::Existence(ExistenceCondition { lh }),
-    };
-
-    Ok(cond)
-}
-
-fn parse_conjunction_chain(arguments: &mut PeekableTagTokenIter<'_>) -> Result<Condition> {
-    let mut lh = parse_atom_condition(arguments)?;
-
-    if let Some(val) = arguments.peek().map(TagToken::as_str).map(|f| f.to_ascii_lowercase()) {
-        if val.as_str() == "and" {
-            arguments.next();
-            let rh = parse_conjunction_chain(arguments)?;
-            lh = Condition::Conjunction(Box::new(rh), Box::new(lh));
-        } else if val.as_str() == "or" {
-            arguments.next();
-            let rh = parse_conjunction_chain(arguments)?;
-            lh = Condition::Disjunction(Box::new(rh), Box::new(lh));
-        }
+#[cfg(feature = "serde")]
+#[typetag::serde(name = "if")]
+impl liquid_core::runtime::SerializeRenderable for Conditional {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
     }
-
-    Ok(lh)
-}
-
-/// Common parsing for "if" and "unless" condition
-fn parse_condition(arguments: TagTokenIter<'_>) -> Result<Condition> {
-    let mut arguments = PeekableTagTokenIter {
-        iter: arguments,
-        peeked: None,
-    };
-    let lh = parse_conjunction_chain(&mut arguments)?;
-    //std::io::stdout().write_all(&format!("Parsed condition={} \n", lh).as_bytes());
-
-    // while let Some(token) = arguments.next() {
-    //     token
-    //         .expect_case_insensitive_str("or")
-    //         .into_result_custom_msg("\"and\" or \"or\" expected.")?;
-
-    //     let rh = parse_conjunction_chain(&mut arguments)?;
-    //     lh = Condition::Disjunction(Box::new(lh), Box::new(rh));
-    // }
-
-    Ok(lh)
-}
-
-/// Format an error for an unexpected value.
-fn unexpected_value_error<S: ToString>(expected: &str, actual: Option<S>) -> Error {
-    let actual = actual.map(|x| x.to_string());
-    unexpected_value_error_string(expected, actual)
-}
-
-fn unexpected_value_error_string(expected: &str, actual: Option<String>) -> Error {
-    let actual = actual.unwrap_or_else(|| "nothing".to_owned());
-    Error::with_msg(format!("Expected {}, found `{}`", expected, actual))
 }
 
 #[cfg(test)]
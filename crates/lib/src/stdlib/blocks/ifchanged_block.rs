@@ -6,6 +6,7 @@ use liquid_core::Renderable;
 use liquid_core::Result;
 use liquid_core::Runtime;
 use liquid_core::Template;
+use liquid_core::Visitor;
 use liquid_core::{BlockReflection, ParseBlock, TagBlock, TagTokenIter};
 
 #[derive(Copy, Clone, Debug, Default)]
@@ -53,6 +54,7 @@ impl ParseBlock for IfChangedBlock {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct IfChanged {
     if_changed: Template,
 }
@@ -65,12 +67,11 @@ impl IfChanged {
 
 impl Renderable for IfChanged {
     fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
-        let mut rendered = Vec::new();
-        self.if_changed
-            .render_to(&mut rendered, runtime)
+        let rendered = self
+            .if_changed
+            .render(runtime)
             .trace_with(|| self.trace().into())?;
 
-        let rendered = String::from_utf8(rendered).expect("render only writes UTF-8");
         if runtime
             .registers()
             .get_mut::<ChangedRegister>()
@@ -82,9 +83,28 @@ impl Renderable for IfChanged {
         Ok(())
     }
 
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.enter_tag("ifchanged");
+        self.if_changed.visit(visitor);
+        visitor.leave_tag("ifchanged");
+    }
+
     fn is_blank(&self) -> bool {
         true
     }
+
+    #[cfg(feature = "serde")]
+    fn as_serialize(&self) -> Option<&dyn liquid_core::runtime::SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[typetag::serde(name = "ifchanged")]
+impl liquid_core::runtime::SerializeRenderable for IfChanged {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
 }
 
 /// Remembers the content of the last rendered `ifstate` block.
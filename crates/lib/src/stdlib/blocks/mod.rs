@@ -19,3 +19,4 @@ pub use self::raw_block::RawBlock;
 pub use self::for_block::ForloopObject;
 pub use self::for_block::Range;
 pub use self::for_block::RangeExpression;
+pub use self::for_block::RangeIter;
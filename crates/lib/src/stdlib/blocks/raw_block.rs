@@ -52,6 +52,7 @@ impl ParseBlock for RawBlock {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct RawT {
     content: String,
 }
@@ -66,6 +67,18 @@ impl Renderable for RawT {
         false
     }
 
+    #[cfg(feature = "serde")]
+    fn as_serialize(&self) -> Option<&dyn liquid_core::runtime::SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[typetag::serde(name = "raw")]
+impl liquid_core::runtime::SerializeRenderable for RawT {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
 }
 
 #[cfg(test)]
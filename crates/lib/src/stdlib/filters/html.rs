@@ -1,65 +1,30 @@
+use liquid_core::model::Scalar;
+use liquid_core::runtime::{escape_html, escape_html_once, is_safe};
 use liquid_core::Result;
 use liquid_core::Runtime;
 use liquid_core::{Display_filter, Filter, FilterReflection, ParseFilter};
 use liquid_core::{Value, ValueView};
 use regex::Regex;
 
-/// Returns the number of already escaped characters.
-fn nr_escaped(text: &str) -> usize {
-    for prefix in &["lt;", "gt;", "#39;", "quot;", "amp;"] {
-        if text.starts_with(prefix) {
-            return prefix.len();
-        }
-    }
-    0
-}
-
-// The code is adapted from
-// https://github.com/rust-lang/rust/blob/master/src/librustdoc/html/escape.rs
-// Retrieved 2016-11-19.
-fn escape(input: &dyn ValueView, once_p: bool) -> Result<Value> {
+fn escape(input: &dyn ValueView, runtime: &dyn Runtime, once_p: bool) -> Result<Value> {
     if input.is_nil() {
         return Ok(Value::Nil);
     }
-    let s = input.to_kstr();
-    let mut result = String::new();
-    let mut last = 0;
-    let mut skip = 0;
-    for (i, c) in s.char_indices() {
-        if skip > 0 {
-            skip -= 1;
-            continue;
-        }
-        match c {
-            '<' | '>' | '\'' | '"' | '&' => {
-                result.push_str(&s[last..i]);
-                last = i + 1;
-                let escaped = match c {
-                    '<' => "&lt;",
-                    '>' => "&gt;",
-                    '\'' => "&#39;",
-                    '"' => "&quot;",
-                    '&' => {
-                        if once_p {
-                            skip = nr_escaped(&s[last..]);
-                        }
-                        if skip == 0 {
-                            "&amp;"
-                        } else {
-                            "&"
-                        }
-                    }
-                    _ => unreachable!(),
-                };
-                result.push_str(escaped);
-            }
-            _ => {}
-        }
+    // Only auto-escaping tracks what is safe; otherwise `escape` does what it says.
+    if runtime.auto_escape() && is_safe(input) {
+        return Ok(input.to_value());
     }
-    if last < s.len() {
-        result.push_str(&s[last..]);
+    let s = input.to_kstr();
+    let result = if once_p {
+        escape_html_once(&s)
+    } else {
+        escape_html(&s)
+    };
+    if runtime.auto_escape() {
+        Ok(Value::Scalar(Scalar::new_safe(result)))
+    } else {
+        Ok(Value::scalar(result))
     }
-    Ok(Value::scalar(result))
 }
 
 #[derive(Clone, ParseFilter, FilterReflection)]
@@ -75,8 +40,8 @@ pub struct Escape;
 struct EscapeFilter;
 
 impl Filter for EscapeFilter {
-    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
-        escape(input, false)
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        escape(input, runtime, false)
     }
 }
 
@@ -93,11 +58,56 @@ pub struct EscapeOnce;
 struct EscapeOnceFilter;
 
 impl Filter for EscapeOnceFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        escape(input, runtime, true)
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "safe",
+    description = "Marks a string as safe to output as HTML, so it isn't escaped.",
+    parsed(SafeFilter)
+)]
+pub struct Safe;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "safe"]
+struct SafeFilter;
+
+impl Filter for SafeFilter {
     fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
-        escape(input, true)
+        mark_safe(input)
     }
 }
 
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "raw",
+    description = "Outputs a string as-is, without auto-escaping it.",
+    parsed(RawFilter)
+)]
+pub struct Raw;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "raw"]
+struct RawFilter;
+
+impl Filter for RawFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        mark_safe(input)
+    }
+}
+
+fn mark_safe(input: &dyn ValueView) -> Result<Value> {
+    if input.is_nil() {
+        return Ok(Value::Nil);
+    }
+    Ok(Value::Scalar(Scalar::new_safe(
+        input.to_kstr().into_owned(),
+    )))
+}
+
 #[derive(Clone, ParseFilter, FilterReflection)]
 #[filter(
     name = "strip_html",
@@ -143,8 +153,19 @@ pub struct NewlineToBr;
 struct NewlineToBrFilter;
 
 impl Filter for NewlineToBrFilter {
-    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
         // TODO handle windows line endings
+        if runtime.auto_escape() {
+            // Escape the text around the breaks, so the result can be marked safe.
+            let text = if is_safe(input) {
+                input.to_kstr().into_string()
+            } else {
+                escape_html(&input.to_kstr())
+            };
+            return Ok(Value::Scalar(Scalar::new_safe(
+                text.replace('\n', "<br />\n"),
+            )));
+        }
         let input = input.to_kstr();
         Ok(Value::scalar(input.replace('\n', "<br />\n")))
     }
@@ -190,6 +211,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn unit_escape_safe() {
+        let safe = liquid_core::call_filter!(Safe, "<b>bold</b>").unwrap();
+        assert!(safe.as_scalar().unwrap().is_safe());
+        let runtime = liquid_core::runtime::RuntimeBuilder::new()
+            .set_auto_escape(true)
+            .build();
+        assert_eq!(
+            EscapeFilter.evaluate(&safe, &runtime).unwrap(),
+            liquid_core::value!("<b>bold</b>")
+        );
+        let runtime = liquid_core::runtime::RuntimeBuilder::new().build();
+        assert_eq!(
+            EscapeFilter.evaluate(&safe, &runtime).unwrap(),
+            liquid_core::value!("&lt;b&gt;bold&lt;/b&gt;")
+        );
+        let raw = liquid_core::call_filter!(Raw, "<b>bold</b>").unwrap();
+        assert!(raw.as_scalar().unwrap().is_safe());
+        assert_eq!(
+            liquid_core::call_filter!(Safe, liquid_core::Value::Nil).unwrap(),
+            liquid_core::Value::Nil
+        );
+    }
+
     #[test]
     fn unit_strip_html() {
         assert_eq!(
@@ -18,7 +18,7 @@ pub use self::array::{
     Compact, Concat, First, Join, Last, Map, Reverse, Sort, SortNatural, Uniq, Where,
 };
 pub use self::date::Date;
-pub use self::html::{Escape, EscapeOnce, NewlineToBr, StripHtml};
+pub use self::html::{Escape, EscapeOnce, NewlineToBr, Raw, Safe, StripHtml};
 pub use self::math::{
     Abs, AtLeast, AtMost, Ceil, DividedBy, Floor, Minus, Modulo, Plus, Round, Times,
 };
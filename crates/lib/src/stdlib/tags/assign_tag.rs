@@ -1,11 +1,13 @@
 use std::io::Write;
 
 use liquid_core::error::ResultLiquidExt;
-use liquid_core::parser::FilterChain;
+use liquid_core::runtime::TagExpression;
 use liquid_core::Language;
 use liquid_core::Renderable;
 use liquid_core::Result;
 use liquid_core::Runtime;
+use liquid_core::ValueView;
+use liquid_core::Visitor;
 use liquid_core::{ParseTag, TagReflection, TagTokenIter};
 
 #[derive(Copy, Clone, Debug, Default)]
@@ -45,10 +47,7 @@ impl ParseTag for AssignTag {
             .expect_str("=")
             .into_result_custom_msg("Assignment operator \"=\" expected.")?;
 
-        let src = arguments
-            .expect_next("FilterChain expected.")?
-            .expect_filter_chain(options)
-            .into_result()?;
+        let src = arguments.expect_tag_expression(options)?;
 
         // no more arguments should be supplied, trying to supply them is an error
         arguments.expect_nothing()?;
@@ -62,9 +61,10 @@ impl ParseTag for AssignTag {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Assign {
     dst: liquid_core::model::KString,
-    src: FilterChain,
+    src: TagExpression,
 }
 
 impl Assign {
@@ -80,13 +80,39 @@ impl Renderable for Assign {
             .evaluate(runtime)
             .trace_with(|| self.trace().into())?
             .into_owned();
+        if let Some(scalar) = value.as_scalar() {
+            runtime
+                .limits()
+                .check_assign_size(scalar.to_kstr().len())
+                .trace_with(|| self.trace().into())?;
+        }
         runtime.set_global(self.dst.clone(), value);
         Ok(())
     }
 
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.enter_tag("assign");
+        self.src.walk(visitor);
+        visitor.visit_assign(self.dst.as_str());
+        visitor.leave_tag("assign");
+    }
+
     fn is_blank(&self) -> bool {
         true
     }
+
+    #[cfg(feature = "serde")]
+    fn as_serialize(&self) -> Option<&dyn liquid_core::runtime::SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[typetag::serde(name = "assign")]
+impl liquid_core::runtime::SerializeRenderable for Assign {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
 }
 
 #[cfg(test)]
@@ -227,4 +253,47 @@ mod test {
             assert_eq!(output, "<p>Freestyle!</p>");
         }
     }
+
+    #[test]
+    fn assign_condition() {
+        let text = concat!(
+            "{% assign is_sale = price < compare_at %}",
+            "{{ is_sale }}",
+            "{% assign is_free = price == 0 or tags contains 'free' %}",
+            "{{ is_free }}"
+        );
+        let options = options();
+        let template = parser::parse(text, &options)
+            .map(runtime::Template::new)
+            .unwrap();
+
+        let runtime = RuntimeBuilder::new().build();
+        runtime.set_global("price".into(), Value::scalar(5));
+        runtime.set_global("compare_at".into(), Value::scalar(8));
+        runtime.set_global("tags".into(), Value::Array(vec![Value::scalar("free")]));
+
+        let output = template.render(&runtime).unwrap();
+        assert_eq!(output, "truetrue");
+    }
+
+    #[test]
+    fn assign_inline_conditional() {
+        let text = concat!(
+            "{% assign label = 'sale' if price < compare_at else 'regular' %}",
+            "{{ label }}",
+            "{% assign missing = 'sale' if price > compare_at %}",
+            "[{{ missing }}]"
+        );
+        let options = options();
+        let template = parser::parse(text, &options)
+            .map(runtime::Template::new)
+            .unwrap();
+
+        let runtime = RuntimeBuilder::new().build();
+        runtime.set_global("price".into(), Value::scalar(5));
+        runtime.set_global("compare_at".into(), Value::scalar(8));
+
+        let output = template.render(&runtime).unwrap();
+        assert_eq!(output, "sale[]");
+    }
 }
@@ -4,11 +4,13 @@ use std::io::Write;
 use liquid_core::error::{ResultLiquidExt, ResultLiquidReplaceExt};
 use liquid_core::parser::TagToken;
 use liquid_core::parser::TryMatchToken;
+use liquid_core::runtime::{handle_strictness, WarningKind};
 use liquid_core::Expression;
 use liquid_core::Language;
 use liquid_core::Renderable;
 use liquid_core::Runtime;
 use liquid_core::ValueView;
+use liquid_core::Visitor;
 use liquid_core::{Error, Result};
 use liquid_core::{ParseTag, TagReflection, TagTokenIter};
 
@@ -103,6 +105,7 @@ fn parse_cycle(mut arguments: TagTokenIter<'_>, _options: &Language) -> Result<C
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Cycle {
     name: String,
     values: Vec<Expression>,
@@ -122,17 +125,48 @@ impl Renderable for Cycle {
         let expr = runtime
             .registers()
             .get_mut::<CycleRegister>()
-            .cycle(&self.name, &self.values)
-            .trace_with(|| self.trace().into())?;
+            .cycle(&self.name, &self.values);
+        let expr = match expr {
+            Ok(expr) => expr,
+            Err(err) => {
+                let err = err.trace(self.trace());
+                let strictness = runtime.render_options().cycle_mismatches();
+                handle_strictness(strictness, WarningKind::CycleMismatch, err, runtime)?;
+                runtime
+                    .registers()
+                    .get_mut::<CycleRegister>()
+                    .restart(&self.name, &self.values)
+            }
+        };
         let value = expr.evaluate(runtime).trace_with(|| self.trace().into())?;
         write!(writer, "{}", value.render()).replace("Failed to render")?;
         Ok(())
     }
 
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.enter_tag("cycle");
+        for value in &self.values {
+            visitor.visit_expression(value);
+        }
+        visitor.leave_tag("cycle");
+    }
+
     fn is_blank(&self) -> bool {
         false
     }
 
+    #[cfg(feature = "serde")]
+    fn as_serialize(&self) -> Option<&dyn liquid_core::runtime::SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[typetag::serde(name = "cycle")]
+impl liquid_core::runtime::SerializeRenderable for Cycle {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
@@ -156,6 +190,12 @@ impl CycleRegister {
         Ok(&values[index])
     }
 
+    /// Start the cycle over, for when it was used with fewer values than before.
+    fn restart<'e>(&mut self, name: &str, values: &'e [Expression]) -> &'e Expression {
+        self.cycles.insert(name.to_owned(), 1 % values.len());
+        &values[0]
+    }
+
     fn cycle_index(&mut self, name: &str, max: usize) -> usize {
         let i = self.cycles.entry(name.to_owned()).or_insert(0);
         let j = *i;
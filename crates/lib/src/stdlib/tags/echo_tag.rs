@@ -0,0 +1,139 @@
+use std::io::Write;
+
+use liquid_core::error::ResultLiquidExt;
+use liquid_core::runtime::TagExpression;
+use liquid_core::Language;
+use liquid_core::Renderable;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::Visitor;
+use liquid_core::{ParseTag, TagReflection, TagTokenIter};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct EchoTag;
+
+impl EchoTag {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl TagReflection for EchoTag {
+    fn tag(&self) -> &'static str {
+        "echo"
+    }
+
+    fn description(&self) -> &'static str {
+        ""
+    }
+}
+
+impl ParseTag for EchoTag {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        let src = arguments.expect_tag_expression(options)?;
+
+        // no more arguments should be supplied, trying to supply them is an error
+        arguments.expect_nothing()?;
+
+        Ok(Box::new(Echo { src }))
+    }
+
+    fn reflection(&self) -> &dyn TagReflection {
+        self
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Echo {
+    src: TagExpression,
+}
+
+impl Echo {
+    fn trace(&self) -> String {
+        format!("{{% echo {} %}}", self.src)
+    }
+}
+
+impl Renderable for Echo {
+    fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+        self.src
+            .render_to(writer, runtime)
+            .trace_with(|| self.trace().into())
+    }
+
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.enter_tag("echo");
+        self.src.walk(visitor);
+        visitor.leave_tag("echo");
+    }
+
+    fn is_blank(&self) -> bool {
+        false
+    }
+
+    #[cfg(feature = "serde")]
+    fn as_serialize(&self) -> Option<&dyn liquid_core::runtime::SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[typetag::serde(name = "echo")]
+impl liquid_core::runtime::SerializeRenderable for Echo {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use liquid_core::parser;
+    use liquid_core::runtime;
+    use liquid_core::runtime::RuntimeBuilder;
+    use liquid_core::Value;
+
+    fn options() -> Language {
+        let mut options = Language::default();
+        options.tags.register("echo".to_string(), EchoTag.into());
+        options
+    }
+
+    #[test]
+    fn echo() {
+        let options = options();
+        let template = parser::parse("{% echo name %}", &options)
+            .map(runtime::Template::new)
+            .unwrap();
+
+        let runtime = RuntimeBuilder::new().build();
+        runtime.set_global("name".into(), Value::scalar("world"));
+
+        let output = template.render(&runtime).unwrap();
+        assert_eq!(output, "world");
+    }
+
+    #[test]
+    fn echo_inline_conditional() {
+        let options = options();
+        let template = parser::parse(
+            "{% echo name if name != blank else 'stranger' %} {% echo name == 'world' %}",
+            &options,
+        )
+        .map(runtime::Template::new)
+        .unwrap();
+
+        let runtime = RuntimeBuilder::new().build();
+        runtime.set_global("name".into(), Value::scalar(""));
+        assert_eq!(template.render(&runtime).unwrap(), "stranger false");
+
+        runtime.set_global("name".into(), Value::scalar("world"));
+        assert_eq!(template.render(&runtime).unwrap(), "world true");
+    }
+}
@@ -2,10 +2,12 @@ use std::io::Write;
 
 use liquid_core::error::ResultLiquidExt;
 use liquid_core::model::KString;
+use liquid_core::runtime::Binding;
 use liquid_core::Expression;
 use liquid_core::Language;
 use liquid_core::Renderable;
 use liquid_core::ValueView;
+use liquid_core::Visitor;
 use liquid_core::{runtime::StackFrame, Runtime};
 use liquid_core::{Error, Result};
 use liquid_core::{ParseTag, TagReflection, TagTokenIter};
@@ -76,6 +78,7 @@ impl ParseTag for IncludeTag {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Include {
     partial: Expression,
     vars: Vec<(KString, Expression)>,
@@ -106,7 +109,15 @@ impl Renderable for Include {
                 }
             }
 
-            let scope = StackFrame::new(runtime, &pass_through);
+            runtime
+                .cancellation()
+                .check()
+                .trace_with(|| format!("{{% include {} %}}", self.partial).into())?;
+            let _depth = runtime
+                .limits()
+                .enter_partial()
+                .trace_with(|| format!("{{% include {} %}}", self.partial).into())?;
+            let scope = StackFrame::new(runtime, &pass_through).with_name(name.clone());
             let partial = scope
                 .partials()
                 .get(&name)
@@ -122,10 +133,33 @@ impl Renderable for Include {
         Ok(())
     }
 
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.enter_tag("include");
+        let mut bindings = Vec::new();
+        for (id, val) in &self.vars {
+            visitor.visit_expression(val);
+            bindings.push((id.as_str(), Binding::Value(val)));
+        }
+        visitor.visit_partial_call(&self.partial, &bindings, false);
+        visitor.leave_tag("include");
+    }
+
     fn is_blank(&self) -> bool {
         false
     }
 
+    #[cfg(feature = "serde")]
+    fn as_serialize(&self) -> Option<&dyn liquid_core::runtime::SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[typetag::serde(name = "include")]
+impl liquid_core::runtime::SerializeRenderable for Include {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
 }
 
 #[cfg(test)]
@@ -1,7 +1,9 @@
 use std::io::Write;
 
 use liquid_core::error::ResultLiquidReplaceExt;
-use liquid_core::model::{Value, ValueView};
+use liquid_core::model::{KString, Value, ValueView};
+use liquid_core::runtime::{warn, WarningKind};
+use liquid_core::Error;
 use liquid_core::Language;
 use liquid_core::Renderable;
 use liquid_core::Result;
@@ -51,17 +53,30 @@ impl ParseTag for IncrementTag {
     }
 }
 
+/// The current value of the counter `id`, starting from 0.
+///
+/// Starting a counter is warned about, as a misspelt name starts a new one.
+fn counter(id: &KString, runtime: &dyn Runtime) -> i64 {
+    match runtime.get_index(id) {
+        Some(val) => val.as_scalar().and_then(|i| i.to_integer()).unwrap_or(0),
+        None => {
+            let err =
+                Error::with_msg("Unknown counter, starting from 0").context("counter", id.clone());
+            warn(WarningKind::UnknownCounter, err, runtime);
+            0
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Increment {
     id: liquid_core::model::KString,
 }
 
 impl Renderable for Increment {
     fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
-        let mut val = runtime
-            .get_index(&self.id)
-            .and_then(|i| i.as_scalar().and_then(|i| i.to_integer()))
-            .unwrap_or(0);
+        let mut val = counter(&self.id, runtime);
 
         write!(writer, "{}", val).replace("Failed to render")?;
         val += 1;
@@ -72,6 +87,19 @@ impl Renderable for Increment {
     fn is_blank(&self) -> bool {
         false
     }
+
+    #[cfg(feature = "serde")]
+    fn as_serialize(&self) -> Option<&dyn liquid_core::runtime::SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[typetag::serde(name = "increment")]
+impl liquid_core::runtime::SerializeRenderable for Increment {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
 }
 
 #[derive(Copy, Clone, Debug, Default)]
@@ -118,16 +146,14 @@ impl ParseTag for DecrementTag {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Decrement {
     id: liquid_core::model::KString,
 }
 
 impl Renderable for Decrement {
     fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
-        let mut val = runtime
-            .get_index(&self.id)
-            .and_then(|i| i.as_scalar().and_then(|i| i.to_integer()))
-            .unwrap_or(0);
+        let mut val = counter(&self.id, runtime);
 
         val -= 1;
         write!(writer, "{}", val).replace("Failed to render")?;
@@ -138,6 +164,19 @@ impl Renderable for Decrement {
     fn is_blank(&self) -> bool {
         false
     }
+
+    #[cfg(feature = "serde")]
+    fn as_serialize(&self) -> Option<&dyn liquid_core::runtime::SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[typetag::serde(name = "decrement")]
+impl liquid_core::runtime::SerializeRenderable for Decrement {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
 }
 
 #[cfg(test)]
@@ -43,6 +43,7 @@ impl ParseTag for BreakTag {
 }
 
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Break;
 
 impl Renderable for Break {
@@ -57,6 +58,19 @@ impl Renderable for Break {
     fn is_blank(&self) -> bool {
         false
     }
+
+    #[cfg(feature = "serde")]
+    fn as_serialize(&self) -> Option<&dyn liquid_core::runtime::SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[typetag::serde(name = "break")]
+impl liquid_core::runtime::SerializeRenderable for Break {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
 }
 
 #[derive(Copy, Clone, Debug, Default)]
@@ -95,6 +109,7 @@ impl ParseTag for ContinueTag {
 }
 
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Continue;
 
 impl Renderable for Continue {
@@ -109,6 +124,19 @@ impl Renderable for Continue {
     fn is_blank(&self) -> bool {
         false
     }
+
+    #[cfg(feature = "serde")]
+    fn as_serialize(&self) -> Option<&dyn liquid_core::runtime::SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[typetag::serde(name = "continue")]
+impl liquid_core::runtime::SerializeRenderable for Continue {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
 }
 
 #[cfg(test)]
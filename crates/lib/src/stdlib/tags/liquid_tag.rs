@@ -0,0 +1,170 @@
+use std::io::Write;
+
+use liquid_core::error::ResultLiquidExt;
+use liquid_core::Language;
+use liquid_core::Renderable;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::Template;
+use liquid_core::Visitor;
+use liquid_core::{ParseTag, TagReflection, TagTokenIter};
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LiquidTag;
+
+impl LiquidTag {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl TagReflection for LiquidTag {
+    fn tag(&self) -> &'static str {
+        "liquid"
+    }
+
+    fn description(&self) -> &'static str {
+        ""
+    }
+}
+
+impl ParseTag for LiquidTag {
+    fn parse(
+        &self,
+        arguments: TagTokenIter<'_>,
+        options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        let template = arguments.parse_lines(options).trace("{% liquid %}")?;
+
+        Ok(Box::new(Liquid {
+            template: Template::new(template),
+        }))
+    }
+
+    fn reflection(&self) -> &dyn TagReflection {
+        self
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Liquid {
+    template: Template,
+}
+
+impl Renderable for Liquid {
+    fn render_to(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
+        self.template.render_to(writer, runtime)
+    }
+
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        self.template.visit(visitor);
+    }
+
+    fn is_blank(&self) -> bool {
+        self.template.is_blank()
+    }
+
+    #[cfg(feature = "serde")]
+    fn as_serialize(&self) -> Option<&dyn liquid_core::runtime::SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[typetag::serde(name = "liquid")]
+impl liquid_core::runtime::SerializeRenderable for Liquid {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use liquid_core::parser;
+    use liquid_core::runtime;
+    use liquid_core::runtime::RuntimeBuilder;
+
+    use crate::stdlib;
+
+    fn options() -> Language {
+        let mut options = Language::default();
+        options
+            .tags
+            .register("liquid".to_string(), LiquidTag.into());
+        options
+            .tags
+            .register("assign".to_string(), stdlib::AssignTag.into());
+        options
+            .tags
+            .register("echo".to_string(), stdlib::EchoTag.into());
+        options
+            .blocks
+            .register("if".to_string(), stdlib::IfBlock.into());
+        options
+            .filters
+            .register("append".to_string(), Box::new(stdlib::Append));
+        options
+            .filters
+            .register("divided_by".to_string(), Box::new(stdlib::DividedBy));
+        options
+    }
+
+    #[test]
+    fn liquid_nested_blocks() {
+        let text = "{% liquid
+            assign x = 'a'
+            if x == 'a'
+              echo x | append: 'b'
+            endif
+        %}";
+        let options = options();
+        let template = parser::parse(text, &options)
+            .map(runtime::Template::new)
+            .unwrap();
+
+        let runtime = RuntimeBuilder::new().build();
+
+        let output = template.render(&runtime).unwrap();
+        assert_eq!(output, "ab");
+    }
+
+    #[test]
+    fn liquid_lines_may_contain_delimiters() {
+        let text = "{% liquid
+            echo '%}'
+            echo '{% x %}'
+        %}";
+        let options = options();
+        let template = parser::parse(text, &options)
+            .map(runtime::Template::new)
+            .unwrap();
+
+        let runtime = RuntimeBuilder::new().build();
+
+        let output = template.render(&runtime).unwrap();
+        assert_eq!(output, "%}{% x %}");
+    }
+
+    #[test]
+    fn liquid_errors_point_into_the_template() {
+        let text = "{% liquid\n  assign x = 1\n  echo x | divided_by: 0\n%}";
+        let options = options();
+        let template = parser::parse(text, &options)
+            .map(runtime::Template::new)
+            .unwrap();
+
+        let runtime = RuntimeBuilder::new().build();
+
+        let error = template.render(&runtime).unwrap_err();
+        let location = error.location().unwrap();
+        assert_eq!(location.line(), 3);
+        assert_eq!(location.column(), 3);
+
+        let text = "{% liquid\n  assign x = 1\n  frobnicate x\n%}";
+        let error = parser::parse(text, &options).unwrap_err();
+        assert!(error.to_string().contains("--> 3:3"), "{}", error);
+    }
+}
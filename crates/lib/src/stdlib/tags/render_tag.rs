@@ -3,6 +3,7 @@ use std::io::Write;
 use liquid_core::error::ResultLiquidExt;
 use liquid_core::model::KString;
 use liquid_core::parser::TryMatchToken;
+use liquid_core::runtime::Binding;
 use liquid_core::runtime::GlobalFrame;
 use liquid_core::runtime::Interrupt;
 use liquid_core::runtime::InterruptRegister;
@@ -12,6 +13,7 @@ use liquid_core::Language;
 use liquid_core::Renderable;
 use liquid_core::Runtime;
 use liquid_core::ValueView;
+use liquid_core::Visitor;
 use liquid_core::{Error, Result};
 use liquid_core::{ParseTag, TagReflection, TagTokenIter};
 
@@ -144,6 +146,7 @@ impl ParseTag for RenderTag {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Render {
     partial: Expression,
     for_: Option<(RangeExpression, KString)>,
@@ -164,11 +167,19 @@ impl Renderable for Render {
             let range = range
                 .evaluate(runtime)
                 .trace_with(|| format!("{{% render {} %}}", self.partial).into())?;
-            let array = range.evaluate()?;
-
-            if !array.is_empty() {
-                let len = array.len();
-                for (i, v) in array.into_iter().enumerate() {
+            let array = range.iter(None, 0, false)?;
+
+            let len = array.len();
+            if len != 0 {
+                for (i, v) in array.enumerate() {
+                    runtime
+                        .cancellation()
+                        .check()
+                        .trace_with(|| format!("{{% render {} %}}", self.partial).into())?;
+                    runtime
+                        .limits()
+                        .count_iteration()
+                        .trace_with(|| format!("{{% render {} %}}", self.partial).into())?;
                     let forloop = ForloopObject::new(i, len);
 
                     let mut root = std::collections::HashMap::new();
@@ -182,7 +193,13 @@ impl Renderable for Render {
                     root.insert("forloop".into(), liquid_core::ValueCow::Borrowed(&forloop));
                     root.insert(var_name.as_ref(), v);
 
-                    let scope = GlobalFrame::new(SandboxedStackFrame::new(runtime, &root));
+                    let _depth = runtime
+                        .limits()
+                        .enter_partial()
+                        .trace_with(|| format!("{{% render {} %}}", self.partial).into())?;
+                    let scope = GlobalFrame::new(
+                        SandboxedStackFrame::new(runtime, &root).with_name(name.clone()),
+                    );
 
                     let partial = scope
                         .partials()
@@ -217,7 +234,16 @@ impl Renderable for Render {
                 root.insert(id.as_ref(), value);
             }
 
-            let scope = GlobalFrame::new(SandboxedStackFrame::new(runtime, &root));
+            runtime
+                .cancellation()
+                .check()
+                .trace_with(|| format!("{{% render {} %}}", self.partial).into())?;
+            let _depth = runtime
+                .limits()
+                .enter_partial()
+                .trace_with(|| format!("{{% render {} %}}", self.partial).into())?;
+            let scope =
+                GlobalFrame::new(SandboxedStackFrame::new(runtime, &root).with_name(name.clone()));
 
             let partial = scope
                 .partials()
@@ -235,11 +261,38 @@ impl Renderable for Render {
         Ok(())
     }
 
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.enter_tag("render");
+        let mut bindings = Vec::new();
+        if let Some((range, var_name)) = &self.for_ {
+            range.walk(visitor);
+            bindings.push((var_name.as_str(), range.binding()));
+            bindings.push(("forloop", Binding::Other));
+        }
+        for (id, val) in &self.vars {
+            visitor.visit_expression(val);
+            bindings.push((id.as_str(), Binding::Value(val)));
+        }
+        visitor.visit_partial_call(&self.partial, &bindings, true);
+        visitor.leave_tag("render");
+    }
 
     fn is_blank(&self) -> bool {
         false
     }
 
+    #[cfg(feature = "serde")]
+    fn as_serialize(&self) -> Option<&dyn liquid_core::runtime::SerializeRenderable> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[typetag::serde(name = "render")]
+impl liquid_core::runtime::SerializeRenderable for Render {
+    fn into_renderable(self: Box<Self>) -> Box<dyn Renderable> {
+        self
+    }
 }
 
 #[cfg(test)]
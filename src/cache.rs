@@ -0,0 +1,146 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync;
+
+use liquid_core::parser::ParseMode;
+
+use super::Template;
+
+/// How well a `Parser`'s template cache is doing, see `ParserBuilder::cache`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CacheStats {
+    /// Parses answered from the cache.
+    pub hits: u64,
+    /// Parses that had to run the parser.
+    pub misses: u64,
+    /// Templates currently cached.
+    pub len: usize,
+    /// Most templates that will be cached at once.
+    pub capacity: usize,
+}
+
+/// A bounded cache of parsed templates, evicting the least recently used.
+#[derive(Debug)]
+pub(crate) struct TemplateCache {
+    state: sync::Mutex<CacheState>,
+}
+
+#[derive(Debug)]
+struct CacheState {
+    entries: HashMap<u64, CacheEntry>,
+    capacity: usize,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    mode: ParseMode,
+    source: String,
+    template: sync::Arc<Template>,
+    last_used: u64,
+}
+
+impl TemplateCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            state: sync::Mutex::new(CacheState {
+                entries: HashMap::with_capacity(capacity),
+                capacity,
+                clock: 0,
+                hits: 0,
+                misses: 0,
+            }),
+        }
+    }
+
+    /// Look up `source`, or parse it with `parse` and cache the result.
+    ///
+    /// The lock isn't held while parsing, so the same source may be parsed more than once when
+    /// first requested from several threads at the same time.
+    pub(crate) fn get_or_parse<F>(
+        &self,
+        source: &str,
+        mode: ParseMode,
+        parse: F,
+    ) -> liquid_core::Result<sync::Arc<Template>>
+    where
+        F: FnOnce() -> liquid_core::Result<Template>,
+    {
+        let key = key(source, mode);
+        {
+            let mut state = self.lock();
+            state.clock += 1;
+            let clock = state.clock;
+            let hit = match state.entries.get_mut(&key) {
+                Some(entry) if entry.mode == mode && entry.source == source => {
+                    entry.last_used = clock;
+                    Some(entry.template.clone())
+                }
+                _ => None,
+            };
+            match hit {
+                Some(template) => {
+                    state.hits += 1;
+                    return Ok(template);
+                }
+                None => state.misses += 1,
+            }
+        }
+
+        let template = sync::Arc::new(parse()?);
+        let mut state = self.lock();
+        if state.capacity == 0 {
+            return Ok(template);
+        }
+        if state.entries.len() >= state.capacity && !state.entries.contains_key(&key) {
+            let oldest = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| *key);
+            if let Some(oldest) = oldest {
+                state.entries.remove(&oldest);
+            }
+        }
+        let last_used = state.clock;
+        state.entries.insert(
+            key,
+            CacheEntry {
+                mode,
+                source: source.to_owned(),
+                template: template.clone(),
+                last_used,
+            },
+        );
+        Ok(template)
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        let state = self.lock();
+        CacheStats {
+            hits: state.hits,
+            misses: state.misses,
+            len: state.entries.len(),
+            capacity: state.capacity,
+        }
+    }
+
+    pub(crate) fn clear(&self) {
+        self.lock().entries.clear();
+    }
+
+    fn lock(&self) -> sync::MutexGuard<'_, CacheState> {
+        self.state.lock().expect("not to be poisoned and reused")
+    }
+}
+
+fn key(source: &str, mode: ParseMode) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    mode.hash(&mut hasher);
+    hasher.finish()
+}
@@ -22,12 +22,31 @@
 
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 
+mod cache;
 mod parser;
 mod template;
 
+pub mod lint;
 pub mod partials;
 pub mod reflection;
 
+/// Lossless syntax trees of template source, for editors and other tooling.
+///
+/// ```rust
+/// use liquid::cst::SyntaxKind;
+///
+/// let parser = liquid::ParserBuilder::with_stdlib().build().unwrap();
+/// let tree = parser.parse_cst("Hi {{- name }}");
+/// let [text, trimmed, output] = tree.root().children() else { panic!() };
+/// assert_eq!(text.kind(), SyntaxKind::Text);
+/// assert_eq!(tree.node_text(trimmed), " ");
+/// assert_eq!(output.kind(), SyntaxKind::Output);
+/// assert_eq!(tree.node_text(output), "{{- name }}");
+/// ```
+pub mod cst {
+    pub use liquid_core::parser::{SyntaxKind, SyntaxNode, SyntaxTree};
+}
+
 /// Liquid data model.
 pub mod model {
     pub use liquid_core::array;
@@ -37,10 +56,17 @@ pub mod model {
     pub use liquid_core::value;
 }
 
+pub use crate::cache::CacheStats;
 pub use crate::parser::*;
 pub use crate::template::*;
+pub use liquid_core::error::ErrorKind;
 pub use liquid_core::model::{_ObjectView as ObjectView, _ValueView as ValueView};
 pub use liquid_core::object;
+pub use liquid_core::runtime::{Cancellation, ChunkedWriter, Limits};
+pub use liquid_core::runtime::{PathSegment, VariablePath, VariableUsage};
+pub use liquid_core::runtime::{Profile, ProfileNode};
+pub use liquid_core::runtime::{RenderOptions, Strictness};
+pub use liquid_core::runtime::{Warning, WarningKind};
 pub use liquid_core::to_object;
 pub use liquid_core::Error;
 pub use liquid_core::Object;
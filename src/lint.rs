@@ -0,0 +1,323 @@
+//! Check templates for common mistakes, without rendering them.
+//!
+//! ```rust
+//! let template = liquid::ParserBuilder::with_stdlib()
+//!     .build().unwrap()
+//!     .parse("{% assign unused = 1 %}{% unless ready %}wait{% else %}go{% endunless %}").unwrap();
+//!
+//! let findings = template.lint();
+//! let rules: Vec<_> = findings.iter().map(|f| f.rule()).collect();
+//! assert_eq!(rules, [liquid::lint::Rule::UnusedAssign, liquid::lint::Rule::UnlessElse]);
+//! assert_eq!(findings[1].span().unwrap().column(), 24);
+//! ```
+//!
+//! `Finding` serializes with serde, for tools wanting the findings as JSON.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use liquid_core::model::{State, Value};
+use liquid_core::runtime;
+use liquid_core::runtime::{Binding, Expression, PartialStore, Renderable, Variable, Visitor};
+use liquid_core::ValueView;
+
+pub use liquid_core::parser::SourceSpan;
+
+/// Check `template` for common mistakes.
+///
+/// `partials` are used to report partials that don't exist, and to follow `include`s for the
+/// variables they read.  Findings are only reported within `template` itself.
+pub fn lint(template: &dyn Renderable, partials: Option<&dyn PartialStore>) -> Vec<Finding> {
+    let globals = runtime::variable_usage(template, partials)
+        .roots()
+        .into_iter()
+        .map(|root| root.to_owned())
+        .collect();
+    let mut linter = Linter {
+        partials,
+        globals,
+        spans: Vec::new(),
+        tags: Vec::new(),
+        loop_vars: Vec::new(),
+        assigned: Vec::new(),
+        reads: HashSet::new(),
+        followed: Vec::new(),
+        findings: Vec::new(),
+    };
+    template.visit(&mut linter);
+    linter.finish()
+}
+
+/// A kind of mistake reported by `lint`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum Rule {
+    /// A variable is set by `assign` or `capture` but never read.
+    UnusedAssign,
+    /// A `for` or `tablerow` variable hides a global, an assigned variable or an outer loop
+    /// variable of the same name.
+    ShadowedVariable,
+    /// `include` or `render` names a partial that doesn't exist.
+    UnknownPartial,
+    /// `empty` or `blank` is used other than as a side of `==` or `!=`, where it is never true.
+    MisusedEmpty,
+    /// `unless` has an `else` branch, which reads better as `if`.
+    UnlessElse,
+    /// `include` is used where `render` would do, since the partial assigns nothing.
+    DeprecatedInclude,
+}
+
+impl Rule {
+    /// The rule's name, as serialized.
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::UnusedAssign => "unused-assign",
+            Rule::ShadowedVariable => "shadowed-variable",
+            Rule::UnknownPartial => "unknown-partial",
+            Rule::MisusedEmpty => "misused-empty",
+            Rule::UnlessElse => "unless-else",
+            Rule::DeprecatedInclude => "deprecated-include",
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A mistake reported by `lint`.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Finding {
+    rule: Rule,
+    message: String,
+    span: Option<SourceSpan>,
+}
+
+impl Finding {
+    /// The kind of mistake.
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    /// A description of the mistake.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The tag, block or output the mistake is in, if known.
+    pub fn span(&self) -> Option<&SourceSpan> {
+        self.span.as_ref()
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(span) = &self.span {
+            write!(f, "{}:{}: ", span.line(), span.column())?;
+        }
+        write!(f, "{} [{}]", self.message, self.rule)
+    }
+}
+
+struct Linter<'p> {
+    partials: Option<&'p dyn PartialStore>,
+    /// Names the template reads as globals.
+    globals: HashSet<String>,
+    spans: Vec<SourceSpan>,
+    tags: Vec<String>,
+    loop_vars: Vec<Vec<String>>,
+    /// Names the template assigns, where they were first assigned.
+    assigned: Vec<(String, Option<SourceSpan>)>,
+    /// Names read other than through a loop variable, including by `include`d partials.
+    reads: HashSet<String>,
+    /// The `include`d partials being walked for the names they read.
+    followed: Vec<String>,
+    findings: Vec<Finding>,
+}
+
+impl<'p> Linter<'p> {
+    fn report(&mut self, rule: Rule, message: String) {
+        if !self.followed.is_empty() {
+            return;
+        }
+        self.findings.push(Finding {
+            rule,
+            message,
+            span: self.spans.last().copied(),
+        });
+    }
+
+    fn is_loop_var(&self, name: &str) -> bool {
+        self.loop_vars.iter().flatten().any(|v| v == name)
+    }
+
+    fn finish(mut self) -> Vec<Finding> {
+        for (name, span) in std::mem::take(&mut self.assigned) {
+            if !self.reads.contains(&name) {
+                self.findings.push(Finding {
+                    rule: Rule::UnusedAssign,
+                    message: format!("`{}` is assigned but never read", name),
+                    span,
+                });
+            }
+        }
+        self.findings
+            .sort_by_key(|finding| finding.span.map(|span| span.start()));
+        self.findings
+    }
+}
+
+fn empty_literal(expression: &Expression) -> Option<&'static str> {
+    match expression {
+        Expression::Literal(Value::State(State::Empty)) => Some("empty"),
+        Expression::Literal(Value::State(State::Blank)) => Some("blank"),
+        _ => None,
+    }
+}
+
+impl<'p> Visitor for Linter<'p> {
+    fn enter_tag(&mut self, name: &str) {
+        if name == "else" && self.tags.last().map(String::as_str) == Some("unless") {
+            self.report(
+                Rule::UnlessElse,
+                "`unless` with an `else` branch reads better as `if`".to_owned(),
+            );
+        }
+        self.tags.push(name.to_owned());
+    }
+
+    fn leave_tag(&mut self, _name: &str) {
+        self.tags.pop();
+    }
+
+    fn enter_span(&mut self, span: &SourceSpan) {
+        self.spans.push(*span);
+    }
+
+    fn leave_span(&mut self) {
+        self.spans.pop();
+    }
+
+    fn enter_scope(&mut self, locals: &[&str]) {
+        self.loop_vars
+            .push(locals.iter().map(|l| (*l).to_owned()).collect());
+    }
+
+    fn leave_scope(&mut self) {
+        self.loop_vars.pop();
+    }
+
+    fn visit_binding(&mut self, name: &str, _value: Binding<'_>) {
+        let outer = &self.loop_vars[..self.loop_vars.len().saturating_sub(1)];
+        let shadows = self.globals.contains(name)
+            || self.assigned.iter().any(|(n, _)| n == name)
+            || outer.iter().flatten().any(|v| v == name);
+        if shadows {
+            self.report(
+                Rule::ShadowedVariable,
+                format!(
+                    "Loop variable `{}` shadows a variable of the same name",
+                    name
+                ),
+            );
+        }
+    }
+
+    fn visit_compared(&mut self, operator: &str, operand: &Expression) {
+        let is_equality = operator == "==" || operator == "!=";
+        if is_equality && empty_literal(operand).is_some() {
+            return;
+        }
+        self.visit_expression(operand);
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        if let Some(literal) = empty_literal(expression) {
+            self.report(
+                Rule::MisusedEmpty,
+                format!("`{}` is only meaningful as a side of `==` or `!=`", literal),
+            );
+        }
+        expression.walk(self);
+    }
+
+    fn visit_variable(&mut self, variable: &Variable) {
+        let root = variable.name().to_kstr();
+        if !self.is_loop_var(root.as_str()) {
+            self.reads.insert(root.into_string());
+        }
+        variable.walk(self);
+    }
+
+    fn visit_assign(&mut self, name: &str) {
+        if self.followed.is_empty() && !self.assigned.iter().any(|(n, _)| n == name) {
+            self.assigned
+                .push((name.to_owned(), self.spans.last().copied()));
+        }
+    }
+
+    fn visit_partial_call(
+        &mut self,
+        name: &Expression,
+        _bindings: &[(&str, Binding<'_>)],
+        isolated: bool,
+    ) {
+        self.visit_expression(name);
+
+        let (Expression::Literal(name), Some(partials)) = (name, self.partials) else {
+            return;
+        };
+        let name = name.to_kstr().into_string();
+        let Some(partial) = partials.try_get(&name) else {
+            if !partials.contains(&name) {
+                self.report(Rule::UnknownPartial, format!("Unknown partial `{}`", name));
+            }
+            return;
+        };
+
+        if self.tags.last().map(String::as_str) == Some("include") {
+            let mut finder = AssignFinder(false);
+            partial.visit(&mut finder);
+            if !finder.0 {
+                self.report(
+                    Rule::DeprecatedInclude,
+                    format!(
+                        "`{}` assigns nothing, so `render` can be used instead of `include`",
+                        name
+                    ),
+                );
+            }
+        }
+
+        // Partials sharing the caller's variables may read what it assigns.
+        if !isolated && !self.followed.contains(&name) {
+            self.followed.push(name);
+            partial.visit(self);
+            self.followed.pop();
+        }
+    }
+}
+
+/// Whether a partial assigns anything its caller could see.
+struct AssignFinder(bool);
+
+impl Visitor for AssignFinder {
+    fn visit_assign(&mut self, _name: &str) {
+        self.0 = true;
+    }
+
+    fn visit_partial_call(
+        &mut self,
+        _name: &Expression,
+        _bindings: &[(&str, Binding<'_>)],
+        isolated: bool,
+    ) {
+        // Without following it, assume a nested `include` may assign.
+        if !isolated {
+            self.0 = true;
+        }
+    }
+}
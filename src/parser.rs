@@ -9,8 +9,12 @@ use liquid_core::parser::ParseMode;
 use liquid_core::runtime;
 
 use super::Template;
+use crate::cache::{CacheStats, TemplateCache};
 use crate::reflection;
+use crate::template::{LoadedPartials, LoadedTemplate};
 use liquid_core::partials;
+#[cfg(feature = "extra")]
+use liquid_lib::extra;
 #[cfg(feature = "stdlib")]
 use liquid_lib::stdlib;
 
@@ -25,6 +29,11 @@ where
     tags: parser::PluginRegistry<Box<dyn parser::ParseTag>>,
     filters: parser::PluginRegistry<Box<dyn parser::ParseFilter>>,
     partials: Option<P>,
+    limits: runtime::Limits,
+    auto_escape: bool,
+    string_escapes: bool,
+    defer_unknown_filters: bool,
+    cache: Option<usize>,
 }
 
 impl ParserBuilder<Partials> {
@@ -50,9 +59,11 @@ where
             .tag(stdlib::BreakTag)
             .tag(stdlib::ContinueTag)
             .tag(stdlib::CycleTag)
+            .tag(stdlib::EchoTag)
             .tag(stdlib::IncludeTag)
             .tag(stdlib::IncrementTag)
             .tag(stdlib::DecrementTag)
+            .tag(stdlib::LiquidTag)
             .tag(stdlib::RenderTag)
             .block(stdlib::RawBlock)
             .block(stdlib::IfBlock)
@@ -88,6 +99,7 @@ where
             .filter(stdlib::NewlineToBr)
             .filter(stdlib::Plus)
             .filter(stdlib::Prepend)
+            .filter(stdlib::Raw)
             .filter(stdlib::Remove)
             .filter(stdlib::RemoveFirst)
             .filter(stdlib::Replace)
@@ -95,6 +107,7 @@ where
             .filter(stdlib::Reverse)
             .filter(stdlib::Round)
             .filter(stdlib::Rstrip)
+            .filter(stdlib::Safe)
             .filter(stdlib::Size)
             .filter(stdlib::Slice)
             .filter(stdlib::Sort)
@@ -113,12 +126,43 @@ where
             .filter(stdlib::Where)
     }
 
+    #[cfg(feature = "extra")]
+    /// Adds template inheritance: `{% extends %}`, `{% layout %}` and `{% block %}`
+    pub fn inheritance(self) -> Self {
+        self.block(extra::ExtendsBlock)
+            .block(extra::LayoutBlock)
+            .block(extra::BlockBlock)
+    }
+
     /// Sets the parse mode to lax.
     pub fn in_lax_mode(mut self) -> Self {
         self.mode = ParseMode::Lax;
         self
     }
 
+    /// HTML-escapes the output of every `{{ }}`, unless marked as `safe`.
+    pub fn auto_escape(mut self) -> Self {
+        self.auto_escape = true;
+        self
+    }
+
+    /// Decodes backslash escapes in string literals, like `\n`, `\"` or `\u{e9}`.
+    ///
+    /// Without this, a backslash is kept as written.
+    pub fn string_escapes(mut self) -> Self {
+        self.string_escapes = true;
+        self
+    }
+
+    /// Leaves unknown filters for `RenderOptions::set_undefined_filters` to decide on when
+    /// rendering, rather than failing the parse.
+    ///
+    /// Lax mode always does this.
+    pub fn defer_unknown_filters(mut self) -> Self {
+        self.defer_unknown_filters = true;
+        self
+    }
+
     /// Inserts a new custom block into the parser
     pub fn block<B: Into<Box<dyn parser::ParseBlock>>>(mut self, block: B) -> Self {
         let block = block.into();
@@ -142,6 +186,19 @@ where
         self
     }
 
+    /// Bound the work rendering a template may do.
+    pub fn limits(mut self, limits: runtime::Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Keep up to `capacity` parsed templates for `Parser::parse_cached`, evicting the least
+    /// recently used.
+    pub fn cache(mut self, capacity: usize) -> Self {
+        self.cache = Some(capacity);
+        self
+    }
+
     /// Set which partial-templates will be available.
     pub fn partials<N: partials::PartialCompiler>(self, partials: N) -> ParserBuilder<N> {
         let Self {
@@ -150,6 +207,11 @@ where
             tags,
             filters,
             partials: _partials,
+            limits,
+            auto_escape,
+            string_escapes,
+            defer_unknown_filters,
+            cache,
         } = self;
         ParserBuilder {
             mode,
@@ -157,6 +219,11 @@ where
             tags,
             filters,
             partials: Some(partials),
+            limits,
+            auto_escape,
+            string_escapes,
+            defer_unknown_filters,
+            cache,
         }
     }
 
@@ -168,10 +235,17 @@ where
             tags,
             filters,
             partials,
+            limits,
+            auto_escape,
+            string_escapes,
+            defer_unknown_filters,
+            cache,
         } = self;
 
         let mut options = parser::Language::empty();
         options.mode = mode;
+        options.string_escapes = string_escapes;
+        options.defer_unknown_filters = defer_unknown_filters;
         options.blocks = blocks;
         options.tags = tags;
         options.filters = filters;
@@ -181,7 +255,14 @@ where
             .map(|r| r.map(Some))
             .unwrap_or(Ok(None))?
             .map(|p| p.into());
-        let p = Parser { options, partials };
+        let cache = cache.map(|capacity| sync::Arc::new(TemplateCache::new(capacity)));
+        let p = Parser {
+            options,
+            partials,
+            limits,
+            auto_escape,
+            cache,
+        };
         Ok(p)
     }
 }
@@ -197,6 +278,11 @@ where
             tags: Default::default(),
             filters: Default::default(),
             partials: Default::default(),
+            limits: Default::default(),
+            auto_escape: false,
+            string_escapes: false,
+            defer_unknown_filters: false,
+            cache: None,
         }
     }
 }
@@ -231,6 +317,9 @@ where
 pub struct Parser {
     options: sync::Arc<parser::Language>,
     partials: Option<sync::Arc<dyn runtime::PartialStore + Send + Sync>>,
+    limits: runtime::Limits,
+    auto_escape: bool,
+    cache: Option<sync::Arc<TemplateCache>>,
 }
 
 impl Parser {
@@ -258,9 +347,116 @@ impl Parser {
         Ok(Template {
             template,
             partials: self.partials.clone(),
+            limits: self.limits,
+            auto_escape: self.auto_escape,
         })
     }
 
+    /// Re-emits the template source `text` in a consistent style.
+    ///
+    /// Whitespace within `{{ }}` and `{% %}` is normalized and nested tags are indented where
+    /// whitespace control trims the indentation, see `liquid_core::parser::format`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let parser = liquid::ParserBuilder::with_stdlib().build().unwrap();
+    /// let formatted = parser
+    ///     .format("{%if ready%}\n{{-name|upcase}}\n{%-endif%}")
+    ///     .unwrap();
+    /// assert_eq!(formatted, "{% if ready %}\n  {{- name | upcase }}\n{%- endif %}");
+    /// ```
+    pub fn format(&self, text: &str) -> Result<String> {
+        parser::format(text, &self.options)
+    }
+
+    /// Lexes `text` into a lossless syntax tree, as this parser would read it.
+    ///
+    /// See `liquid::cst`.
+    pub fn parse_cst<'s>(&self, text: &'s str) -> crate::cst::SyntaxTree<'s> {
+        parser::parse_cst(text, &self.options)
+    }
+
+    /// Loads a `Template` saved with its `Serialize` impl, without re-parsing it.
+    ///
+    /// Filters are looked up again in this parser, so it must have every filter the template
+    /// uses.  The template's partials are used if it was saved with any, otherwise the parser's.
+    ///
+    /// The format must be self-describing, like JSON, CBOR or MessagePack.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let parser = liquid::ParserBuilder::with_stdlib().build().unwrap();
+    /// let template = parser.parse("{{ num | minus: 2 }}").unwrap();
+    /// let saved = serde_json::to_string(&template).unwrap();
+    ///
+    /// let mut deserializer = serde_json::Deserializer::from_str(&saved);
+    /// let template = parser.load_template(&mut deserializer).unwrap();
+    /// let globals = liquid::object!({ "num": 4 });
+    /// assert_eq!(template.render(&globals).unwrap(), "2");
+    /// ```
+    pub fn load_template<'de, D>(&self, deserializer: D) -> std::result::Result<Template, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::Deserialize as _;
+
+        let loaded = runtime::with_language(self.options.clone(), || {
+            LoadedTemplate::deserialize(deserializer)
+        })?;
+        let partials = if loaded.partials.is_empty() {
+            self.partials.clone()
+        } else {
+            let partials = LoadedPartials::new(loaded.partials);
+            Some(sync::Arc::new(partials) as sync::Arc<dyn runtime::PartialStore + Send + Sync>)
+        };
+        Ok(Template {
+            template: loaded.template,
+            partials,
+            limits: self.limits,
+            auto_escape: self.auto_escape,
+        })
+    }
+
+    /// Parses a liquid template, reusing the `Template` from an earlier call with the same text.
+    ///
+    /// Templates are only kept when the parser was built with `ParserBuilder::cache`; clones of
+    /// the parser share its cache.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let parser = liquid::ParserBuilder::with_stdlib()
+    ///     .cache(100)
+    ///     .build().unwrap();
+    ///
+    /// let first = parser.parse_cached("Liquid!").unwrap();
+    /// let second = parser.parse_cached("Liquid!").unwrap();
+    /// assert!(std::sync::Arc::ptr_eq(&first, &second));
+    ///
+    /// let stats = parser.cache_stats().unwrap();
+    /// assert_eq!((stats.hits, stats.misses), (1, 1));
+    /// ```
+    pub fn parse_cached(&self, text: &str) -> Result<sync::Arc<Template>> {
+        match self.cache {
+            Some(ref cache) => cache.get_or_parse(text, self.options.mode, || self.parse(text)),
+            None => self.parse(text).map(sync::Arc::new),
+        }
+    }
+
+    /// How the cache used by `parse_cached` is doing, if the parser has one.
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(|cache| cache.stats())
+    }
+
+    /// Drop all templates cached by `parse_cached`.
+    pub fn clear_cache(&self) {
+        if let Some(ref cache) = self.cache {
+            cache.clear();
+        }
+    }
+
     /// Parse a liquid template from a file, returning a `Result<Template, Error>`.
     /// # Examples
     ///
@@ -40,5 +40,16 @@
 //!
 //! assert_eq!(rendered, "Liquid! Number: 42");
 //! ```
+//!
+//! To read partials from disk instead, use a `FileSystemSource`:
+//!
+//! ```rust,no_run
+//! use liquid::ParserBuilder;
+//! use liquid::partials::{FileSystemSource, LazyCompiler};
+//!
+//! // `{% render 'card' %}` reads `theme/snippets/card.liquid`.
+//! let partials = LazyCompiler::new(FileSystemSource::new("theme/snippets"));
+//! let parser = ParserBuilder::with_stdlib().partials(partials).build().unwrap();
+//! ```
 
 pub use liquid_core::partials::*;
@@ -1,32 +1,75 @@
+use std::collections::HashMap;
+use std::fmt;
 use std::io::Write;
 use std::sync;
+use std::time::Instant;
+
+use serde::ser::{Error as _, SerializeStruct as _};
 
 use liquid_core::error::Result;
 use liquid_core::runtime;
 use liquid_core::runtime::PartialStore;
 use liquid_core::runtime::Renderable;
 use liquid_core::runtime::RenderingMode;
+use liquid_core::runtime::Runtime;
+use liquid_core::runtime::SerializeRenderable;
+use liquid_core::runtime::VariableUsage;
+use liquid_core::runtime::Visitor;
+use liquid_core::runtime::{AsyncFilters, Cancellation, Profile, Profiler};
+use liquid_core::runtime::{RenderOptions, Strictness, Warning, Warnings};
+use liquid_core::Error;
+use liquid_core::Expression;
+use liquid_core::ValueView;
 
 #[derive(Debug)]
 pub struct Template {
     pub template: runtime::Template,
     pub partials: Option<sync::Arc<dyn PartialStore + Send + Sync>>,
+    pub limits: runtime::Limits,
+    pub auto_escape: bool,
 }
 
 impl Template {
     /// Renders an instance of the Template, using the given globals.
     pub fn render(&self, globals: &dyn crate::ObjectView) -> Result<String> {
-        self.render_with_mode(globals, RenderingMode::Strict)
+        self.render_with_state(globals, RenderState::default())
     }
 
     /// Renders an instance of the Template, using the given globals.
     pub fn render_to(&self, writer: &mut dyn Write, globals: &dyn crate::ObjectView) -> Result<()> {
-        self.render_to_with_mode(writer, globals, RenderingMode::Strict)
+        self.render_to_with_state(writer, globals, RenderState::default())
+    }
+
+    /// Renders an instance of the Template into a `fmt::Write`, like a `String` or a
+    /// `fmt::Formatter`, using the given globals.
+    pub fn render_to_fmt(
+        &self,
+        writer: &mut dyn fmt::Write,
+        globals: &dyn crate::ObjectView,
+    ) -> Result<()> {
+        let mut writer = runtime::FmtWriter::new(writer);
+        self.render_to_with_state(&mut writer, globals, RenderState::default())
+    }
+
+    /// Renders an instance of the Template, using the given globals, flushing `writer` after each
+    /// top-level element.
+    ///
+    /// Pair with `ChunkedWriter`, or a writer that sends on what is flushed to it, to stream a
+    /// large page while it is still being rendered.
+    pub fn render_chunked(
+        &self,
+        writer: &mut dyn Write,
+        globals: &dyn crate::ObjectView,
+    ) -> Result<()> {
+        let runtime = self.runtime(globals, RenderState::default());
+        let mut writer = runtime::LimitedWriter::new(writer, runtime.limits());
+        let result = self.template.render_chunked_to(&mut writer, &runtime);
+        writer.check(result)
     }
 
     /// Renders an instance of the Template, using the given globals in lax mode.
     pub fn render_lax(&self, globals: &dyn crate::ObjectView) -> Result<String> {
-        self.render_with_mode(globals, RenderingMode::Lax)
+        self.render_with_state(globals, RenderState::with_mode(RenderingMode::Lax))
     }
 
     /// Renders an instance of the Template, using the given globals in lax mode.
@@ -35,38 +78,373 @@ impl Template {
         writer: &mut dyn Write,
         globals: &dyn crate::ObjectView,
     ) -> Result<()> {
-        self.render_to_with_mode(writer, globals, RenderingMode::Lax)
+        self.render_to_with_state(writer, globals, RenderState::with_mode(RenderingMode::Lax))
+    }
+
+    /// Renders an instance of the Template, using the given globals, choosing how strict to be
+    /// about undefined names and bad values.
+    ///
+    /// Along with the output, returns the problems that `options` chose to `Strictness::Warn`
+    /// about.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use liquid::{RenderOptions, Strictness, WarningKind};
+    ///
+    /// let template = liquid::ParserBuilder::with_stdlib()
+    ///     .build().unwrap()
+    ///     .parse("Hello {{ name }}{{ title }}").unwrap();
+    ///
+    /// let options = RenderOptions::new().set_undefined_variables(Strictness::Warn);
+    /// let globals = liquid::object!({ "name": "Liquid" });
+    /// let (output, warnings) = template.render_with_options(&globals, &options).unwrap();
+    /// assert_eq!(output, "Hello Liquid");
+    /// assert_eq!(warnings.len(), 1);
+    /// assert_eq!(warnings[0].kind(), WarningKind::UndefinedVariable);
+    /// ```
+    pub fn render_with_options(
+        &self,
+        globals: &dyn crate::ObjectView,
+        options: &RenderOptions,
+    ) -> Result<(String, Vec<Warning>)> {
+        let warnings = Warnings::new();
+        let state = RenderState {
+            options: *options,
+            warnings: Some(&warnings),
+            ..Default::default()
+        };
+        let output = self.render_with_state(globals, state)?;
+        Ok((output, warnings.into_vec()))
     }
 
-    /// Renders an instance of the Template, using the given globals with the provided rendering mode.
-    fn render_with_mode(
+    /// Renders an instance of the Template, using the given globals, carrying on past every
+    /// problem covered by `RenderOptions` and returning them alongside the output.
+    ///
+    /// This is `render_with_options` with every option set to `Strictness::Warn`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use liquid::WarningKind;
+    ///
+    /// let template = liquid::ParserBuilder::with_stdlib()
+    ///     .build().unwrap()
+    ///     .parse("Hello {{ name }}\n{{ 'x' | abs }}").unwrap();
+    ///
+    /// let (output, warnings) = template.render_with_warnings(&liquid::Object::new()).unwrap();
+    /// assert_eq!(output, "Hello \n");
+    /// assert_eq!(warnings[0].kind(), WarningKind::UndefinedVariable);
+    /// assert_eq!(warnings[1].kind(), WarningKind::FilterTypeMismatch);
+    /// assert_eq!(warnings[1].location().unwrap().line(), 2);
+    /// ```
+    pub fn render_with_warnings(
         &self,
         globals: &dyn crate::ObjectView,
-        mode: RenderingMode,
+    ) -> Result<(String, Vec<Warning>)> {
+        let options = RenderOptions::new().set_all(Strictness::Warn);
+        self.render_with_options(globals, &options)
+    }
+
+    /// Renders an instance of the Template, using the given globals, and records how long each
+    /// tag, block and expression took to render.
+    pub fn render_profiled(&self, globals: &dyn crate::ObjectView) -> Result<(String, Profile)> {
+        let profiler = Profiler::new();
+        let start = Instant::now();
+        let state = RenderState {
+            profiler: Some(&profiler),
+            ..Default::default()
+        };
+        let output = self.render_with_state(globals, state)?;
+        let profile = profiler.to_profile().with_total_time(start.elapsed());
+        Ok((output, profile))
+    }
+
+    /// Renders an instance of the Template, using the given globals, stopping early with an
+    /// `ErrorKind::Cancelled` error once `cancellation` triggers.
+    pub fn render_cancellable(
+        &self,
+        globals: &dyn crate::ObjectView,
+        cancellation: &Cancellation,
+    ) -> Result<String> {
+        let state = RenderState {
+            cancellation: Some(cancellation),
+            ..Default::default()
+        };
+        self.render_with_state(globals, state)
+    }
+
+    /// Renders an instance of the Template, using the given globals, fetching the values it reads
+    /// through `ObjectView::get_async` and awaiting its `AsyncFilter`s.
+    ///
+    /// See `liquid_core::runtime::prefetch` for which values can be fetched.  When the template
+    /// calls async filters, it is rendered once to start them, and once more with their results,
+    /// which are all awaited at once.  An async filter whose input comes from another needs a
+    /// further pass, so tags with side effects outside of the render should be avoided.
+    pub async fn render_async(&self, globals: &(dyn crate::ObjectView + Sync)) -> Result<String> {
+        let fetched = {
+            let partials = self
+                .partials
+                .as_ref()
+                .map(|p| p.as_ref() as &dyn PartialStore);
+            runtime::prefetch(&self.template, partials, globals)
+        }
+        .await;
+
+        let mut filters = AsyncFilters::new();
+        loop {
+            let output = {
+                let globals = runtime::Prefetched::new(&fetched, globals);
+                let state = RenderState {
+                    async_filters: Some(&filters),
+                    ..Default::default()
+                };
+                self.render_with_state(&globals, state)
+            };
+            if filters.is_complete() {
+                return output;
+            }
+            filters.resolve().await?;
+        }
+    }
+
+    /// Walks the parsed template with `visitor`, without rendering it.
+    pub fn visit(&self, visitor: &mut dyn runtime::Visitor) {
+        self.template.visit(visitor);
+    }
+
+    /// Lists the globals the template, and the partials it renders, read, without rendering it.
+    ///
+    /// See `liquid_core::runtime::variable_usage` for what is and isn't counted.
+    ///
+    /// ```rust
+    /// let template = liquid::ParserBuilder::with_stdlib()
+    ///     .build().unwrap()
+    ///     .parse("{% assign n = products | size %}{% for p in products %}{{ p.title }}{% endfor %}{{ n }}").unwrap();
+    ///
+    /// let usage = template.variable_usage();
+    /// assert_eq!(usage.roots(), ["products"]);
+    /// let paths: Vec<_> = usage.paths().map(|p| p.to_string()).collect();
+    /// assert_eq!(paths, ["products", "products[].title"]);
+    /// ```
+    pub fn variable_usage(&self) -> VariableUsage {
+        let partials = self
+            .partials
+            .as_ref()
+            .map(|p| p.as_ref() as &dyn PartialStore);
+        runtime::variable_usage(&self.template, partials)
+    }
+
+    /// Checks the template for common mistakes, see `lint::lint`.
+    pub fn lint(&self) -> Vec<crate::lint::Finding> {
+        let partials = self
+            .partials
+            .as_ref()
+            .map(|p| p.as_ref() as &dyn PartialStore);
+        crate::lint::lint(&self.template, partials)
+    }
+
+    /// Renders an instance of the Template, using the given globals with the provided state.
+    fn render_with_state(
+        &self,
+        globals: &dyn crate::ObjectView,
+        state: RenderState<'_>,
     ) -> Result<String> {
         const BEST_GUESS: usize = 10_000;
         let mut data = Vec::with_capacity(BEST_GUESS);
-        self.render_to_with_mode(&mut data, globals, mode)?;
+        self.render_to_with_state(&mut data, globals, state)?;
 
         Ok(convert_buffer(data))
     }
 
-    /// Renders an instance of the Template, using the given globals with the provided rendering mode.
-    fn render_to_with_mode(
+    /// Renders an instance of the Template, using the given globals with the provided state.
+    fn render_to_with_state(
         &self,
         writer: &mut dyn Write,
         globals: &dyn crate::ObjectView,
-        mode: RenderingMode,
+        state: RenderState<'_>,
     ) -> Result<()> {
+        let runtime = self.runtime(globals, state);
+        let mut writer = runtime::LimitedWriter::new(writer, runtime.limits());
+        let result = self.template.render_to(&mut writer, &runtime);
+        writer.check(result)
+    }
+
+    fn runtime<'a>(
+        &'a self,
+        globals: &'a dyn crate::ObjectView,
+        state: RenderState<'a>,
+    ) -> impl runtime::Runtime + 'a {
+        let mode = match state.options.undefined_variables() {
+            Strictness::Error => RenderingMode::Strict,
+            Strictness::Empty | Strictness::Warn => RenderingMode::Lax,
+        };
         let runtime = runtime::RuntimeBuilder::new()
             .set_globals(globals)
-            .set_render_mode(mode);
+            .set_render_mode(mode)
+            .set_render_options(state.options)
+            .set_limits(self.limits)
+            .set_auto_escape(self.auto_escape);
         let runtime = match self.partials {
             Some(ref partials) => runtime.set_partials(partials.as_ref()),
             None => runtime,
         };
-        let runtime = runtime.build();
-        self.template.render_to(writer, &runtime)
+        let runtime = match state.profiler {
+            Some(profiler) => runtime.set_profiler(profiler),
+            None => runtime,
+        };
+        let runtime = match state.cancellation {
+            Some(cancellation) => runtime.set_cancellation(cancellation.clone()),
+            None => runtime,
+        };
+        let runtime = match state.async_filters {
+            Some(async_filters) => runtime.set_async_filters(async_filters),
+            None => runtime,
+        };
+        let runtime = match state.warnings {
+            Some(warnings) => runtime.set_warnings(warnings),
+            None => runtime,
+        };
+        runtime.build()
+    }
+}
+
+/// What a render is given, besides its globals.
+#[derive(Default)]
+struct RenderState<'a> {
+    options: RenderOptions,
+    profiler: Option<&'a Profiler>,
+    cancellation: Option<&'a Cancellation>,
+    async_filters: Option<&'a AsyncFilters>,
+    warnings: Option<&'a Warnings>,
+}
+
+impl<'a> RenderState<'a> {
+    fn with_mode(mode: RenderingMode) -> Self {
+        Self {
+            options: RenderOptions::with_mode(&mode),
+            ..Default::default()
+        }
+    }
+}
+
+/// Saves the parsed template, along with the partials it renders, to be loaded back with
+/// `Parser::load_template`.
+///
+/// Every tag and block in them must support serialization, see
+/// `liquid_core::runtime::SerializeRenderable`.
+impl serde::Serialize for Template {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut partials = Vec::new();
+        if let Some(ref store) = self.partials {
+            let mut finder = PartialFinder {
+                store: store.as_ref(),
+                names: Vec::new(),
+                dynamic: false,
+            };
+            self.template.visit(&mut finder);
+            let mut names = if finder.dynamic {
+                // Any partial could be rendered by a name only known at render time.
+                store.names().into_iter().map(str::to_owned).collect()
+            } else {
+                finder.names
+            };
+            names.sort_unstable();
+            for name in names {
+                // A missing partial fails at render time, as it would have without saving.
+                if let Some(partial) = store.try_get(&name) {
+                    partials.push((name, partial));
+                }
+            }
+        }
+        let partials = partials
+            .iter()
+            .map(|(name, partial)| {
+                let partial = partial.as_serialize().ok_or_else(|| {
+                    S::Error::custom(format!("Partial `{}` doesn't support serialization", name))
+                })?;
+                Ok((name.as_str(), partial))
+            })
+            .collect::<Result<Vec<_>, S::Error>>()?;
+
+        let mut template = serializer.serialize_struct("Template", 2)?;
+        template.serialize_field("template", &self.template)?;
+        template.serialize_field("partials", &partials)?;
+        template.end()
+    }
+}
+
+/// Finds the partials a template renders, following them into the partials they render.
+struct PartialFinder<'s> {
+    store: &'s (dyn PartialStore + Send + Sync),
+    names: Vec<String>,
+    /// Whether a partial's name is only known at render time.
+    dynamic: bool,
+}
+
+impl Visitor for PartialFinder<'_> {
+    fn visit_partial(&mut self, name: &Expression) {
+        let Expression::Literal(name) = name else {
+            self.dynamic = true;
+            return;
+        };
+        let name = name.to_kstr().into_string();
+        if self.names.contains(&name) {
+            return;
+        }
+        let partial = self.store.try_get(&name);
+        self.names.push(name);
+        if let Some(partial) = partial {
+            partial.visit(self);
+        }
+    }
+}
+
+/// A template and its partials, as saved by `Template`'s `Serialize`.
+#[derive(serde::Deserialize)]
+pub(crate) struct LoadedTemplate {
+    pub(crate) template: runtime::Template,
+    pub(crate) partials: Vec<(String, Box<dyn SerializeRenderable>)>,
+}
+
+/// Partials loaded along with a template.
+#[derive(Debug)]
+pub(crate) struct LoadedPartials {
+    store: HashMap<String, sync::Arc<dyn Renderable>>,
+}
+
+impl LoadedPartials {
+    pub(crate) fn new(partials: Vec<(String, Box<dyn SerializeRenderable>)>) -> Self {
+        let store = partials
+            .into_iter()
+            .map(|(name, partial)| (name, sync::Arc::from(partial.into_renderable())))
+            .collect();
+        Self { store }
+    }
+}
+
+impl PartialStore for LoadedPartials {
+    fn contains(&self, name: &str) -> bool {
+        self.store.contains_key(name)
+    }
+
+    fn names(&self) -> Vec<&str> {
+        self.store.keys().map(|s| s.as_str()).collect()
+    }
+
+    fn try_get(&self, name: &str) -> Option<sync::Arc<dyn Renderable>> {
+        self.store.get(name).cloned()
+    }
+
+    fn get(&self, name: &str) -> Result<sync::Arc<dyn Renderable>> {
+        self.store.get(name).cloned().ok_or_else(|| {
+            let mut available: Vec<_> = self.names();
+            available.sort_unstable();
+            let available = available.join(", ");
+            Error::with_msg("Unknown partial-template")
+                .context("requested partial", name.to_owned())
+                .context("available partials", available)
+        })
     }
 }
 
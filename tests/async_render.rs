@@ -0,0 +1,338 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::{pin, Pin};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread;
+
+use liquid_core::model::{BoxFuture, DisplayCow, KStringCow, State};
+use liquid_core::parser::{evaluate_async_filter, AsyncFilter};
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::{Display_filter, Filter, FilterReflection, ParseFilter};
+use liquid_core::{ObjectView, Value, ValueCow, ValueView};
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    struct ThreadWaker(thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// Pending on first poll, like a query waiting on the network.
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            context.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+/// A product whose title can only be read asynchronously.
+#[derive(Debug)]
+struct Product {
+    id: i64,
+    /// How many titles, of all products, have started being fetched.
+    started: Arc<AtomicUsize>,
+}
+
+impl ValueView for Product {
+    fn as_debug(&self) -> &dyn fmt::Debug {
+        self
+    }
+
+    fn render(&self) -> DisplayCow<'_> {
+        Value::Nil.render()
+    }
+    fn source(&self) -> DisplayCow<'_> {
+        Value::Nil.source()
+    }
+    fn type_name(&self) -> &'static str {
+        "object"
+    }
+    fn query_state(&self, state: State) -> bool {
+        matches!(state, State::Truthy)
+    }
+
+    fn to_kstr(&self) -> KStringCow<'_> {
+        KStringCow::from_static("")
+    }
+    fn to_value(&self) -> Value {
+        Value::Object(Default::default())
+    }
+
+    fn as_object(&self) -> Option<&dyn ObjectView> {
+        Some(self)
+    }
+}
+
+impl ObjectView for Product {
+    fn as_value(&self) -> &dyn ValueView {
+        self
+    }
+
+    fn size(&self) -> i64 {
+        0
+    }
+
+    fn keys<'k>(&'k self) -> Box<dyn Iterator<Item = KStringCow<'k>> + 'k> {
+        Box::new(std::iter::empty())
+    }
+
+    fn values<'k>(&'k self) -> Box<dyn Iterator<Item = &'k dyn ValueView> + 'k> {
+        Box::new(std::iter::empty())
+    }
+
+    fn iter<'k>(&'k self) -> Box<dyn Iterator<Item = (KStringCow<'k>, &'k dyn ValueView)> + 'k> {
+        Box::new(std::iter::empty())
+    }
+
+    fn contains_key(&self, _index: &str) -> bool {
+        false
+    }
+
+    fn get<'s>(&'s self, _index: &str) -> Option<ValueCow<'s>> {
+        None
+    }
+
+    fn get_async<'s>(&'s self, index: &'s str) -> Option<BoxFuture<'s, Option<Value>>> {
+        if index != "title" {
+            return None;
+        }
+        Some(Box::pin(async move {
+            self.started.fetch_add(1, Ordering::SeqCst);
+            YieldNow(false).await;
+            let started = self.started.load(Ordering::SeqCst);
+            let title = format!("Product {} ({} started)", self.id, started);
+            Some(Value::scalar(title))
+        }))
+    }
+}
+
+/// Globals, some of which can only be read asynchronously.
+#[derive(Debug)]
+struct Catalog {
+    products: Vec<Product>,
+}
+
+impl Catalog {
+    fn new() -> Self {
+        let started = Arc::new(AtomicUsize::new(0));
+        let products = (1..=2)
+            .map(|id| Product {
+                id,
+                started: started.clone(),
+            })
+            .collect();
+        Self { products }
+    }
+}
+
+impl ValueView for Catalog {
+    fn as_debug(&self) -> &dyn fmt::Debug {
+        self
+    }
+
+    fn render(&self) -> DisplayCow<'_> {
+        Value::Nil.render()
+    }
+    fn source(&self) -> DisplayCow<'_> {
+        Value::Nil.source()
+    }
+    fn type_name(&self) -> &'static str {
+        "object"
+    }
+    fn query_state(&self, state: State) -> bool {
+        matches!(state, State::Truthy)
+    }
+
+    fn to_kstr(&self) -> KStringCow<'_> {
+        KStringCow::from_static("")
+    }
+    fn to_value(&self) -> Value {
+        Value::Object(Default::default())
+    }
+
+    fn as_object(&self) -> Option<&dyn ObjectView> {
+        Some(self)
+    }
+}
+
+impl ObjectView for Catalog {
+    fn as_value(&self) -> &dyn ValueView {
+        self
+    }
+
+    fn size(&self) -> i64 {
+        1
+    }
+
+    fn keys<'k>(&'k self) -> Box<dyn Iterator<Item = KStringCow<'k>> + 'k> {
+        Box::new(std::iter::once(KStringCow::from_static("products")))
+    }
+
+    fn values<'k>(&'k self) -> Box<dyn Iterator<Item = &'k dyn ValueView> + 'k> {
+        Box::new(std::iter::once(&self.products as &dyn ValueView))
+    }
+
+    fn iter<'k>(&'k self) -> Box<dyn Iterator<Item = (KStringCow<'k>, &'k dyn ValueView)> + 'k> {
+        Box::new(self.keys().zip(ObjectView::values(self)))
+    }
+
+    fn contains_key(&self, index: &str) -> bool {
+        index == "products"
+    }
+
+    fn get<'s>(&'s self, index: &str) -> Option<ValueCow<'s>> {
+        match index {
+            "products" => Some(ValueCow::Borrowed(&self.products)),
+            _ => None,
+        }
+    }
+
+    fn get_async<'s>(&'s self, index: &'s str) -> Option<BoxFuture<'s, Option<Value>>> {
+        let value = match index {
+            "shirt" => liquid::model::value!({ "title": "Shirt", "price": 10 }),
+            "sizes" => liquid::model::value!(["S", "M", "L"]),
+            _ => return None,
+        };
+        Some(Box::pin(async move {
+            YieldNow(false).await;
+            Some(value)
+        }))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "product_title",
+    description = "Looks up a product's title by id.",
+    parsed(ProductTitleFilter)
+)]
+struct ProductTitle;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "product_title"]
+struct ProductTitleFilter;
+
+impl Filter for ProductTitleFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        evaluate_async_filter(self, input, runtime)
+    }
+}
+
+impl AsyncFilter for ProductTitleFilter {
+    fn evaluate_async(
+        &self,
+        input: &dyn ValueView,
+        _runtime: &dyn Runtime,
+    ) -> Result<BoxFuture<'static, Result<Value>>> {
+        let id = input.to_kstr().into_owned();
+        Ok(Box::pin(async move {
+            YieldNow(false).await;
+            Ok(Value::scalar(format!("Product {}", id)))
+        }))
+    }
+}
+
+#[test]
+fn render_async_fetches_globals() {
+    let template = liquid::ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse("{{ shirt.title }}: {{ shirt.price }} ({{ sizes | join: '/' }}), {{ sizes.last }}")
+        .unwrap();
+
+    let output = block_on(template.render_async(&Catalog::new())).unwrap();
+    assert_eq!(output, "Shirt: 10 (S/M/L), L");
+
+    assert!(template.render(&Catalog::new()).is_err());
+}
+
+#[test]
+fn render_async_fetches_through_loop_variables() {
+    let template = liquid::ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse("{% for p in products %}{{ p.title }}, {% endfor %}{{ products.last.title }}")
+        .unwrap();
+
+    // Every title is started before any finishes.
+    let output = block_on(template.render_async(&Catalog::new())).unwrap();
+    assert_eq!(
+        output,
+        "Product 1 (2 started), Product 2 (2 started), Product 2 (2 started)"
+    );
+}
+
+#[test]
+fn render_async_awaits_filters() {
+    let template = liquid::ParserBuilder::with_stdlib()
+        .filter(ProductTitle)
+        .build()
+        .unwrap()
+        .parse(
+            "{% for id in ids %}{{ id | product_title | upcase }}, {% endfor %}\
+             {% assign last = ids.last | product_title %}{{ last }}",
+        )
+        .unwrap();
+    let globals = liquid::object!({
+        "ids": [1, 2],
+    });
+
+    let output = block_on(template.render_async(&globals)).unwrap();
+    assert_eq!(output, "PRODUCT 1, PRODUCT 2, Product 2");
+
+    let error = template.render(&globals).unwrap_err();
+    assert!(error.to_string().contains("asynchronous render"));
+}
+
+#[test]
+fn render_async_chains_filters() {
+    let template = liquid::ParserBuilder::with_stdlib()
+        .filter(ProductTitle)
+        .build()
+        .unwrap()
+        .parse("{{ 1 | product_title | product_title }}, {{ 2 | product_title }}")
+        .unwrap();
+
+    let output = block_on(template.render_async(&liquid::Object::new())).unwrap();
+    assert_eq!(output, "Product Product 1, Product 2");
+}
+
+#[test]
+fn render_async_is_send() {
+    fn assert_send<T: Send>(_: &T) {}
+
+    let template = liquid::ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse("{{ shirt.title }}")
+        .unwrap();
+    let catalog = Catalog::new();
+    let render = template.render_async(&catalog);
+    assert_send(&render);
+    assert_eq!(block_on(render).unwrap(), "Shirt");
+}
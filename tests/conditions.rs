@@ -0,0 +1,92 @@
+use liquid::ParserBuilder;
+
+fn render(text: &str, globals: &liquid::Object) -> String {
+    ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse(text)
+        .unwrap()
+        .render(globals)
+        .unwrap()
+}
+
+#[test]
+fn output_conditions() {
+    let globals = liquid::object!({ "price": 5, "compare_at": 8, "tags": ["new"] });
+    assert_eq!(render("{{ price < compare_at }}", &globals), "true");
+    assert_eq!(
+        render("{{ tags contains 'new' and price > 10 }}", &globals),
+        "false"
+    );
+}
+
+#[test]
+fn output_inline_conditionals() {
+    let globals = liquid::object!({ "price": 5, "compare_at": 8, "name": "ada" });
+    assert_eq!(
+        render(
+            "{{ 'sale' | upcase if price < compare_at else 'regular' }}",
+            &globals
+        ),
+        "SALE"
+    );
+    assert_eq!(
+        render(
+            "{{ 'a' if price > 10 else 'b' if name else 'c' }}",
+            &globals
+        ),
+        "b"
+    );
+    assert_eq!(render("[{{ 'a' if missing }}]", &globals), "[]");
+}
+
+#[test]
+fn filter_argument_conditions() {
+    let globals = liquid::object!({ "price": 5, "compare_at": 8 });
+    assert_eq!(
+        render("{{ 'sale: ' | append: (price < compare_at) }}", &globals),
+        "sale: true"
+    );
+    assert_eq!(
+        render(
+            "{{ nil | default: (price > 10 or compare_at == 8) }}",
+            &globals
+        ),
+        "true"
+    );
+
+    let parser = ParserBuilder::with_stdlib().build().unwrap();
+    assert!(parser.parse("{{ 'a' | append: (price <) }}").is_err());
+    assert!(parser.parse("{{ 'a' | append: (price 5) }}").is_err());
+}
+
+#[test]
+fn output_conditions_are_escaped() {
+    let template = ParserBuilder::with_stdlib()
+        .auto_escape()
+        .build()
+        .unwrap()
+        .parse("{{ x if x else 'none' }}")
+        .unwrap();
+    let output = template.render(&liquid::object!({ "x": "<b>" })).unwrap();
+    assert_eq!(output, "&lt;b&gt;");
+}
+
+#[test]
+fn invalid_conditions_are_errors() {
+    let parser = ParserBuilder::with_stdlib().build().unwrap();
+    assert!(parser.parse("{{ a b }}").is_err());
+    assert!(parser.parse("{{ a | upcase == b }}").is_err());
+    assert!(parser.parse("{% assign x = a == %}").is_err());
+}
+
+#[test]
+fn condition_variables() {
+    let template = ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse("{% assign x = a.b < c %}{{ d if e == f else g }}")
+        .unwrap();
+    let usage = template.variable_usage();
+    assert_eq!(usage.roots(), ["a", "c", "d", "e", "f", "g"]);
+}
@@ -3,3 +3,56 @@
 fn test_global_register_order() {
     panic!("Implementation specific: conflicting filters");
 }
+
+fn assigns() -> liquid::Object {
+    o!({
+      "hash": { "zebra": 1, "apple": 2, "mango": 3 }
+    })
+}
+
+#[test]
+#[cfg(not(feature = "preserve_order"))]
+fn test_hash_iterates_sorted_by_key() {
+    assert_template_result!(
+        "apple=2 mango=3 zebra=1 ",
+        "{% for pair in hash %}{{ pair[0] }}={{ pair[1] }} {% endfor %}",
+        assigns()
+    );
+}
+
+#[test]
+#[cfg(feature = "preserve_order")]
+fn test_hash_iterates_in_insertion_order() {
+    assert_template_result!(
+        "zebra=1 apple=2 mango=3 ",
+        "{% for pair in hash %}{{ pair[0] }}={{ pair[1] }} {% endfor %}",
+        assigns()
+    );
+}
+
+#[test]
+#[cfg(feature = "preserve_order")]
+fn test_removing_a_key_keeps_insertion_order() {
+    let mut hash = o!({ "zebra": 1, "apple": 2, "mango": 3 });
+    hash.remove("zebra");
+    hash.insert("banana".into(), v!(4));
+    let keys: Vec<_> = hash.keys().map(|k| k.as_str()).collect();
+    assert_eq!(keys, ["apple", "mango", "banana"]);
+}
+
+#[test]
+#[cfg(feature = "preserve_order")]
+fn test_serde_keeps_insertion_order() {
+    let json = r#"{"zebra":1,"apple":{"y":true,"b":false},"mango":3}"#;
+    let hash: liquid::Object = serde_json::from_str(json).unwrap();
+    assert_eq!(serde_json::to_string(&hash).unwrap(), json);
+
+    #[derive(serde::Serialize)]
+    struct Config {
+        zebra: i32,
+        apple: i32,
+    }
+    let object = liquid::to_object(&Config { zebra: 1, apple: 2 }).unwrap();
+    let keys: Vec<_> = object.keys().map(|k| k.as_str()).collect();
+    assert_eq!(keys, ["zebra", "apple"]);
+}
@@ -1,191 +1,299 @@
+use liquid_core::runtime::{Expression, Variable, Visitor};
+use liquid_core::ValueView;
+
+#[derive(Default)]
+struct VariableNames(Vec<String>);
+
+impl Visitor for VariableNames {
+    fn visit_variable(&mut self, variable: &Variable) {
+        self.0.push(variable.name().to_kstr().into_string());
+        variable.walk(self);
+    }
+}
+
+/// Renders the tree as `tag(children)`, with variables as leaves.
+#[derive(Default)]
+struct Tree(String);
+
+impl Visitor for Tree {
+    fn enter_tag(&mut self, name: &str) {
+        self.0.push_str(name);
+        self.0.push('(');
+    }
+
+    fn leave_tag(&mut self, _name: &str) {
+        if self.0.ends_with(' ') {
+            self.0.pop();
+        }
+        self.0.push_str(") ");
+    }
+
+    fn visit_variable(&mut self, variable: &Variable) {
+        self.0.push_str(&variable.name().to_kstr());
+        self.0.push(' ');
+        variable.walk(self);
+    }
+
+    fn visit_partial(&mut self, name: &Expression) {
+        self.0.push_str("partial:");
+        self.visit_expression(name);
+    }
+}
+
+fn parse(template: &str) -> liquid::Template {
+    liquid::ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse(template)
+        .unwrap()
+}
+
+fn traversal(template: &str) -> Vec<String> {
+    let mut visitor = VariableNames::default();
+    parse(template).visit(&mut visitor);
+    visitor.0
+}
+
 #[test]
-#[should_panic]
 fn test_variable() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(vec!["test"], traversal("{{ test }}"));
 }
 
 #[test]
-#[should_panic]
 fn test_variable_with_filter() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(
+        vec!["test", "infilter"],
+        traversal("{{ test | split: infilter }}")
+    );
 }
 
 #[test]
-#[should_panic]
 fn test_dynamic_variable() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(vec!["test", "inlookup"], traversal("{{ test[inlookup] }}"));
 }
 
 #[test]
-#[should_panic]
 fn test_if_condition() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(vec!["test"], traversal("{% if test %}{% endif %}"));
 }
 
 #[test]
-#[should_panic]
 fn test_complex_if_condition() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(
+        vec!["test"],
+        traversal("{% if 1 == 1 and 2 == test %}{% endif %}")
+    );
 }
 
 #[test]
-#[should_panic]
 fn test_if_body() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(
+        vec!["test"],
+        traversal("{% if 1 == 1 %}{{ test }}{% endif %}")
+    );
 }
 
 #[test]
-#[should_panic]
 fn test_unless_condition() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(vec!["test"], traversal("{% unless test %}{% endunless %}"));
 }
 
 #[test]
-#[should_panic]
 fn test_complex_unless_condition() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(
+        vec!["test"],
+        traversal("{% unless 1 == 1 and 2 == test %}{% endunless %}")
+    );
 }
 
 #[test]
-#[should_panic]
 fn test_unless_body() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(
+        vec!["test"],
+        traversal("{% unless 1 == 1 %}{{ test }}{% endunless %}")
+    );
 }
 
 #[test]
-#[should_panic]
 fn test_elsif_condition() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(
+        vec!["test"],
+        traversal("{% if 1 == 1 %}{% elsif test %}{% endif %}")
+    );
 }
 
 #[test]
-#[should_panic]
 fn test_complex_elsif_condition() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(
+        vec!["test"],
+        traversal("{% if 1 == 1 %}{% elsif 1 == 1 and 2 == test %}{% endif %}")
+    );
 }
 
 #[test]
-#[should_panic]
 fn test_elsif_body() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(
+        vec!["test"],
+        traversal("{% if 1 == 1 %}{% elsif 2 == 2 %}{{ test }}{% endif %}")
+    );
 }
 
 #[test]
-#[should_panic]
 fn test_else_body() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(
+        vec!["test"],
+        traversal("{% if 1 == 1 %}{% else %}{{ test }}{% endif %}")
+    );
 }
 
 #[test]
-#[should_panic]
 fn test_case_left() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(vec!["test"], traversal("{% case test %}{% endcase %}"));
 }
 
 #[test]
-#[should_panic]
 fn test_case_condition() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(
+        vec!["test"],
+        traversal("{% case 1 %}{% when test %}{% endcase %}")
+    );
 }
 
 #[test]
-#[should_panic]
 fn test_case_when_body() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(
+        vec!["test"],
+        traversal("{% case 1 %}{% when 2 %}{{ test }}{% endcase %}")
+    );
 }
 
 #[test]
-#[should_panic]
 fn test_case_else_body() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(
+        vec!["test"],
+        traversal("{% case 1 %}{% else %}{{ test }}{% endcase %}")
+    );
 }
 
 #[test]
-#[should_panic]
 fn test_for_in() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(vec!["test"], traversal("{% for x in test %}{% endfor %}"));
 }
 
 #[test]
-#[should_panic]
 fn test_for_limit() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(
+        vec!["test"],
+        traversal("{% for x in (1..5) limit: test %}{% endfor %}")
+    );
 }
 
 #[test]
-#[should_panic]
 fn test_for_offset() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(
+        vec!["test"],
+        traversal("{% for x in (1..5) offset: test %}{% endfor %}")
+    );
 }
 
 #[test]
-#[should_panic]
 fn test_for_body() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(
+        vec!["test"],
+        traversal("{% for x in (1..5) %}{{ test }}{% endfor %}")
+    );
 }
 
 #[test]
-#[should_panic]
 fn test_tablerow_in() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(
+        vec!["test"],
+        traversal("{% tablerow x in test %}{% endtablerow %}")
+    );
 }
 
 #[test]
-#[should_panic]
 fn test_tablerow_limit() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(
+        vec!["test"],
+        traversal("{% tablerow x in (1..5) limit: test %}{% endtablerow %}")
+    );
 }
 
 #[test]
-#[should_panic]
 fn test_tablerow_offset() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(
+        vec!["test"],
+        traversal("{% tablerow x in (1..5) offset: test %}{% endtablerow %}")
+    );
 }
 
 #[test]
-#[should_panic]
 fn test_tablerow_body() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(
+        vec!["test"],
+        traversal("{% tablerow x in (1..5) %}{{ test }}{% endtablerow %}")
+    );
 }
 
 #[test]
-#[should_panic]
 fn test_cycle() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(vec!["test"], traversal("{% cycle test %}"));
 }
 
 #[test]
-#[should_panic]
 fn test_assign() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(vec!["test"], traversal("{% assign x = test %}"));
 }
 
 #[test]
-#[should_panic]
 fn test_capture() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(
+        vec!["test"],
+        traversal("{% capture x %}{{ test }}{% endcapture %}")
+    );
 }
 
 #[test]
-#[should_panic]
 fn test_include() {
-    panic!("Implementation specific: parsing API");
+    assert_eq!(vec!["test"], traversal("{% include test %}"));
 }
 
 #[test]
 #[should_panic]
 fn test_include_with() {
-    panic!("Implementation specific: parsing API");
+    panic!("Implementation specific: `include` does not support `with`");
 }
 
 #[test]
 #[should_panic]
 fn test_include_for() {
-    panic!("Implementation specific: parsing API");
+    panic!("Implementation specific: `include` does not support `for`");
+}
+
+#[test]
+fn test_render_with() {
+    assert_eq!(
+        vec!["test"],
+        traversal(r#"{% render "hai" with test as x %}"#)
+    );
+}
+
+#[test]
+fn test_render_for() {
+    assert_eq!(
+        vec!["test", "other"],
+        traversal(r#"{% render "hai" for test as x, y: other %}"#)
+    );
 }
 
 #[test]
-#[should_panic]
 fn test_preserve_tree_structure() {
-    panic!("Implementation specific: parsing API");
+    let mut visitor = Tree::default();
+    parse("{% for x in xs offset: test %}{{ other }}{% if x %}{% else %}{% include other %}{% endif %}{% endfor %}")
+        .visit(&mut visitor);
+    assert_eq!(
+        "for(xs test other if(x else(include(partial:other))))",
+        visitor.0.trim_end()
+    );
 }
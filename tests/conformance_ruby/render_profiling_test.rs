@@ -1,83 +1,186 @@
+use std::borrow;
+
+#[derive(Default, Debug, Clone, Copy)]
+struct ProfilingFileSystem;
+
+impl liquid::partials::PartialSource for ProfilingFileSystem {
+    fn contains(&self, _name: &str) -> bool {
+        true
+    }
+
+    fn names(&self) -> Vec<&str> {
+        vec![]
+    }
+
+    fn try_get<'a>(&'a self, name: &str) -> Option<borrow::Cow<'a, str>> {
+        let template = format!(
+            "Rendering template {{% assign template_name = '{name}'%}}\n{{{{ template_name }}}}"
+        );
+        Some(template.into())
+    }
+}
+
+fn profile(template: &str, globals: &liquid::Object) -> liquid::Profile {
+    let (_, profile) = liquid::ParserBuilder::with_stdlib()
+        .partials(liquid::partials::OnDemandCompiler::<ProfilingFileSystem>::empty())
+        .build()
+        .unwrap()
+        .parse(template)
+        .unwrap()
+        .render_profiled(globals)
+        .unwrap();
+    profile
+}
+
 #[test]
 #[should_panic]
 fn test_template_allows_flagging_profiling() {
-    panic!("Implementation specific: profiling API");
+    panic!("Implementation specific: profiling is requested per render, not per template");
 }
 
 #[test]
-#[should_panic]
 fn test_parse_makes_available_simple_profiling() {
-    panic!("Implementation specific: profiling API");
+    let profile = profile("{{ 'a string' | upcase }}", &o!({}));
+    assert_eq!(1, profile.children().len());
+
+    let node = &profile.children()[0];
+    assert_eq!("'a string' | upcase", node.code());
 }
 
 #[test]
-#[should_panic]
 fn test_render_ignores_raw_strings_when_profiling() {
-    panic!("Implementation specific: profiling API");
+    let profile = profile("This is raw string\nstuff\nNewline", &o!({}));
+    assert_eq!(0, profile.children().len());
 }
 
 #[test]
-#[should_panic]
 fn test_profiling_includes_line_numbers_of_liquid_nodes() {
-    panic!("Implementation specific: profiling API");
+    let profile = profile("{{ 'a string' | upcase }}\n{% increment test %}", &o!({}));
+    assert_eq!(2, profile.children().len());
+    // {{ 'a string' | upcase }}
+    assert_eq!(1, profile.children()[0].line());
+    // {{ increment test }}
+    assert_eq!(2, profile.children()[1].line());
 }
 
 #[test]
-#[should_panic]
 fn test_profiling_includes_line_numbers_of_included_partials() {
-    panic!("Implementation specific: profiling API");
+    let profile = profile("{% include 'a_template' %}", &o!({}));
+    let included_children = profile.children()[0].children();
+    // {% assign template_name = 'a_template' %}
+    assert_eq!(1, included_children[0].line());
+    // {{ template_name }}
+    assert_eq!(2, included_children[1].line());
 }
 
 #[test]
-#[should_panic]
 fn test_profiling_times_the_rendering_of_tokens() {
-    panic!("Implementation specific: profiling API");
+    let profile = profile("{% include 'a_template' %}", &o!({}));
+    let node = &profile.children()[0];
+    assert_eq!(1, node.calls());
+    assert!(node.total_time() >= node.self_time());
 }
 
 #[test]
-#[should_panic]
 fn test_profiling_times_the_entire_render() {
-    panic!("Implementation specific: profiling API");
+    let profile = profile("{% include 'a_template' %}", &o!({}));
+    assert!(profile.total_time() >= profile.children()[0].total_time());
 }
 
 #[test]
-#[should_panic]
 fn test_profiling_uses_include_to_mark_children() {
-    panic!("Implementation specific: profiling API");
+    let profile = profile(
+        "{{ 'a string' | upcase }}\n{% include 'a_template' %}",
+        &o!({}),
+    );
+    let include_node = &profile.children()[1];
+    assert_eq!(2, include_node.children().len());
 }
 
 #[test]
-#[should_panic]
 fn test_profiling_marks_children_with_the_name_of_included_partial() {
-    panic!("Implementation specific: profiling API");
+    let profile = profile(
+        "{{ 'a string' | upcase }}\n{% include 'a_template' %}",
+        &o!({}),
+    );
+    let include_node = &profile.children()[1];
+    for child in include_node.children() {
+        assert_eq!(Some("a_template"), child.partial());
+    }
 }
 
 #[test]
-#[should_panic]
 fn test_profiling_supports_multiple_templates() {
-    panic!("Implementation specific: profiling API");
+    let profile = profile(
+        "{{ 'a string' | upcase }}\n{% include 'a_template' %}\n{% include 'b_template' %}",
+        &o!({}),
+    );
+
+    let a_template = &profile.children()[1];
+    for child in a_template.children() {
+        assert_eq!(Some("a_template"), child.partial());
+    }
+
+    let b_template = &profile.children()[2];
+    for child in b_template.children() {
+        assert_eq!(Some("b_template"), child.partial());
+    }
 }
 
 #[test]
-#[should_panic]
 fn test_profiling_supports_rendering_the_same_partial_multiple_times() {
-    panic!("Implementation specific: profiling API");
+    let profile = profile(
+        "{{ 'a string' | upcase }}\n{% include 'a_template' %}\n{% include 'a_template' %}",
+        &o!({}),
+    );
+
+    let a_template1 = &profile.children()[1];
+    for child in a_template1.children() {
+        assert_eq!(Some("a_template"), child.partial());
+    }
+
+    let a_template2 = &profile.children()[2];
+    for child in a_template2.children() {
+        assert_eq!(Some("a_template"), child.partial());
+    }
 }
 
 #[test]
-#[should_panic]
 fn test_can_iterate_over_each_profiling_entry() {
-    panic!("Implementation specific: profiling API");
+    let profile = profile("{{ 'a string' | upcase }}\n{% increment test %}", &o!({}));
+    assert_eq!(2, profile.children().iter().count());
 }
 
 #[test]
-#[should_panic]
 fn test_profiling_marks_children_of_if_blocks() {
-    panic!("Implementation specific: profiling API");
+    let profile = profile(
+        "{% if true %} {% increment test %} {{ test }} {% endif %}",
+        &o!({}),
+    );
+    assert_eq!(1, profile.children().len());
+    assert_eq!(2, profile.children()[0].children().len());
 }
 
 #[test]
-#[should_panic]
 fn test_profiling_marks_children_of_for_blocks() {
-    panic!("Implementation specific: profiling API");
+    let profile = profile(
+        "{% for item in collection %} {{ item }} {% endfor %}",
+        &o!({"collection": ["one", "two"]}),
+    );
+    assert_eq!(1, profile.children().len());
+    // Each invocation of the loop body is aggregated into one entry
+    let children = profile.children()[0].children();
+    assert_eq!(1, children.len());
+    assert_eq!(2, children[0].calls());
+}
+
+#[test]
+fn test_profiling_marks_children_of_render_tags() {
+    let profile = profile("{% render 'a_template' %}", &o!({}));
+    let render_node = &profile.children()[0];
+    assert_eq!("render 'a_template'", render_node.code());
+    assert_eq!(2, render_node.children().len());
+    for child in render_node.children() {
+        assert_eq!(Some("a_template"), child.partial());
+    }
 }
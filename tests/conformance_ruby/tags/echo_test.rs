@@ -0,0 +1,17 @@
+#[test]
+fn test_echo_outputs_its_input() {
+    assert_template_result!(
+        "BAR",
+        "{%- echo variable-name | upcase -%}\n",
+        o!({"variable-name": "bar"}),
+    );
+}
+
+#[test]
+fn test_echo_can_be_used_in_liquid_tag() {
+    assert_template_result!(
+        "BAR",
+        "{%- liquid echo variable-name | upcase -%}\n",
+        o!({"variable-name": "bar"}),
+    );
+}
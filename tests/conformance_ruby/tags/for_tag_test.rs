@@ -246,7 +246,6 @@ fn test_offset_only() {
 }
 
 #[test]
-#[should_panic] // liquid-rust#274
 fn test_pause_resume() {
     let assigns = o!({ "array": { "items": [1, 2, 3, 4, 5, 6, 7, 8, 9, 0] } });
     let markup = r#"
@@ -267,7 +266,6 @@ fn test_pause_resume() {
 }
 
 #[test]
-#[should_panic] // liquid-rust#274
 fn test_pause_resume_limit() {
     let assigns = o!({ "array": { "items": [1, 2, 3, 4, 5, 6, 7, 8, 9, 0] } });
     let markup = r#"
@@ -288,7 +286,6 @@ fn test_pause_resume_limit() {
 }
 
 #[test]
-#[should_panic] // liquid-rust#274
 fn test_pause_resume_big_limit() {
     let assigns = o!({ "array": { "items": [1, 2, 3, 4, 5, 6, 7, 8, 9, 0] } });
     let markup = r#"
@@ -309,7 +306,6 @@ fn test_pause_resume_big_limit() {
 }
 
 #[test]
-#[should_panic] // liquid-rust#274
 fn test_pause_resume_big_offset() {
     let assigns = o!({ "array": { "items": [1, 2, 3, 4, 5, 6, 7, 8, 9, 0] } });
     let markup = "{%for i in array.items limit:3 %}{{i}}{%endfor%}
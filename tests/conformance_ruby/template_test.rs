@@ -1,3 +1,15 @@
+use liquid::{ErrorKind, Limits};
+
+fn render_limited(template: &str, limits: Limits) -> Result<String, liquid::Error> {
+    liquid::ParserBuilder::with_stdlib()
+        .limits(limits)
+        .build()
+        .unwrap()
+        .parse(template)
+        .unwrap()
+        .render(&o!({}))
+}
+
 #[test]
 #[should_panic]
 fn test_instance_assigns_persist_on_same_template_object_between_parses() {
@@ -53,21 +65,31 @@ fn test_resource_limits_works_with_custom_length_method() {
 }
 
 #[test]
-#[should_panic]
 fn test_resource_limits_render_length() {
-    panic!("Implementation specific: render API");
+    let err = render_limited("0123456789", Limits::new().set_max_output_bytes(9)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ResourceLimit);
+    assert_eq!(
+        render_limited("0123456789", Limits::new().set_max_output_bytes(10)).unwrap(),
+        "0123456789"
+    );
 }
 
 #[test]
-#[should_panic]
 fn test_resource_limits_render_score() {
-    panic!("Implementation specific: render API");
+    let template = "{% for a in (1..10) %} {% for a in (1..10) %} foo {% endfor %} {% endfor %}";
+    let err = render_limited(template, Limits::new().set_max_iterations(50)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ResourceLimit);
+    render_limited(template, Limits::new().set_max_iterations(200)).unwrap();
 }
 
 #[test]
-#[should_panic]
 fn test_resource_limits_assign_score() {
-    panic!("Implementation specific: render API");
+    let template = "{% assign foo = 'hello' %}{% capture bar %}{{ foo }} world{% endcapture %}";
+    let err = render_limited(template, Limits::new().set_max_assign_size(4)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ResourceLimit);
+    let err = render_limited(template, Limits::new().set_max_assign_size(5)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ResourceLimit);
+    render_limited(template, Limits::new().set_max_assign_size(11)).unwrap();
 }
 
 #[test]
@@ -77,9 +99,26 @@ fn test_resource_limits_assign_score_nested() {
 }
 
 #[test]
-#[should_panic]
 fn test_resource_limits_aborts_rendering_after_first_error() {
-    panic!("Implementation specific: render API");
+    let template =
+        "{% for a in (1..100) %} foo1 {% endfor %} bar {% for a in (1..100) %} foo2 {% endfor %}";
+    let err = render_limited(template, Limits::new().set_max_iterations(50)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ResourceLimit);
+}
+
+#[test]
+fn test_resource_limits_huge_range_is_not_materialized() {
+    let template = "{% for a in (1..1000000000) %}{% endfor %}";
+    let err = render_limited(template, Limits::new().set_max_iterations(50)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ResourceLimit);
+    let template = "{% for a in (1..1000000000) limit: 2 offset: 5 reversed %}{{ a }}{% endfor %}";
+    assert_eq!(
+        render_limited(template, Limits::new().set_max_iterations(50)).unwrap(),
+        "76"
+    );
+    let template = "{% for a in (-9223372036854775808..9223372036854775807) %}{% endfor %}";
+    let err = render_limited(template, Limits::new().set_max_iterations(50)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ResourceLimit);
 }
 
 #[test]
@@ -89,9 +128,38 @@ fn test_resource_limits_hash_in_template_gets_updated_even_if_no_limits_are_set(
 }
 
 #[test]
-#[should_panic]
 fn test_render_length_persists_between_blocks() {
-    panic!("Implementation specific: render API");
+    let template = "{% if true %}aaaa{% endif %}";
+    assert!(render_limited(template, Limits::new().set_max_output_bytes(3)).is_err());
+    render_limited(template, Limits::new().set_max_output_bytes(4)).unwrap();
+
+    let template = "{% if true %}aaaa{% endif %}{% if true %}aaaaa{% endif %}";
+    assert!(render_limited(template, Limits::new().set_max_output_bytes(8)).is_err());
+    render_limited(template, Limits::new().set_max_output_bytes(9)).unwrap();
+}
+
+#[test]
+fn test_resource_limits_render_length_counts_captures() {
+    let template =
+        "{% capture big %}{% for a in (1..1000) %}aaaaaaaaaa{% endfor %}{% endcapture %}";
+    let err = render_limited(template, Limits::new().set_max_output_bytes(100)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ResourceLimit);
+    render_limited(template, Limits::new().set_max_output_bytes(10_000)).unwrap();
+}
+
+#[test]
+fn test_resource_limits_partial_depth() {
+    let mut partials = liquid::partials::InMemorySource::new();
+    partials.add("loop", "{% include 'loop' %}");
+    let template = liquid::ParserBuilder::with_stdlib()
+        .partials(liquid::partials::LazyCompiler::new(partials))
+        .limits(Limits::new().set_max_depth(10))
+        .build()
+        .unwrap()
+        .parse("{% include 'loop' %}")
+        .unwrap();
+    let err = template.render(&o!({})).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ResourceLimit);
 }
 
 #[test]
@@ -23,3 +23,55 @@ liquid:  --> 1:3
         ),
     }
 }
+
+#[test]
+fn test_render_error_location() {
+    let template = liquid::ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse("Hello\n  {{ x | divided_by: 0 }}")
+        .unwrap();
+    let err = template.render(&liquid::object!({"x": 1})).unwrap_err();
+
+    let location = err.location().unwrap();
+    assert_eq!(location.name(), None);
+    assert_eq!(location.line(), 2);
+    assert_eq!(location.column(), 3);
+    assert_data_eq!(
+        err.to_string(),
+        str![[r#"
+liquid: Invalid argument
+  with:
+    argument=operand
+    cause=Can't divide by zero
+  --> 2:3
+from: Filter error
+  with:
+    filter=divided_by : 0
+    input=1
+
+"#]]
+    );
+}
+
+#[test]
+fn test_render_error_location_in_partial() {
+    let mut partials = liquid::partials::InMemorySource::new();
+    partials.add(
+        "header",
+        "{% if true %}\n{{ x | divided_by: 0 }}{% endif %}",
+    );
+    let template = liquid::ParserBuilder::with_stdlib()
+        .partials(liquid::partials::EagerCompiler::new(partials))
+        .build()
+        .unwrap()
+        .parse("{% include 'header' %}")
+        .unwrap();
+    let err = template.render(&liquid::object!({"x": 1})).unwrap_err();
+
+    let location = err.location().unwrap();
+    assert_eq!(location.name(), Some("header"));
+    assert_eq!(location.line(), 2);
+    assert_eq!(location.column(), 1);
+    assert_eq!(location.to_string(), "header:2:1");
+}
@@ -539,3 +539,60 @@ fn test_compact() {
     let output = template.render(&globals).unwrap();
     assert_eq!(output, "A C".to_string());
 }
+
+#[test]
+pub fn auto_escape() {
+    let text = "{{ text }} {{ text | escape }} {{ text | safe }} {{ text | raw }} {% echo text %} \
+                {% raw %}<i>{% endraw %} {{ lines | newline_to_br }}";
+    let globals = liquid::object!({
+        "text": "<b>Tom & Jerry</b>",
+        "lines": "<i>\nx",
+    });
+    let template = liquid::ParserBuilder::with_stdlib()
+        .auto_escape()
+        .build()
+        .unwrap()
+        .parse(text)
+        .unwrap();
+    let output = template.render(&globals).unwrap();
+    assert_eq!(
+        output,
+        "&lt;b&gt;Tom &amp; Jerry&lt;/b&gt; &lt;b&gt;Tom &amp; Jerry&lt;/b&gt; <b>Tom & Jerry</b> \
+         <b>Tom & Jerry</b> &lt;b&gt;Tom &amp; Jerry&lt;/b&gt; <i> &lt;i&gt;<br />\nx"
+            .to_string()
+    );
+}
+
+#[test]
+pub fn auto_escape_captured_partial() {
+    let text = "{% capture card %}{% include 'card' %}{% endcapture %}{{ card }}";
+    let globals = liquid::object!({
+        "name": "<script>",
+    });
+    let mut partials = liquid::partials::InMemorySource::new();
+    partials.add("card", "<p>{{ name }}</p>");
+    let template = liquid::ParserBuilder::with_stdlib()
+        .auto_escape()
+        .partials(liquid::partials::EagerCompiler::new(partials))
+        .build()
+        .unwrap()
+        .parse(text)
+        .unwrap();
+    let output = template.render(&globals).unwrap();
+    assert_eq!(output, "<p>&lt;script&gt;</p>".to_string());
+}
+
+#[test]
+pub fn escape_without_auto_escape() {
+    let text = "{{ text }} {{ text | escape | escape }} {{ text | safe | escape }}";
+    let globals = liquid::object!({
+        "text": "<b>",
+    });
+    let template = liquid::ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse(text)
+        .unwrap();
+    let output = template.render(&globals).unwrap();
+    assert_eq!(output, "<b> &amp;lt;b&amp;gt; &lt;b&gt;".to_string());
+}
@@ -0,0 +1,114 @@
+use liquid::ParserBuilder;
+
+fn format(text: &str) -> String {
+    ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .format(text)
+        .unwrap()
+}
+
+#[test]
+fn indents_nested_blocks() {
+    let text = "\
+{%-for item in items  limit:2-%}
+{%-if item.ok%}
+<b>{{item.name|upcase}}</b>
+{%-elsif item.name==\"x\"-%}
+{%-  case item.kind %}
+{%- when 'a','b' %}
+ab
+{%-  endcase -%}
+{%- else -%}
+{%assign n=item|size-%}
+{%-endif%}
+{%-endfor%}";
+    let expected = "\
+{%- for item in items limit: 2 -%}
+  {%- if item.ok %}
+<b>{{ item.name | upcase }}</b>
+  {%- elsif item.name == \"x\" -%}
+    {%- case item.kind %}
+    {%- when 'a', 'b' %}
+ab
+    {%- endcase -%}
+  {%- else -%}
+    {% assign n = item | size -%}
+  {%- endif %}
+{%- endfor %}";
+    assert_eq!(format(text), expected);
+    assert_eq!(format(expected), expected);
+
+    let parser = ParserBuilder::with_stdlib().build().unwrap();
+    let globals = liquid::object!({
+        "items": [{"ok": true, "name": "a"}, {"name": "x", "kind": "b"}],
+    });
+    assert_eq!(
+        parser.parse(text).unwrap().render(&globals).unwrap(),
+        parser.parse(expected).unwrap().render(&globals).unwrap()
+    );
+}
+
+#[test]
+fn keeps_untrimmed_indentation() {
+    let text = "{%if a%}\n{{a}}\n    {%endif%}\n   {%-if b%}{%endif%}";
+    assert_eq!(
+        format(text),
+        "{% if a %}\n{{ a }}\n    {% endif %}\n{%- if b %}{% endif %}"
+    );
+}
+
+#[test]
+fn keeps_whitespace_control() {
+    let text = "<ul>\n    {%- for i in (1..n) -%}\n<li>{{-i-}}</li>\n{%-endfor%}\n</ul>";
+    let formatted = format(text);
+    assert_eq!(
+        formatted,
+        "<ul>\n{%- for i in (1..n) -%}\n<li>{{- i -}}</li>\n{%- endfor %}\n</ul>"
+    );
+
+    let parser = ParserBuilder::with_stdlib().build().unwrap();
+    let globals = liquid::object!({ "n": 2 });
+    assert_eq!(
+        parser.parse(text).unwrap().render(&globals).unwrap(),
+        parser.parse(&formatted).unwrap().render(&globals).unwrap()
+    );
+}
+
+#[test]
+fn keeps_raw_and_comment_bodies() {
+    let text = "\
+{%if a%}
+{%raw%}
+   {{ keep|this }}{%  if %}
+{%endraw%}
+{%comment%}
+ {%comment%} {{x}} {%endcomment%}
+    {%if%}
+{%endcomment%}
+{%endif%}";
+    let expected = "\
+{% if a %}
+{% raw %}
+   {{ keep|this }}{%  if %}
+{% endraw %}
+{% comment %}
+ {%comment%} {{x}} {%endcomment%}
+    {%if%}
+{% endcomment %}
+{% endif %}";
+    assert_eq!(format(text), expected);
+}
+
+#[test]
+fn keeps_liquid_tags() {
+    let text = "{%- liquid\n  assign x = 1\n  echo x\n-%}";
+    assert_eq!(format(text), text);
+}
+
+#[test]
+fn invalid_templates_are_errors() {
+    let parser = ParserBuilder::with_stdlib().build().unwrap();
+    assert!(parser.format("{% if %}").is_err());
+    assert!(parser.format("{% for x in y %}").is_err());
+}
@@ -0,0 +1,45 @@
+#![cfg(all(feature = "extra", feature = "stdlib"))]
+
+use liquid::partials::{EagerCompiler, InMemorySource};
+use liquid::ParserBuilder;
+
+fn parser() -> liquid::Parser {
+    let mut source = InMemorySource::new();
+    source.add(
+        "base",
+        "<title>{% block title %}Site{% endblock %}</title>{% block content %}{% endblock %}",
+    );
+    source.add("page", "<main>{{ content }}</main>");
+    ParserBuilder::with_stdlib()
+        .inheritance()
+        .partials(EagerCompiler::new(source))
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn extends_overrides_blocks() {
+    let template = parser()
+        .parse("{% extends 'base' %}{% block title %}{{ super }} | Home{% endblock %}")
+        .unwrap();
+    let output = template.render(&liquid::object!({})).unwrap();
+    assert_eq!(output, "<title>Site | Home</title>");
+}
+
+#[test]
+fn layout_wraps_content() {
+    let template = parser()
+        .parse("{% layout 'page' %}Hello {{ name }}")
+        .unwrap();
+    let output = template
+        .render(&liquid::object!({ "name": "World" }))
+        .unwrap();
+    assert_eq!(output, "<main>Hello World</main>");
+}
+
+#[test]
+fn extends_must_come_first() {
+    assert!(parser()
+        .parse("{% if true %}{% extends 'base' %}{% endif %}")
+        .is_err());
+}
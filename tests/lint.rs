@@ -0,0 +1,104 @@
+use liquid::lint::{Finding, Rule};
+use liquid::partials::{EagerCompiler, InMemorySource};
+use liquid::ParserBuilder;
+
+fn lint(text: &str) -> Vec<Finding> {
+    let mut source = InMemorySource::new();
+    source.add("card", "{{ product.title }}");
+    source.add("header", "{% assign title = 'Home' %}");
+    source.add("footer", "{{ year }}");
+    ParserBuilder::with_stdlib()
+        .partials(EagerCompiler::new(source))
+        .build()
+        .unwrap()
+        .parse(text)
+        .unwrap()
+        .lint()
+}
+
+fn rules(text: &str) -> Vec<Rule> {
+    lint(text).iter().map(|f| f.rule()).collect()
+}
+
+#[test]
+fn unused_assigns() {
+    let findings = lint("{% assign a = 1 %}\n{% capture b %}x{% endcapture %}{{ a }}");
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].rule(), Rule::UnusedAssign);
+    assert!(findings[0].message().contains("`b`"));
+    assert_eq!(findings[0].span().unwrap().line(), 2);
+
+    // Included partials share the caller's variables.
+    let mut source = InMemorySource::new();
+    source.add("uses", "{{ year }}");
+    let template = ParserBuilder::with_stdlib()
+        .partials(EagerCompiler::new(source))
+        .build()
+        .unwrap()
+        .parse("{% assign year = 2024 %}{% include 'uses' %}")
+        .unwrap();
+    assert!(!template
+        .lint()
+        .iter()
+        .any(|f| f.rule() == Rule::UnusedAssign));
+}
+
+#[test]
+fn loop_variables_shadowing() {
+    assert_eq!(
+        rules("{% for product in products %}{{ product }}{% endfor %}{{ product }}"),
+        [Rule::ShadowedVariable]
+    );
+    assert_eq!(
+        rules("{% for i in (1..3) %}{% for i in (1..3) %}{{ i }}{% endfor %}{% endfor %}"),
+        [Rule::ShadowedVariable]
+    );
+    assert!(rules("{% for item in items %}{{ item }}{% endfor %}").is_empty());
+}
+
+#[test]
+fn unknown_partials() {
+    let findings = lint("{% render 'card' %}{% render 'missing' %}");
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].rule(), Rule::UnknownPartial);
+    assert_eq!(findings[0].span().unwrap().start(), 19);
+}
+
+#[test]
+fn misused_empty() {
+    assert!(rules("{% if list == empty %}{% endif %}{% if s != blank %}{% endif %}").is_empty());
+    assert!(rules("{% case list %}{% when empty %}{% endcase %}").is_empty());
+    assert_eq!(
+        rules("{% if list contains empty %}{% endif %}{% if blank %}{% endif %}"),
+        [Rule::MisusedEmpty, Rule::MisusedEmpty]
+    );
+}
+
+#[test]
+fn unless_else() {
+    assert_eq!(
+        rules("{% unless a %}x{% else %}y{% endunless %}"),
+        [Rule::UnlessElse]
+    );
+    assert!(rules("{% unless a %}x{% endunless %}{% if a %}{% else %}{% endif %}").is_empty());
+}
+
+#[test]
+fn deprecated_include() {
+    assert_eq!(rules("{% include 'footer' %}"), [Rule::DeprecatedInclude]);
+    assert!(rules("{% include 'header' %}{{ title }}").is_empty());
+}
+
+#[test]
+fn findings_as_json() {
+    let findings = lint("{% unless a %}{% else %}{% endunless %}");
+    let json = serde_json::to_value(&findings).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!([{
+            "rule": "unless-else",
+            "message": "`unless` with an `else` branch reads better as `if`",
+            "span": { "start": 0, "end": 14, "line": 1, "column": 1 },
+        }])
+    );
+}
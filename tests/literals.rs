@@ -0,0 +1,67 @@
+use liquid::ParserBuilder;
+
+fn render(text: &str, globals: &liquid::Object) -> String {
+    ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse(text)
+        .unwrap()
+        .render(globals)
+        .unwrap()
+}
+
+#[test]
+fn array_literals() {
+    let globals = liquid::object!({ "x": "c" });
+    assert_eq!(
+        render("{% assign a = [1, 'b', x] %}{{ a | join: '-' }}", &globals),
+        "1-b-c"
+    );
+    assert_eq!(
+        render("{% for i in [x, [2, 3]] %}<{{ i }}>{% endfor %}", &globals),
+        "<c><[2, 3]>"
+    );
+    assert_eq!(
+        render("{{ 'a,b' | split: ',' | concat: [x] | join }}", &globals),
+        "a b c"
+    );
+    assert_eq!(render("{{ [] | size }}", &globals), "0");
+}
+
+#[test]
+fn hash_literals() {
+    let globals = liquid::object!({ "x": 2 });
+    assert_eq!(
+        render(
+            r#"{% assign h = { "a": 1, 'b': [x] } %}{{ h.a }}{{ h["b"][0] }}"#,
+            &globals
+        ),
+        "12"
+    );
+    assert_eq!(render("{{ {} | size }}", &globals), "0");
+}
+
+#[test]
+fn render_with_literal() {
+    let mut partials = liquid::partials::InMemorySource::new();
+    partials.add("item", "{{ item.name }}:{{ item.tags | join: ',' }}");
+    let template = ParserBuilder::with_stdlib()
+        .partials(liquid::partials::EagerCompiler::new(partials))
+        .build()
+        .unwrap()
+        .parse(r#"{% render 'item' with { "name": n, "tags": ["a", "b"] } as item %}"#)
+        .unwrap();
+    let output = template.render(&liquid::object!({ "n": "x" })).unwrap();
+    assert_eq!(output, "x:a,b");
+}
+
+#[test]
+fn literal_entries_are_variables() {
+    let template = ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse("{{ [a, {'k': b.c}] }}")
+        .unwrap();
+    let usage = template.variable_usage();
+    assert_eq!(usage.roots(), ["a", "b"]);
+}
@@ -1,5 +1,7 @@
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
+use std::time::{Duration, Instant};
 
 use snapbox::assert_data_eq;
 
@@ -36,3 +38,43 @@ pub fn pass_between_threads() {
     // Wait for threads to finish
     handles.into_iter().map(|h| h.join()).last();
 }
+
+#[test]
+pub fn cancel_from_another_thread() {
+    let template = liquid::ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse(
+            "{% for i in (1..100000) %}{% for j in (1..100000) %}{{ j }}{% endfor %}{% endfor %}",
+        )
+        .unwrap();
+
+    let flag = Arc::new(AtomicBool::new(false));
+    let cancellation = liquid::Cancellation::new().set_flag(flag.clone());
+    let canceller = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(10));
+        flag.store(true, Ordering::Relaxed);
+    });
+
+    let err = template
+        .render_cancellable(&liquid::object!({}), &cancellation)
+        .unwrap_err();
+    assert_eq!(err.kind(), liquid::ErrorKind::Cancelled);
+    canceller.join().unwrap();
+}
+
+#[test]
+pub fn cancel_after_deadline() {
+    let template = liquid::ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse("{% for i in (1..100000) %}{% for j in (1..100000) %}{{ j | plus: 1 }}{% endfor %}{% endfor %}")
+        .unwrap();
+
+    let cancellation =
+        liquid::Cancellation::new().set_deadline(Instant::now() + Duration::from_millis(10));
+    let err = template
+        .render_cancellable(&liquid::object!({}), &cancellation)
+        .unwrap_err();
+    assert_eq!(err.kind(), liquid::ErrorKind::Cancelled);
+}
@@ -0,0 +1,109 @@
+use liquid::{ErrorKind, ParserBuilder, RenderOptions, Strictness, WarningKind};
+
+#[test]
+fn undefined_variables() {
+    let template = ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse("[{{ missing }}][{{ page.missing }}]")
+        .unwrap();
+    let globals = liquid::object!({ "page": {} });
+
+    let options = RenderOptions::new();
+    assert!(template.render_with_options(&globals, &options).is_err());
+
+    let options = RenderOptions::new().set_undefined_variables(Strictness::Empty);
+    let (output, warnings) = template.render_with_options(&globals, &options).unwrap();
+    assert_eq!(output, "[][]");
+    assert!(warnings.is_empty());
+
+    let options = RenderOptions::new().set_undefined_variables(Strictness::Warn);
+    let (output, warnings) = template.render_with_options(&globals, &options).unwrap();
+    assert_eq!(output, "[][]");
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(warnings[0].kind(), WarningKind::UndefinedVariable);
+    assert!(warnings[0].to_string().contains("Unknown variable"));
+    assert!(warnings[1].to_string().contains("Unknown index"));
+}
+
+#[test]
+fn out_of_range() {
+    let template = ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse("[{{ list[5] }}]")
+        .unwrap();
+    let globals = liquid::object!({ "list": [1, 2] });
+
+    let options = RenderOptions::new().set_undefined_variables(Strictness::Empty);
+    assert!(template.render_with_options(&globals, &options).is_err());
+
+    let options = RenderOptions::new().set_out_of_range(Strictness::Warn);
+    let (output, warnings) = template.render_with_options(&globals, &options).unwrap();
+    assert_eq!(output, "[]");
+    assert_eq!(warnings.len(), 1);
+}
+
+#[test]
+fn undefined_filters() {
+    let parser = ParserBuilder::with_stdlib().build().unwrap();
+    assert!(parser.parse("{{ 'a' | shout }}").is_err());
+
+    let template = ParserBuilder::with_stdlib()
+        .defer_unknown_filters()
+        .build()
+        .unwrap()
+        .parse("{{ 'a' | shout }}")
+        .unwrap();
+    let globals = liquid::Object::new();
+    assert!(template.render(&globals).is_err());
+    let options = RenderOptions::new().set_undefined_filters(Strictness::Warn);
+    let (output, warnings) = template.render_with_options(&globals, &options).unwrap();
+    assert_eq!(output, "a");
+    assert_eq!(warnings.len(), 1);
+
+    let template = ParserBuilder::with_stdlib()
+        .in_lax_mode()
+        .build()
+        .unwrap()
+        .parse("{{ 'a' | shout | upcase }}")
+        .unwrap();
+    let globals = liquid::Object::new();
+    assert_eq!(template.render(&globals).unwrap(), "A");
+
+    let options = RenderOptions::new().set_undefined_filters(Strictness::Error);
+    let err = template
+        .render_with_options(&globals, &options)
+        .unwrap_err();
+    assert!(err.to_string().contains("shout"), "{}", err);
+
+    let options = RenderOptions::new().set_undefined_filters(Strictness::Warn);
+    let (output, warnings) = template.render_with_options(&globals, &options).unwrap();
+    assert_eq!(output, "A");
+    assert_eq!(warnings.len(), 1);
+}
+
+#[test]
+fn filter_type_mismatches() {
+    let template = ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse("[{{ 'abc' | abs }}][{{ 2 | plus: 1 }}]")
+        .unwrap();
+    let globals = liquid::Object::new();
+
+    let err = template.render(&globals).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    let options = RenderOptions::new().set_filter_type_mismatches(Strictness::Empty);
+    let (output, warnings) = template.render_with_options(&globals, &options).unwrap();
+    assert_eq!(output, "[][3]");
+    assert!(warnings.is_empty());
+
+    let options = RenderOptions::new().set_all(Strictness::Warn);
+    let (output, warnings) = template.render_with_options(&globals, &options).unwrap();
+    assert_eq!(output, "[][3]");
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].kind(), WarningKind::FilterTypeMismatch);
+    assert_eq!(warnings[0].error().kind(), ErrorKind::InvalidInput);
+}
@@ -0,0 +1,181 @@
+use std::io::Write;
+
+use liquid::partials::{EagerCompiler, InMemorySource};
+use liquid::ParserBuilder;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::{Display_filter, Filter, FilterReflection, ParseFilter};
+use liquid_core::{Language, ParseTag, Renderable, TagReflection, TagTokenIter};
+use liquid_core::{Value, ValueView};
+
+fn round_trip(parser: &liquid::Parser, template: &liquid::Template) -> liquid::Template {
+    let saved = serde_json::to_string(template).unwrap();
+    let mut deserializer = serde_json::Deserializer::from_str(&saved);
+    parser.load_template(&mut deserializer).unwrap()
+}
+
+#[test]
+fn serialize_stdlib_template() {
+    let parser = ParserBuilder::with_stdlib().build().unwrap();
+    let text = r#"{% assign total = 0 -%}
+{% for item in items limit: 2 %}{% if item.price > 1.5 and item.name != blank %}{{ item.name | upcase }}{% else %}{{ item.name | default: "none" }}{% endif %}{% increment count %}{% capture x %}{{ forloop.index }}{% endcapture %}{{ x }},{% endfor -%}
+{% case total %}{% when 0, nil %}zero{% else %}more{% endcase %}{% raw %}{{ raw }}{% endraw %}{% comment %}hidden{% endcomment %}{% for i in (1..3) reversed %}{{ i }}{% endfor %}{{ "a" | append: 'b' }}{{ empty }}{{ 2.50 | times: 2 }}"#;
+    let template = parser.parse(text).unwrap();
+    let globals = liquid::object!({
+        "items": [
+            { "name": "cat", "price": 2.0 },
+            { "name": "dog", "price": 1.0 },
+            { "name": "eel", "price": 3.0 },
+        ],
+    });
+    let expected = template.render(&globals).unwrap();
+
+    let loaded = round_trip(&parser, &template);
+    assert_eq!(loaded.render(&globals).unwrap(), expected);
+    assert_eq!(
+        serde_json::to_string(&loaded).unwrap(),
+        serde_json::to_string(&template).unwrap()
+    );
+}
+
+#[test]
+fn serialize_partials() {
+    let mut source = InMemorySource::new();
+    source.add("header", "Hello {{ name }}!");
+    let parser = ParserBuilder::with_stdlib()
+        .partials(EagerCompiler::new(source))
+        .build()
+        .unwrap();
+    let template = parser
+        .parse(r#"{% render "header", name: "Liquid" %}"#)
+        .unwrap();
+    let saved = serde_json::to_string(&template).unwrap();
+
+    let empty = ParserBuilder::with_stdlib().build().unwrap();
+    let mut deserializer = serde_json::Deserializer::from_str(&saved);
+    let loaded = empty.load_template(&mut deserializer).unwrap();
+    assert_eq!(
+        loaded.render(&liquid::Object::new()).unwrap(),
+        "Hello Liquid!"
+    );
+}
+
+#[test]
+fn serialize_only_rendered_partials() {
+    let mut source = InMemorySource::new();
+    source.add("page", r#"{% include "header" %}"#);
+    source.add("header", "Hello {{ name }}!");
+    source.add("footer", "Bye {{ name }}!");
+    let parser = ParserBuilder::with_stdlib()
+        .partials(EagerCompiler::new(source))
+        .build()
+        .unwrap();
+    let template = parser
+        .parse(r#"{% render "page", name: "Liquid" %}"#)
+        .unwrap();
+    let saved = serde_json::to_string(&template).unwrap();
+    assert!(saved.contains("header"));
+    assert!(!saved.contains("footer"));
+
+    let empty = ParserBuilder::with_stdlib().build().unwrap();
+    let mut deserializer = serde_json::Deserializer::from_str(&saved);
+    let loaded = empty.load_template(&mut deserializer).unwrap();
+    assert_eq!(
+        loaded.render(&liquid::Object::new()).unwrap(),
+        "Hello Liquid!"
+    );
+}
+
+#[test]
+fn serialize_auto_escape_comes_from_parser() {
+    let parser = ParserBuilder::with_stdlib().build().unwrap();
+    let template = parser.parse("{{ html }}").unwrap();
+    let escaping = ParserBuilder::with_stdlib().auto_escape().build().unwrap();
+
+    let loaded = round_trip(&escaping, &template);
+    let globals = liquid::object!({ "html": "<b>" });
+    assert_eq!(loaded.render(&globals).unwrap(), "&lt;b&gt;");
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "shout",
+    description = "Upper-cases and adds a `!`.",
+    parsed(ShoutFilter)
+)]
+struct Shout;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "shout"]
+struct ShoutFilter;
+
+impl Filter for ShoutFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        Ok(Value::scalar(format!(
+            "{}!",
+            input.to_kstr().to_uppercase()
+        )))
+    }
+}
+
+#[test]
+fn serialize_custom_filter() {
+    let parser = ParserBuilder::with_stdlib().filter(Shout).build().unwrap();
+    let template = parser.parse("{{ 'hi' | shout }}").unwrap();
+    let saved = serde_json::to_string(&template).unwrap();
+
+    let loaded = round_trip(&parser, &template);
+    assert_eq!(loaded.render(&liquid::Object::new()).unwrap(), "HI!");
+
+    let missing = ParserBuilder::with_stdlib().build().unwrap();
+    let mut deserializer = serde_json::Deserializer::from_str(&saved);
+    let err = missing.load_template(&mut deserializer).unwrap_err();
+    assert!(err.to_string().contains("shout"), "{}", err);
+}
+
+#[derive(Copy, Clone)]
+struct PingTag;
+
+impl TagReflection for PingTag {
+    fn tag(&self) -> &'static str {
+        "ping"
+    }
+
+    fn description(&self) -> &'static str {
+        ""
+    }
+}
+
+impl ParseTag for PingTag {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        _options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        arguments.expect_nothing()?;
+        Ok(Box::new(Ping))
+    }
+
+    fn reflection(&self) -> &dyn TagReflection {
+        self
+    }
+}
+
+#[derive(Debug)]
+struct Ping;
+
+impl Renderable for Ping {
+    fn render_to(&self, writer: &mut dyn Write, _runtime: &dyn Runtime) -> Result<()> {
+        write!(writer, "pong").unwrap();
+        Ok(())
+    }
+}
+
+#[test]
+fn serialize_unsupported_tag() {
+    let parser = ParserBuilder::with_stdlib().tag(PingTag).build().unwrap();
+    let template = parser.parse("{% ping %}").unwrap();
+
+    let err = serde_json::to_string(&template).unwrap_err();
+    assert!(err.to_string().contains("`ping`"), "{}", err);
+}
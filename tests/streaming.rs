@@ -0,0 +1,65 @@
+use std::fmt;
+
+use liquid::*;
+
+fn parse(text: &str) -> Template {
+    ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse(text)
+        .unwrap()
+}
+
+#[test]
+fn render_to_fmt() {
+    struct Page<'t>(&'t Template);
+
+    impl<'t> fmt::Display for Page<'t> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let globals = object!({ "name": "Zoë" });
+            self.0.render_to_fmt(f, &globals).map_err(|_| fmt::Error)
+        }
+    }
+
+    let template = parse("<h1>{{ name | upcase }}</h1>");
+    assert_eq!(Page(&template).to_string(), "<h1>ZOË</h1>");
+
+    let mut output = String::from("<!DOCTYPE html>");
+    template
+        .render_to_fmt(&mut output, &object!({ "name": "Ann" }))
+        .unwrap();
+    assert_eq!(output, "<!DOCTYPE html><h1>ANN</h1>");
+}
+
+#[test]
+fn render_chunked() {
+    let template = parse("<ul>{% for i in (1..3) %}<li>{{ i }}</li>{% endfor %}</ul>{{ footer }}");
+    let mut chunks = Vec::new();
+    let mut writer = ChunkedWriter::new(|chunk: &str| {
+        chunks.push(chunk.to_owned());
+        Ok(())
+    });
+    template
+        .render_chunked(&mut writer, &object!({ "footer": "fin" }))
+        .unwrap();
+    drop(writer);
+    assert_eq!(
+        chunks,
+        ["<ul>", "<li>1</li><li>2</li><li>3</li>", "</ul>", "fin"]
+    );
+}
+
+#[test]
+fn render_chunked_stops_on_error() {
+    let template = parse("start{{ missing }}end");
+    let mut chunks = Vec::new();
+    let mut writer = ChunkedWriter::new(|chunk: &str| {
+        chunks.push(chunk.to_owned());
+        Ok(())
+    });
+    assert!(template
+        .render_chunked(&mut writer, &Object::new())
+        .is_err());
+    drop(writer);
+    assert_eq!(chunks, ["start"]);
+}
@@ -68,3 +68,24 @@ topic1-bar\
 ",
     );
 }
+
+#[test]
+pub fn string_escapes() {
+    let parser = ParserBuilder::with_stdlib()
+        .string_escapes()
+        .build()
+        .unwrap();
+    let template = parser
+        .parse(r#"{{ "one\ntwo" | split: "\n" | join: "\t" }} {{ 'it\'s "\u{2713}"' }}"#)
+        .unwrap();
+    let output = template.render(&Object::default()).unwrap();
+    assert_eq!(output, "one\ttwo it's \"\u{2713}\"");
+
+    let template = ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse(r#"{{ "one\ntwo" | split: "\n" | size }}"#)
+        .unwrap();
+    let output = template.render(&Object::default()).unwrap();
+    assert_eq!(output, "2");
+}
@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use liquid::*;
+
+#[test]
+fn cache_reuses_templates() {
+    let parser = ParserBuilder::with_stdlib().cache(10).build().unwrap();
+
+    let first = parser.parse_cached("{{ name }}").unwrap();
+    let clone = parser.clone();
+    let second = clone.parse_cached("{{ name }}").unwrap();
+    assert!(Arc::ptr_eq(&first, &second));
+    assert_eq!(
+        second.render(&object!({ "name": "Liquid" })).unwrap(),
+        "Liquid"
+    );
+
+    let other = parser.parse_cached("{{ name}}").unwrap();
+    assert!(!Arc::ptr_eq(&first, &other));
+
+    let stats = parser.cache_stats().unwrap();
+    assert_eq!(stats.hits, 1);
+    assert_eq!(stats.misses, 2);
+    assert_eq!(stats.len, 2);
+    assert_eq!(stats.capacity, 10);
+}
+
+#[test]
+fn cache_evicts_least_recently_used() {
+    let parser = ParserBuilder::with_stdlib().cache(2).build().unwrap();
+
+    let a = parser.parse_cached("a").unwrap();
+    let _b = parser.parse_cached("b").unwrap();
+    assert!(Arc::ptr_eq(&a, &parser.parse_cached("a").unwrap()));
+    let _c = parser.parse_cached("c").unwrap();
+
+    assert!(Arc::ptr_eq(&a, &parser.parse_cached("a").unwrap()));
+    assert_eq!(parser.cache_stats().unwrap().len, 2);
+    let misses = parser.cache_stats().unwrap().misses;
+    parser.parse_cached("b").unwrap();
+    assert_eq!(parser.cache_stats().unwrap().misses, misses + 1);
+
+    parser.clear_cache();
+    assert_eq!(parser.cache_stats().unwrap().len, 0);
+}
+
+#[test]
+fn cache_skips_errors() {
+    let parser = ParserBuilder::with_stdlib().cache(2).build().unwrap();
+
+    assert!(parser.parse_cached("{% if %}").is_err());
+    assert!(parser.parse_cached("{% if %}").is_err());
+    let stats = parser.cache_stats().unwrap();
+    assert_eq!(stats.misses, 2);
+    assert_eq!(stats.len, 0);
+}
+
+#[test]
+fn no_cache() {
+    let parser = ParserBuilder::with_stdlib().build().unwrap();
+
+    let first = parser.parse_cached("a").unwrap();
+    let second = parser.parse_cached("a").unwrap();
+    assert!(!Arc::ptr_eq(&first, &second));
+    assert_eq!(parser.cache_stats(), None);
+}
@@ -0,0 +1,95 @@
+use liquid::partials::{EagerCompiler, InMemorySource};
+use liquid::ParserBuilder;
+
+fn paths(template: &liquid::Template) -> Vec<String> {
+    template
+        .variable_usage()
+        .paths()
+        .map(|p| p.to_string())
+        .collect()
+}
+
+#[test]
+fn assigned_names_are_not_globals() {
+    let template = ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse(
+            "{% assign total = price | times: qty %}{% capture label %}{{ name }}{% endcapture %}{{ total }}{{ label }}",
+        )
+        .unwrap();
+
+    let usage = template.variable_usage();
+    assert_eq!(usage.roots(), ["name", "price", "qty"]);
+    assert!(!usage.contains_root("total"));
+}
+
+#[test]
+fn loops_trace_back_to_globals() {
+    let template = ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse(
+            "{% for product in collection.products %}{{ product.title }}{{ product.images[0].src }}{{ forloop.index }}{% endfor %}{% for i in (1..count) %}{{ list[i] }}{% endfor %}",
+        )
+        .unwrap();
+
+    assert_eq!(
+        paths(&template),
+        [
+            "collection.products",
+            "collection.products[].images[0].src",
+            "collection.products[].title",
+            "count",
+            "list[]",
+        ]
+    );
+}
+
+#[test]
+fn quoted_keys() {
+    let template = ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse("{{ page['my key'] }}")
+        .unwrap();
+
+    assert_eq!(paths(&template), [r#"page["my key"]"#]);
+}
+
+#[test]
+fn partials_are_followed() {
+    let mut source = InMemorySource::new();
+    source.add("card", "{{ item.name }}{{ site.title }}{{ footer }}");
+    source.add("shared", "{{ header }}{% assign seen = true %}");
+    let template = ParserBuilder::with_stdlib()
+        .partials(EagerCompiler::new(source))
+        .build()
+        .unwrap()
+        .parse(
+            "{% render 'card' for items as item, site: config %}{% include 'shared' %}{{ seen }}",
+        )
+        .unwrap();
+
+    assert_eq!(
+        paths(&template),
+        ["config", "config.title", "header", "items", "items[].name"]
+    );
+}
+
+#[test]
+fn recursive_partials_terminate() {
+    let mut source = InMemorySource::new();
+    source.add(
+        "tree",
+        "{{ node.name }}{% for node in node.children %}{% include 'tree' %}{% endfor %}",
+    );
+    let template = ParserBuilder::with_stdlib()
+        .partials(EagerCompiler::new(source))
+        .build()
+        .unwrap()
+        .parse("{% include 'tree' %}")
+        .unwrap();
+
+    assert_eq!(paths(&template), ["node.children", "node.name"]);
+}
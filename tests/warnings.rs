@@ -0,0 +1,95 @@
+use liquid::partials::{EagerCompiler, InMemorySource};
+use liquid::{ParserBuilder, WarningKind};
+
+#[test]
+fn warnings_have_locations() {
+    let template = ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse("{{ title }}\n{% if true %}\n  {{ items[3] | upcase }}{% endif %}")
+        .unwrap();
+    let globals = liquid::object!({ "items": [1] });
+
+    let (output, warnings) = template.render_with_warnings(&globals).unwrap();
+    assert_eq!(output, "\n\n  ");
+    assert_eq!(warnings.len(), 2);
+
+    assert_eq!(warnings[0].kind(), WarningKind::UndefinedVariable);
+    let location = warnings[0].location().unwrap();
+    assert_eq!((location.line(), location.column()), (1, 1));
+    assert!(warnings[0].to_string().contains("title"));
+
+    assert_eq!(warnings[1].kind(), WarningKind::OutOfRange);
+    let location = warnings[1].location().unwrap();
+    assert_eq!((location.line(), location.column()), (3, 3));
+}
+
+#[test]
+fn warnings_in_partials() {
+    let mut source = InMemorySource::new();
+    source.add("card", "\n{{ missing }}");
+    let template = ParserBuilder::with_stdlib()
+        .partials(EagerCompiler::new(source))
+        .build()
+        .unwrap()
+        .parse("{% render 'card' %}")
+        .unwrap();
+
+    let (_, warnings) = template
+        .render_with_warnings(&liquid::Object::new())
+        .unwrap();
+    assert_eq!(warnings.len(), 1);
+    let location = warnings[0].location().unwrap();
+    assert_eq!(location.name(), Some("card"));
+    assert_eq!(location.line(), 2);
+}
+
+#[test]
+fn warnings_for_counters() {
+    let template = ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse("{% increment count %}{% increment count %}{% decrement other %}")
+        .unwrap();
+
+    let (output, warnings) = template
+        .render_with_warnings(&liquid::Object::new())
+        .unwrap();
+    assert_eq!(output, "01-1");
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(warnings[0].kind(), WarningKind::UnknownCounter);
+    assert!(warnings[0].to_string().contains("count"));
+    assert!(warnings[1].to_string().contains("other"));
+}
+
+#[test]
+fn warnings_for_mismatched_cycles() {
+    let template = ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse("{% cycle 'g': 1, 2, 3 %}{% cycle 'g': 1, 2, 3 %}{% cycle 'g': 1, 2 %}{% cycle 'g': 1, 2 %}")
+        .unwrap();
+    let globals = liquid::Object::new();
+
+    assert!(template.render(&globals).is_err());
+    assert!(template.render_lax(&globals).is_err());
+
+    let (output, warnings) = template.render_with_warnings(&globals).unwrap();
+    assert_eq!(output, "1212");
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].kind(), WarningKind::CycleMismatch);
+}
+
+#[test]
+fn no_warnings() {
+    let template = ParserBuilder::with_stdlib()
+        .build()
+        .unwrap()
+        .parse("{{ name | upcase }}")
+        .unwrap();
+
+    let globals = liquid::object!({ "name": "liquid" });
+    let (output, warnings) = template.render_with_warnings(&globals).unwrap();
+    assert_eq!(output, "LIQUID");
+    assert!(warnings.is_empty());
+}